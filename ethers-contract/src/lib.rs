@@ -27,6 +27,11 @@ pub use log::{decode_logs, EthLogDecode, LogMeta};
 
 pub mod stream;
 
+mod verifier;
+pub use verifier::{
+    SignatureVerifier, SignatureVerifierError, EIP1271_MAGIC_VALUE, EIP6492_MAGIC_SUFFIX,
+};
+
 #[cfg(any(test, feature = "abigen"))]
 #[cfg_attr(docsrs, doc(cfg(feature = "abigen")))]
 mod multicall;
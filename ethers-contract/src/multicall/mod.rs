@@ -1,9 +1,7 @@
 use crate::call::{ContractCall, ContractError};
 use ethers_core::{
     abi::{Detokenize, Function, Token, Tokenizable},
-    types::{
-        transaction::eip2718::TypedTransaction, Address, BlockNumber, Bytes, NameOrAddress, U256,
-    },
+    types::{Address, BlockNumber, Bytes, NameOrAddress, U256},
 };
 use ethers_providers::{Middleware, PendingTransaction};
 use std::{convert::TryFrom, fmt, result::Result as StdResult, sync::Arc};
@@ -382,11 +380,8 @@ impl<M: Middleware> Multicall<M> {
         call: ContractCall<M, D>,
         allow_failure: bool,
     ) -> &mut Self {
-        let (to, data, value) = match call.tx {
-            TypedTransaction::Legacy(tx) => (tx.to, tx.data, tx.value),
-            TypedTransaction::Eip2930(tx) => (tx.tx.to, tx.tx.data, tx.tx.value),
-            TypedTransaction::Eip1559(tx) => (tx.to, tx.data, tx.value),
-        };
+        let (to, data, value) =
+            (call.tx.to().cloned(), call.tx.data().cloned(), call.tx.value().cloned());
         if data.is_none() && !call.function.outputs.is_empty() {
             return self
         }
@@ -0,0 +1,430 @@
+//! Signature verification for both externally owned accounts and contract wallets.
+
+use ethers_core::{
+    abi::{self, ParamType, Token},
+    types::{
+        transaction::eip712::Eip712, Address, Bytes, Signature, TransactionRequest, H160, H256,
+    },
+    utils::{hash_message, id},
+};
+use ethers_providers::{Middleware, MiddlewareError};
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+/// The value returned by `isValidSignature(bytes32,bytes)` of an [EIP-1271] contract for a valid
+/// signature.
+///
+/// [EIP-1271]: https://eips.ethereum.org/EIPS/eip-1271
+pub const EIP1271_MAGIC_VALUE: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
+
+/// The 32 byte suffix marking a signature as wrapped according to [EIP-6492].
+///
+/// [EIP-6492]: https://eips.ethereum.org/EIPS/eip-6492
+pub const EIP6492_MAGIC_SUFFIX: [u8; 32] = [
+    0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92,
+    0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92,
+];
+
+/// The Multicall3 deployment used to simulate the deployment of counterfactual wallets:
+/// [`0xcA11bde05977b3631167028862bE2a173976CA11`](https://etherscan.io/address/0xcA11bde05977b3631167028862bE2a173976CA11)
+const MULTICALL3_ADDRESS: H160 = H160([
+    0xca, 0x11, 0xbd, 0xe0, 0x59, 0x77, 0xb3, 0x63, 0x11, 0x67, 0x02, 0x88, 0x62, 0xbe, 0x2a, 0x17,
+    0x39, 0x76, 0xca, 0x11,
+]);
+
+/// Error thrown when verifying a signature.
+#[derive(Debug, ThisError)]
+pub enum SignatureVerifierError<M: Middleware> {
+    /// Thrown when the internal middleware errors
+    #[error("{0}")]
+    MiddlewareError(M::Error),
+
+    /// Thrown when an EIP-6492 wrapper or a call result could not be ABI decoded
+    #[error(transparent)]
+    AbiError(#[from] abi::Error),
+
+    /// Thrown when the EIP-712 hash of a payload could not be computed
+    #[error("failed to encode EIP-712 payload: {0}")]
+    Eip712(String),
+}
+
+impl<M: Middleware> MiddlewareError for SignatureVerifierError<M> {
+    type Inner = M::Error;
+
+    fn from_err(src: M::Error) -> Self {
+        SignatureVerifierError::MiddlewareError(src)
+    }
+
+    fn as_inner(&self) -> Option<&Self::Inner> {
+        match self {
+            SignatureVerifierError::MiddlewareError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Verifies signatures of externally owned accounts as well as of smart contract wallets.
+///
+/// Plain ECDSA signatures are checked locally first. If the signature does not recover to the
+/// signer and the signer is a contract, its [EIP-1271] `isValidSignature(bytes32,bytes)` method
+/// is queried instead. Signatures wrapped according to [EIP-6492] are accepted for wallets which
+/// have not been deployed yet: the factory call is simulated together with the
+/// `isValidSignature` call in a single `eth_call` through [Multicall3].
+///
+/// A reverting `isValidSignature` call is treated as an invalid signature.
+///
+/// [EIP-1271]: https://eips.ethereum.org/EIPS/eip-1271
+/// [EIP-6492]: https://eips.ethereum.org/EIPS/eip-6492
+/// [Multicall3]: https://github.com/mds1/multicall
+///
+/// # Example
+///
+/// ```no_run
+/// use ethers_contract::SignatureVerifier;
+/// use ethers_core::types::{Address, Bytes};
+/// use ethers_providers::{Http, Provider};
+/// use std::convert::TryFrom;
+///
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// let provider = Provider::<Http>::try_from("http://localhost:8545")?;
+/// let verifier = SignatureVerifier::<Provider<Http>>::new(provider);
+///
+/// let signer: Address = "0x0000000000000000000000000000000000000001".parse()?;
+/// let signature: Bytes = "0x1234".parse()?;
+/// let valid = verifier.verify_message(signer, "Sign in to example.com", &signature).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SignatureVerifier<M> {
+    client: Arc<M>,
+    multicall: Address,
+}
+
+impl<M> Clone for SignatureVerifier<M> {
+    fn clone(&self) -> Self {
+        Self { client: self.client.clone(), multicall: self.multicall }
+    }
+}
+
+impl<M: Middleware> SignatureVerifier<M> {
+    /// Creates a new verifier using the canonical Multicall3 deployment for EIP-6492 signatures.
+    pub fn new(client: impl Into<Arc<M>>) -> Self {
+        Self { client: client.into(), multicall: MULTICALL3_ADDRESS }
+    }
+
+    /// Sets the address of the Multicall3 contract used to verify EIP-6492 signatures.
+    #[must_use]
+    pub fn multicall(mut self, address: Address) -> Self {
+        self.multicall = address;
+        self
+    }
+
+    /// Returns a reference to the underlying client.
+    pub fn client(&self) -> &M {
+        &self.client
+    }
+
+    /// Returns `true` if `signature` is a valid signature of `signer` over the
+    /// [EIP-191](https://eips.ethereum.org/EIPS/eip-191) personal message `message`.
+    pub async fn verify_message<S>(
+        &self,
+        signer: Address,
+        message: S,
+        signature: &[u8],
+    ) -> Result<bool, SignatureVerifierError<M>>
+    where
+        S: AsRef<[u8]>,
+    {
+        self.verify_hash(signer, hash_message(message), signature).await
+    }
+
+    /// Returns `true` if `signature` is a valid signature of `signer` over the EIP-712 `payload`.
+    pub async fn verify_typed_data<T>(
+        &self,
+        signer: Address,
+        payload: &T,
+        signature: &[u8],
+    ) -> Result<bool, SignatureVerifierError<M>>
+    where
+        T: Eip712,
+    {
+        let hash = payload
+            .encode_eip712()
+            .map_err(|err| SignatureVerifierError::Eip712(err.to_string()))?;
+        self.verify_hash(signer, hash.into(), signature).await
+    }
+
+    /// Returns `true` if `signature` is a valid signature of `signer` over the prehashed `hash`.
+    pub async fn verify_hash(
+        &self,
+        signer: Address,
+        hash: H256,
+        signature: &[u8],
+    ) -> Result<bool, SignatureVerifierError<M>> {
+        if let Some(wrapped) = signature.strip_suffix(&EIP6492_MAGIC_SUFFIX[..]) {
+            return self.verify_eip6492(signer, hash, wrapped).await
+        }
+
+        if recover_ecdsa(hash, signature) == Some(signer) {
+            return Ok(true)
+        }
+
+        if self.get_code(signer).await?.is_empty() {
+            return Ok(false)
+        }
+
+        self.verify_eip1271(signer, hash, signature).await
+    }
+
+    async fn verify_eip1271(
+        &self,
+        signer: Address,
+        hash: H256,
+        signature: &[u8],
+    ) -> Result<bool, SignatureVerifierError<M>> {
+        let tx =
+            TransactionRequest::new().to(signer).data(is_valid_signature_call(hash, signature));
+        match self.client.call(&tx.into(), None).await {
+            Ok(output) => Ok(is_magic_value(&output)),
+            Err(err) if err.as_error_response().is_some() => Ok(false),
+            Err(err) => Err(SignatureVerifierError::MiddlewareError(err)),
+        }
+    }
+
+    async fn verify_eip6492(
+        &self,
+        signer: Address,
+        hash: H256,
+        wrapped: &[u8],
+    ) -> Result<bool, SignatureVerifierError<M>> {
+        let mut tokens =
+            abi::decode(&[ParamType::Address, ParamType::Bytes, ParamType::Bytes], wrapped)?
+                .into_iter();
+        let (
+            Some(Token::Address(factory)),
+            Some(Token::Bytes(factory_calldata)),
+            Some(Token::Bytes(signature)),
+        ) = (tokens.next(), tokens.next(), tokens.next())
+        else {
+            unreachable!("decoded tokens match the requested param types")
+        };
+
+        // an already deployed wallet is verified directly, ignoring the deployment data
+        if !self.get_code(signer).await?.is_empty() {
+            return self.verify_eip1271(signer, hash, &signature).await
+        }
+
+        // aggregate3((address target, bool allowFailure, bytes callData)[])
+        let calls = Token::Array(vec![
+            Token::Tuple(vec![
+                Token::Address(factory),
+                Token::Bool(true),
+                Token::Bytes(factory_calldata),
+            ]),
+            Token::Tuple(vec![
+                Token::Address(signer),
+                Token::Bool(true),
+                Token::Bytes(is_valid_signature_call(hash, &signature).to_vec()),
+            ]),
+        ]);
+        let mut data = id("aggregate3((address,bool,bytes)[])").to_vec();
+        data.extend(abi::encode(&[calls]));
+
+        let tx = TransactionRequest::new().to(self.multicall).data(data);
+        let output = match self.client.call(&tx.into(), None).await {
+            Ok(output) => output,
+            Err(err) if err.as_error_response().is_some() => return Ok(false),
+            Err(err) => return Err(SignatureVerifierError::MiddlewareError(err)),
+        };
+
+        // returns (bool success, bytes returnData)[]
+        let results = abi::decode(
+            &[ParamType::Array(Box::new(ParamType::Tuple(vec![
+                ParamType::Bool,
+                ParamType::Bytes,
+            ])))],
+            &output,
+        )?;
+        let valid = match results.into_iter().next() {
+            Some(Token::Array(results)) => match results.get(1) {
+                Some(Token::Tuple(result)) => matches!(
+                    result.as_slice(),
+                    [Token::Bool(true), Token::Bytes(output)] if is_magic_value(output)
+                ),
+                _ => false,
+            },
+            _ => false,
+        };
+        Ok(valid)
+    }
+
+    async fn get_code(&self, address: Address) -> Result<Bytes, SignatureVerifierError<M>> {
+        self.client.get_code(address, None).await.map_err(SignatureVerifierError::MiddlewareError)
+    }
+}
+
+/// Recovers the signer of a 65 byte ECDSA signature, if it is well-formed.
+fn recover_ecdsa(hash: H256, signature: &[u8]) -> Option<Address> {
+    if signature.len() != 65 || !matches!(signature[64], 0 | 1 | 27 | 28) {
+        return None
+    }
+    Signature::try_from(signature).ok()?.recover(hash).ok()
+}
+
+/// ABI encodes a call to `isValidSignature(bytes32,bytes)`.
+fn is_valid_signature_call(hash: H256, signature: &[u8]) -> Bytes {
+    let mut data = EIP1271_MAGIC_VALUE.to_vec();
+    data.extend(abi::encode(&[
+        Token::FixedBytes(hash.as_bytes().to_vec()),
+        Token::Bytes(signature.to_vec()),
+    ]));
+    data.into()
+}
+
+/// Returns `true` if `output` is the ABI encoded `bytes4` EIP-1271 magic value.
+fn is_magic_value(output: &[u8]) -> bool {
+    output.len() == 32 && output[..4] == EIP1271_MAGIC_VALUE
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use ethers_core::types::transaction::eip712::TypedData;
+    use ethers_providers::{JsonRpcError, MockProvider, Provider};
+    use ethers_signers::{LocalWallet, Signer};
+    use serde_json::{json, Value};
+
+    fn wallet() -> LocalWallet {
+        "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01166992b11".parse().unwrap()
+    }
+
+    fn magic_output() -> Bytes {
+        let mut output = [0u8; 32];
+        output[..4].copy_from_slice(&EIP1271_MAGIC_VALUE);
+        output.to_vec().into()
+    }
+
+    fn call_data(params: &Value) -> Bytes {
+        let tx = &params[0];
+        tx.get("input")
+            .or_else(|| tx.get("data"))
+            .cloned()
+            .map(serde_json::from_value)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn verifies_eoa_signatures_locally() {
+        let (provider, mock) = Provider::mocked();
+        mock.on::<Bytes, _>("eth_getCode", Bytes::new()).unwrap();
+        let verifier = SignatureVerifier::<Provider<MockProvider>>::new(provider);
+        let wallet = wallet();
+
+        let signature = wallet.sign_message("hello").await.unwrap().to_vec();
+        assert!(verifier.verify_message(wallet.address(), "hello", &signature).await.unwrap());
+        assert_eq!(mock.calls("eth_getCode"), 0);
+
+        // a signature by someone else is rejected once the signer turns out to be an EOA
+        assert!(!verifier.verify_message(Address::random(), "hello", &signature).await.unwrap());
+        assert!(!verifier.verify_message(wallet.address(), "bye", &signature).await.unwrap());
+        assert_eq!(mock.calls("eth_getCode"), 2);
+    }
+
+    #[tokio::test]
+    async fn verifies_eip1271_signatures() {
+        let (provider, mock) = Provider::mocked();
+        let verifier = SignatureVerifier::<Provider<MockProvider>>::new(provider);
+        let wallet_address = Address::random();
+        let typed_data: TypedData = serde_json::from_value(json!({
+            "types": {
+                "EIP712Domain": [{ "name": "name", "type": "string" }],
+                "Login": [{ "name": "nonce", "type": "uint256" }]
+            },
+            "primaryType": "Login",
+            "domain": { "name": "example" },
+            "message": { "nonce": 1 }
+        }))
+        .unwrap();
+        let hash = H256::from(typed_data.encode_eip712().unwrap());
+
+        mock.on::<Bytes, _>("eth_getCode", Bytes::from(vec![0x60, 0x00])).unwrap();
+        mock.on_call("eth_call", move |params: &Value| {
+            let to: Address = serde_json::from_value(params[0]["to"].clone()).unwrap();
+            assert_eq!(to, wallet_address);
+            let data = call_data(params);
+            assert_eq!(data, is_valid_signature_call(hash, &[0xaa; 3]));
+            Ok(magic_output())
+        });
+        assert!(verifier.verify_typed_data(wallet_address, &typed_data, &[0xaa; 3]).await.unwrap());
+
+        mock.on::<Bytes, _>("eth_call", Bytes::from(vec![0u8; 32])).unwrap();
+        assert!(!verifier.verify_hash(wallet_address, hash, &[0xaa; 3]).await.unwrap());
+
+        mock.on_error(
+            "eth_call",
+            JsonRpcError { code: 3, message: "execution reverted".to_string(), data: None },
+        );
+        assert!(!verifier.verify_hash(wallet_address, hash, &[0xaa; 3]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn verifies_eip6492_signatures_of_undeployed_wallets() {
+        let (provider, mock) = Provider::mocked();
+        let verifier = SignatureVerifier::<Provider<MockProvider>>::new(provider);
+        let wallet_address = Address::random();
+        let factory = Address::random();
+        let hash = hash_message("hello");
+        let inner = vec![0xbb; 65];
+
+        let mut signature = abi::encode(&[
+            Token::Address(factory),
+            Token::Bytes(vec![0xde, 0xad]),
+            Token::Bytes(inner.clone()),
+        ]);
+        signature.extend(EIP6492_MAGIC_SUFFIX);
+
+        mock.on::<Bytes, _>("eth_getCode", Bytes::new()).unwrap();
+        mock.on_call("eth_call", move |params: &Value| {
+            let to: Address = serde_json::from_value(params[0]["to"].clone()).unwrap();
+            assert_eq!(to, MULTICALL3_ADDRESS);
+            let data = call_data(params);
+            assert_eq!(data[..4], id("aggregate3((address,bool,bytes)[])"));
+            let calls = abi::decode(
+                &[ParamType::Array(Box::new(ParamType::Tuple(vec![
+                    ParamType::Address,
+                    ParamType::Bool,
+                    ParamType::Bytes,
+                ])))],
+                &data[4..],
+            )
+            .unwrap();
+            assert_eq!(
+                calls[0],
+                Token::Array(vec![
+                    Token::Tuple(vec![
+                        Token::Address(factory),
+                        Token::Bool(true),
+                        Token::Bytes(vec![0xde, 0xad]),
+                    ]),
+                    Token::Tuple(vec![
+                        Token::Address(wallet_address),
+                        Token::Bool(true),
+                        Token::Bytes(is_valid_signature_call(hash, &inner).to_vec()),
+                    ]),
+                ])
+            );
+
+            let output = abi::encode(&[Token::Array(vec![
+                Token::Tuple(vec![Token::Bool(true), Token::Bytes(vec![])]),
+                Token::Tuple(vec![Token::Bool(true), Token::Bytes(magic_output().to_vec())]),
+            ])]);
+            Ok(Bytes::from(output))
+        });
+
+        assert!(verifier.verify_hash(wallet_address, hash, &signature).await.unwrap());
+        assert_eq!(mock.calls("eth_call"), 1);
+    }
+}
@@ -7,6 +7,8 @@ pub mod types;
 
 pub mod abi;
 
+pub mod trie;
+
 /// Various utilities
 pub mod utils;
 
@@ -0,0 +1,86 @@
+//! Merkle-Patricia trie utilities.
+//!
+//! Allows cross-checking data returned by an untrusted node: computing the transactions, receipts
+//! and withdrawals roots committed to in a block header, and verifying
+//! [EIP-1186](https://eips.ethereum.org/EIPS/eip-1186) account and storage proofs against a
+//! block's state root.
+
+mod proof;
+pub use proof::{verify_account_proof, verify_proof, verify_storage_proof, ProofError};
+
+mod root;
+pub use root::{ordered_trie_root, receipts_root, transactions_root, trie_root, withdrawals_root};
+
+use crate::types::H256;
+
+/// The root hash of an empty trie, `keccak256(rlp(""))`
+pub const EMPTY_ROOT_HASH: H256 = H256([
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// The hash of empty code, `keccak256("")`
+pub const KECCAK_EMPTY: H256 = H256([
+    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+    0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+]);
+
+/// Splits the key into its nibbles, high nibble first
+fn to_nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect()
+}
+
+/// Encodes a path of nibbles with the hex-prefix encoding used by leaf and extension nodes
+fn encode_path(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 0x20 } else { 0x00 };
+    let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        encoded.push(flag | 0x10 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        encoded.push(flag);
+        nibbles
+    };
+    encoded.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+    encoded
+}
+
+/// Decodes a hex-prefix encoded path into its nibbles and whether it belongs to a leaf node
+fn decode_path(encoded: &[u8]) -> Option<(Vec<u8>, bool)> {
+    let (&first, rest) = encoded.split_first()?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return None
+    }
+    let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+    if flag & 1 == 1 {
+        nibbles.push(first & 0x0f);
+    }
+    nibbles.extend(to_nibbles(rest));
+    Some((nibbles, flag & 2 == 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::keccak256;
+
+    #[test]
+    fn constants() {
+        assert_eq!(EMPTY_ROOT_HASH, H256(keccak256([0x80])));
+        assert_eq!(KECCAK_EMPTY, H256(keccak256([])));
+    }
+
+    #[test]
+    fn hex_prefix_roundtrip() {
+        for (nibbles, is_leaf, encoded) in [
+            (vec![1, 2, 3, 4, 5], false, vec![0x11, 0x23, 0x45]),
+            (vec![0, 1, 2, 3, 4, 5], false, vec![0x00, 0x01, 0x23, 0x45]),
+            (vec![0, 0xf, 1, 0xc, 0xb, 8], true, vec![0x20, 0x0f, 0x1c, 0xb8]),
+            (vec![0xf, 1, 0xc, 0xb, 8], true, vec![0x3f, 0x1c, 0xb8]),
+        ] {
+            assert_eq!(encode_path(&nibbles, is_leaf), encoded);
+            assert_eq!(decode_path(&encoded), Some((nibbles, is_leaf)));
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use super::{decode_path, to_nibbles, EMPTY_ROOT_HASH, KECCAK_EMPTY};
+use crate::{
+    types::{Bytes, EIP1186ProofResponse, StorageProof, H256},
+    utils::keccak256,
+};
+use rlp::{DecoderError, Rlp, RlpStream};
+use thiserror::Error;
+
+/// An error returned when verifying a Merkle-Patricia proof.
+#[derive(Debug, Error)]
+pub enum ProofError {
+    /// A proof node does not hash to the value referenced by its parent
+    #[error("proof node {index} does not match the hash {expected:?} referenced by its parent")]
+    HashMismatch {
+        /// Index of the offending node in the proof
+        index: usize,
+        /// Hash referenced by the parent node
+        expected: H256,
+    },
+    /// The proof ends before reaching the value or proving its absence
+    #[error("proof is missing nodes")]
+    IncompleteProof,
+    /// A proof node is neither a branch, an extension nor a leaf
+    #[error("invalid trie node")]
+    InvalidNode,
+    /// The proven account does not match the account in the response
+    #[error("account proof does not match the account fields")]
+    AccountMismatch,
+    /// The proven storage value does not match the value in the response
+    #[error("storage proof for slot {0:?} does not match its value")]
+    StorageMismatch(H256),
+    /// When decoding a proof node
+    #[error(transparent)]
+    DecodingError(#[from] DecoderError),
+}
+
+/// A reference to a child node, either by hash or inlined in its parent
+enum NodeRef<'a> {
+    Hash(H256),
+    Inline(&'a [u8]),
+}
+
+impl<'a> NodeRef<'a> {
+    /// Returns the reference held by the given item of a node, or `None` if it is empty
+    fn from_item(item: Rlp<'a>) -> Result<Option<Self>, ProofError> {
+        if item.is_list() {
+            return Ok(Some(NodeRef::Inline(item.as_raw())))
+        }
+        match item.data()? {
+            [] => Ok(None),
+            hash if hash.len() == 32 => Ok(Some(NodeRef::Hash(H256::from_slice(hash)))),
+            _ => Err(ProofError::InvalidNode),
+        }
+    }
+}
+
+/// Verifies a Merkle-Patricia proof for `key` against the trie `root`.
+///
+/// Returns the value stored under `key`, or `None` if the proof shows that the trie does not
+/// contain the key.
+pub fn verify_proof(
+    root: H256,
+    key: &[u8],
+    proof: &[Bytes],
+) -> Result<Option<Vec<u8>>, ProofError> {
+    if root == EMPTY_ROOT_HASH {
+        return Ok(None)
+    }
+
+    let path = to_nibbles(key);
+    let mut path = path.as_slice();
+    let mut proof = proof.iter().enumerate();
+    let mut next = NodeRef::Hash(root);
+
+    loop {
+        let node = match next {
+            NodeRef::Hash(expected) => {
+                let (index, node) = proof.next().ok_or(ProofError::IncompleteProof)?;
+                if H256(keccak256(node)) != expected {
+                    return Err(ProofError::HashMismatch { index, expected })
+                }
+                node.as_ref()
+            }
+            NodeRef::Inline(node) => node,
+        };
+
+        let node = Rlp::new(node);
+        let child = match node.item_count()? {
+            // branch node
+            17 => {
+                let Some((&nibble, rest)) = path.split_first() else {
+                    let value = node.at(16)?.data()?;
+                    return Ok((!value.is_empty()).then(|| value.to_vec()))
+                };
+                path = rest;
+                node.at(nibble as usize)?
+            }
+            // leaf or extension node
+            2 => {
+                let (prefix, is_leaf) =
+                    decode_path(node.at(0)?.data()?).ok_or(ProofError::InvalidNode)?;
+                if is_leaf {
+                    if path != prefix.as_slice() {
+                        return Ok(None)
+                    }
+                    return Ok(Some(node.at(1)?.data()?.to_vec()))
+                }
+                let Some(rest) = path.strip_prefix(prefix.as_slice()) else { return Ok(None) };
+                path = rest;
+                node.at(1)?
+            }
+            _ => return Err(ProofError::InvalidNode),
+        };
+
+        match NodeRef::from_item(child)? {
+            Some(child) => next = child,
+            None => return Ok(None),
+        }
+    }
+}
+
+/// Verifies the account proof of an `eth_getProof` response against the `state_root` of a block,
+/// as well as all of its storage proofs against the proven account's storage root.
+pub fn verify_account_proof(
+    state_root: H256,
+    response: &EIP1186ProofResponse,
+) -> Result<(), ProofError> {
+    let key = keccak256(response.address);
+    let account = verify_proof(state_root, &key, &response.account_proof)?;
+
+    let is_empty = response.nonce.is_zero() &&
+        response.balance.is_zero() &&
+        (response.storage_hash == EMPTY_ROOT_HASH || response.storage_hash.is_zero()) &&
+        (response.code_hash == KECCAK_EMPTY || response.code_hash.is_zero());
+    let expected = (!is_empty).then(|| {
+        let mut s = RlpStream::new_list(4);
+        s.append(&response.nonce);
+        s.append(&response.balance);
+        s.append(&response.storage_hash);
+        s.append(&response.code_hash);
+        s.out().to_vec()
+    });
+    if account != expected {
+        return Err(ProofError::AccountMismatch)
+    }
+
+    // nodes report absent accounts with a zero storage root, which stands for the empty trie
+    let storage_root =
+        if response.storage_hash.is_zero() { EMPTY_ROOT_HASH } else { response.storage_hash };
+    response.storage_proof.iter().try_for_each(|proof| verify_storage_proof(storage_root, proof))
+}
+
+/// Verifies a storage proof against the storage root of the account it belongs to
+pub fn verify_storage_proof(storage_root: H256, proof: &StorageProof) -> Result<(), ProofError> {
+    let key = keccak256(proof.key);
+    let value = verify_proof(storage_root, &key, &proof.proof)?;
+    let expected = (!proof.value.is_zero()).then(|| rlp::encode(&proof.value).to_vec());
+    if value != expected {
+        return Err(ProofError::StorageMismatch(proof.key))
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Address;
+
+    fn proof() -> (H256, EIP1186ProofResponse) {
+        let proof: EIP1186ProofResponse =
+            serde_json::from_str(include_str!("../../testdata/proof.json")).unwrap();
+        // the first node of the account proof is the root of the state trie
+        (H256(keccak256(&proof.account_proof[0])), proof)
+    }
+
+    #[test]
+    fn can_verify_account_and_storage_proof() {
+        let (state_root, proof) = proof();
+        verify_account_proof(state_root, &proof).unwrap();
+        proof.verify(state_root).unwrap();
+
+        let account = verify_proof(state_root, &keccak256(proof.address), &proof.account_proof)
+            .unwrap()
+            .unwrap();
+        let account = Rlp::new(&account);
+        assert_eq!(account.val_at::<H256>(2).unwrap(), proof.storage_hash);
+        assert_eq!(account.val_at::<H256>(3).unwrap(), proof.code_hash);
+    }
+
+    #[test]
+    fn rejects_invalid_account_proof() {
+        let (state_root, mut proof) = proof();
+
+        let err = proof.verify(H256::repeat_byte(1)).unwrap_err();
+        assert!(matches!(err, ProofError::HashMismatch { index: 0, .. }), "{err:?}");
+
+        proof.balance = 1u64.into();
+        assert!(matches!(proof.verify(state_root).unwrap_err(), ProofError::AccountMismatch));
+
+        let (_, mut proof) = self::proof();
+        proof.account_proof.pop();
+        assert!(matches!(proof.verify(state_root).unwrap_err(), ProofError::IncompleteProof));
+
+        let (_, mut proof) = self::proof();
+        proof.account_proof.swap(1, 2);
+        assert!(matches!(
+            proof.verify(state_root).unwrap_err(),
+            ProofError::HashMismatch { index: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn can_verify_absent_account_with_zero_storage_hash() {
+        let proof = EIP1186ProofResponse {
+            address: Address::repeat_byte(0x42),
+            storage_proof: vec![StorageProof { key: H256::repeat_byte(1), ..Default::default() }],
+            ..Default::default()
+        };
+        assert!(proof.storage_hash.is_zero());
+        verify_account_proof(EMPTY_ROOT_HASH, &proof).unwrap();
+
+        let mut proof = proof;
+        proof.storage_proof[0].value = 1u64.into();
+        assert!(matches!(
+            verify_account_proof(EMPTY_ROOT_HASH, &proof).unwrap_err(),
+            ProofError::StorageMismatch(_)
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_storage_proof() {
+        let (state_root, mut proof) = proof();
+        proof.storage_proof[0].value = 1u64.into();
+        let key = proof.storage_proof[0].key;
+        assert!(
+            matches!(proof.verify(state_root).unwrap_err(), ProofError::StorageMismatch(k) if k == key)
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use super::{encode_path, to_nibbles};
+use crate::{
+    types::{Transaction, TransactionReceipt, Withdrawal, H256},
+    utils::keccak256,
+};
+use rlp::RlpStream;
+
+/// Computes the root of the trie containing the given key-value pairs.
+///
+/// Keys are expected to be unique, values must not be empty.
+pub fn trie_root<I, K, V>(items: I) -> H256
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<[u8]>,
+    V: AsRef<[u8]>,
+{
+    let mut items: Vec<(Vec<u8>, V)> =
+        items.into_iter().map(|(key, value)| (to_nibbles(key.as_ref()), value)).collect();
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+    keccak256(encode_node(&items, 0)).into()
+}
+
+/// Computes the root of the trie keyed by the RLP-encoded index of each value, which is how the
+/// transactions, receipts and withdrawals of a block are committed to.
+pub fn ordered_trie_root<I, V>(values: I) -> H256
+where
+    I: IntoIterator<Item = V>,
+    V: AsRef<[u8]>,
+{
+    trie_root(values.into_iter().enumerate().map(|(index, value)| (rlp::encode(&index), value)))
+}
+
+/// Computes the `transactions_root` of a block containing the given transactions
+pub fn transactions_root(transactions: &[Transaction]) -> H256 {
+    ordered_trie_root(transactions.iter().map(Transaction::rlp))
+}
+
+/// Computes the `receipts_root` of a block with the given receipts
+pub fn receipts_root(receipts: &[TransactionReceipt]) -> H256 {
+    ordered_trie_root(receipts.iter().map(|receipt| {
+        let mut encoded = Vec::new();
+        // typed receipts are prefixed with their transaction type, as per EIP-2718
+        match receipt.transaction_type {
+            Some(ty) if !ty.is_zero() => encoded.push(ty.low_u64() as u8),
+            _ => {}
+        }
+        encoded.extend_from_slice(&rlp::encode(receipt));
+        encoded
+    }))
+}
+
+/// Computes the `withdrawals_root` of a block with the given withdrawals
+pub fn withdrawals_root(withdrawals: &[Withdrawal]) -> H256 {
+    ordered_trie_root(withdrawals.iter().map(rlp::encode))
+}
+
+/// Returns the RLP encoding of the node holding the given items, whose keys are sorted and share
+/// their first `depth` nibbles
+fn encode_node<V: AsRef<[u8]>>(items: &[(Vec<u8>, V)], depth: usize) -> Vec<u8> {
+    let mut s = RlpStream::new();
+    match items {
+        [] => {
+            s.append_empty_data();
+        }
+        [(key, value)] => {
+            s.begin_list(2);
+            s.append(&encode_path(&key[depth..], true));
+            s.append(&value.as_ref());
+        }
+        [(first, _), .., (last, _)] => {
+            // since the keys are sorted, the prefix shared by all of them is the one shared by the
+            // first and the last key
+            let shared =
+                first[depth..].iter().zip(&last[depth..]).take_while(|(a, b)| a == b).count();
+            if shared > 0 {
+                s.begin_list(2);
+                s.append(&encode_path(&first[depth..depth + shared], false));
+                append_child(&mut s, encode_node(items, depth + shared));
+            } else {
+                let mut rest = items;
+                let value = match rest.split_first() {
+                    Some(((key, value), tail)) if key.len() == depth => {
+                        rest = tail;
+                        Some(value)
+                    }
+                    _ => None,
+                };
+
+                s.begin_list(17);
+                for nibble in 0..16 {
+                    let len = rest.iter().take_while(|(key, _)| key[depth] == nibble).count();
+                    let (children, tail) = rest.split_at(len);
+                    if children.is_empty() {
+                        s.append_empty_data();
+                    } else {
+                        append_child(&mut s, encode_node(children, depth + 1));
+                    }
+                    rest = tail;
+                }
+                match value {
+                    Some(value) => s.append(&value.as_ref()),
+                    None => s.append_empty_data(),
+                };
+            }
+        }
+    }
+    s.out().to_vec()
+}
+
+/// Appends a reference to a child node, which is inlined if its encoding is shorter than a hash
+fn append_child(s: &mut RlpStream, node: Vec<u8>) {
+    if node.len() < 32 {
+        s.append_raw(&node, 1);
+    } else {
+        s.append(&keccak256(node).as_slice());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::EMPTY_ROOT_HASH, *};
+    use crate::types::{Block, U64};
+    use std::str::FromStr;
+
+    #[test]
+    fn empty_roots() {
+        assert_eq!(trie_root(Vec::<(Vec<u8>, Vec<u8>)>::new()), EMPTY_ROOT_HASH);
+        assert_eq!(transactions_root(&[]), EMPTY_ROOT_HASH);
+        assert_eq!(receipts_root(&[]), EMPTY_ROOT_HASH);
+        assert_eq!(withdrawals_root(&[]), EMPTY_ROOT_HASH);
+    }
+
+    #[test]
+    fn known_trie_root() {
+        // from the ethereum/tests `trietest.json` suite
+        let root = trie_root([("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")]);
+        assert_eq!(
+            root,
+            H256::from_str("0x8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3")
+                .unwrap()
+        );
+
+        // insertion order does not matter
+        let root_reversed =
+            trie_root([("dogglesworth", "cat"), ("dog", "puppy"), ("doe", "reindeer")]);
+        assert_eq!(root, root_reversed);
+    }
+
+    #[test]
+    fn block_roots() {
+        let block = r#"{"number":"0x3","hash":"0xda53da08ef6a3cbde84c33e51c04f68c3853b6a3731f10baa2324968eee63972","parentHash":"0x689c70c080ca22bc0e681694fa803c1aba16a69c8b6368fed5311d279eb9de90","mixHash":"0x0000000000000000000000000000000000000000000000000000000000000000","nonce":"0x0000000000000000","sha3Uncles":"0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347","logsBloom":"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","transactionsRoot":"0x7270c1c4440180f2bd5215809ee3d545df042b67329499e1ab97eb759d31610d","stateRoot":"0x29f32984517a7d25607da485b23cefabfd443751422ca7e603395e1de9bc8a4b","receiptsRoot":"0x056b23fbba480696b65fe5a59b8f2148a1299103c4f57df839233af2cf4ca2d2","miner":"0x0000000000000000000000000000000000000000","difficulty":"0x0","totalDifficulty":"0x0","extraData":"0x","size":"0x3e8","gasLimit":"0x6691b7","gasUsed":"0x5208","timestamp":"0x5ecedbb9","transactions":[{"hash":"0xc3c5f700243de37ae986082fd2af88d2a7c2752a0c0f7b9d6ac47c729d45e067","nonce":"0x2","blockHash":"0xda53da08ef6a3cbde84c33e51c04f68c3853b6a3731f10baa2324968eee63972","blockNumber":"0x3","transactionIndex":"0x0","from":"0xfdcedc3bfca10ecb0890337fbdd1977aba84807a","to":"0xdca8ce283150ab773bcbeb8d38289bdb5661de1e","value":"0x0","gas":"0x15f90","gasPrice":"0x4a817c800","input":"0x","v":"0x25","r":"0x19f2694eb9113656dbea0b925e2e7ceb43df83e601c4116aee9c0dd99130be88","s":"0x73e5764b324a4f7679d890a198ba658ba1c8cd36983ff9797e10b1b89dbb448e"}],"uncles":[]}"#;
+        let block: Block<Transaction> = serde_json::from_str(block).unwrap();
+        assert_eq!(transactions_root(&block.transactions), block.transactions_root);
+
+        let receipt = TransactionReceipt {
+            status: Some(U64::one()),
+            cumulative_gas_used: block.gas_used,
+            logs_bloom: block.logs_bloom.unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(receipts_root(&[receipt]), block.receipts_root);
+    }
+}
@@ -1,8 +1,8 @@
 // Modified from <https://github.com/tomusdrw/rust-web3/blob/master/src/types/block.rs>
 
-#[cfg(not(feature = "celo"))]
-use crate::types::Withdrawal;
 use crate::types::{Address, Bloom, Bytes, Transaction, TxHash, H256, U256, U64};
+#[cfg(not(feature = "celo"))]
+use crate::types::{Header, Withdrawal};
 use chrono::{DateTime, TimeZone, Utc};
 use serde::{
     de::{MapAccess, Visitor},
@@ -94,6 +94,18 @@ pub struct Block<TX> {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[cfg(not(feature = "celo"))]
     pub withdrawals: Option<Vec<Withdrawal>>,
+    /// Total blob gas used by the transactions in the block (if past Cancun)
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "blobGasUsed")]
+    #[cfg(not(feature = "celo"))]
+    pub blob_gas_used: Option<U256>,
+    /// Excess blob gas carried over from the previous blocks (if past Cancun)
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "excessBlobGas")]
+    #[cfg(not(feature = "celo"))]
+    pub excess_blob_gas: Option<U256>,
+    /// Root of the parent beacon block (if past Cancun)
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "parentBeaconBlockRoot")]
+    #[cfg(not(feature = "celo"))]
+    pub parent_beacon_block_root: Option<H256>,
 
     #[cfg(feature = "celo")]
     #[cfg_attr(docsrs, doc(cfg(feature = "celo")))]
@@ -140,6 +152,51 @@ pub const ELASTICITY_MULTIPLIER: U256 = U256([2u64, 0, 0, 0]);
 #[cfg(not(feature = "celo"))]
 pub const BASE_FEE_MAX_CHANGE_DENOMINATOR: U256 = U256([8u64, 0, 0, 0]);
 
+// ref <https://eips.ethereum.org/EIPS/eip-4844>
+/// The target blob gas usage of a block, i.e. 3 blobs
+pub const TARGET_BLOB_GAS_PER_BLOCK: U256 = U256([393_216u64, 0, 0, 0]);
+/// The minimum price of a unit of blob gas
+pub const MIN_BLOB_GASPRICE: U256 = U256([1u64, 0, 0, 0]);
+/// Controls the maximum rate of change of the blob gas price
+pub const BLOB_GASPRICE_UPDATE_FRACTION: U256 = U256([3_338_477u64, 0, 0, 0]);
+
+/// Approximates `factor * e ** (numerator / denominator)` using Taylor expansion, as specified by
+/// EIP-4844.
+///
+/// Saturates at `U256::MAX` if the result does not fit, e.g. for an absurdly high excess blob gas.
+pub fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> U256 {
+    let mut i = U256::one();
+    let mut output = U256::zero();
+    let Some(mut numerator_accum) = factor.checked_mul(denominator) else { return U256::MAX };
+    while !numerator_accum.is_zero() {
+        let (Some(sum), Some(product), Some(divisor)) = (
+            output.checked_add(numerator_accum),
+            numerator_accum.checked_mul(numerator),
+            denominator.checked_mul(i),
+        ) else {
+            return U256::MAX
+        };
+        output = sum;
+        numerator_accum = product / divisor;
+        i += U256::one();
+    }
+    output / denominator
+}
+
+/// Calculates the excess blob gas of a block from its parent's excess blob gas and blob gas used.
+/// Reference: <https://eips.ethereum.org/EIPS/eip-4844>
+pub fn calc_excess_blob_gas(parent_excess_blob_gas: U256, parent_blob_gas_used: U256) -> U256 {
+    parent_excess_blob_gas
+        .saturating_add(parent_blob_gas_used)
+        .saturating_sub(TARGET_BLOB_GAS_PER_BLOCK)
+}
+
+/// Calculates the price of a unit of blob gas from a block's excess blob gas.
+/// Reference: <https://eips.ethereum.org/EIPS/eip-4844>
+pub fn calc_blob_gasprice(excess_blob_gas: U256) -> U256 {
+    fake_exponential(MIN_BLOB_GASPRICE, excess_blob_gas, BLOB_GASPRICE_UPDATE_FRACTION)
+}
+
 impl<TX> Block<TX> {
     /// The target gas usage as per EIP-1559
     #[cfg(not(feature = "celo"))]
@@ -180,6 +237,43 @@ impl<TX> Block<TX> {
         }
     }
 
+    /// The price of a unit of blob gas in this block, it is a function of the block's excess blob
+    /// gas. Returns `None` for blocks before Cancun.
+    /// Reference: <https://eips.ethereum.org/EIPS/eip-4844>
+    #[cfg(not(feature = "celo"))]
+    pub fn blob_fee(&self) -> Option<U256> {
+        self.excess_blob_gas.map(calc_blob_gasprice)
+    }
+
+    /// The next block's excess blob gas, it is a function of this block's excess blob gas and blob
+    /// gas usage.
+    /// Reference: <https://eips.ethereum.org/EIPS/eip-4844>
+    #[cfg(not(feature = "celo"))]
+    pub fn next_block_excess_blob_gas(&self) -> Option<U256> {
+        Some(calc_excess_blob_gas(self.excess_blob_gas?, self.blob_gas_used?))
+    }
+
+    /// The next block's price of a unit of blob gas.
+    /// Reference: <https://eips.ethereum.org/EIPS/eip-4844>
+    #[cfg(not(feature = "celo"))]
+    pub fn next_block_blob_fee(&self) -> Option<U256> {
+        self.next_block_excess_blob_gas().map(calc_blob_gasprice)
+    }
+
+    /// Extracts the [`Header`] of this block, which can be RLP-encoded and hashed locally.
+    #[cfg(not(feature = "celo"))]
+    pub fn header(&self) -> Header {
+        self.into()
+    }
+
+    /// Returns `true` if the block's `hash` matches the hash of its header recomputed locally.
+    ///
+    /// Always returns `false` for pending blocks, which have no hash.
+    #[cfg(not(feature = "celo"))]
+    pub fn verify_hash(&self) -> bool {
+        self.hash.map_or(false, |hash| hash == self.header().hash_slow())
+    }
+
     /// Parse [`Self::timestamp`] into a [`DateTime<Utc>`].
     ///
     /// # Errors
@@ -230,6 +324,9 @@ impl Block<TxHash> {
                 base_fee_per_gas,
                 withdrawals_root,
                 withdrawals,
+                blob_gas_used,
+                excess_blob_gas,
+                parent_beacon_block_root,
                 other,
                 ..
             } = self;
@@ -257,6 +354,9 @@ impl Block<TxHash> {
                 base_fee_per_gas,
                 withdrawals_root,
                 withdrawals,
+                blob_gas_used,
+                excess_blob_gas,
+                parent_beacon_block_root,
                 transactions,
                 other,
             }
@@ -338,6 +438,9 @@ impl From<Block<Transaction>> for Block<TxHash> {
                 base_fee_per_gas,
                 withdrawals_root,
                 withdrawals,
+                blob_gas_used,
+                excess_blob_gas,
+                parent_beacon_block_root,
                 other,
             } = full;
             Block {
@@ -364,6 +467,9 @@ impl From<Block<Transaction>> for Block<TxHash> {
                 base_fee_per_gas,
                 withdrawals_root,
                 withdrawals,
+                blob_gas_used,
+                excess_blob_gas,
+                parent_beacon_block_root,
                 transactions: transactions.iter().map(|tx| tx.hash).collect(),
                 other,
             }
@@ -867,6 +973,64 @@ mod tests {
         assert_eq!(block_14402712.next_block_base_fee(), Some(U256::from(27_978_655_303u128)));
     }
 
+    #[test]
+    #[cfg(not(feature = "celo"))]
+    fn test_blob_fee() {
+        // <https://github.com/ethereum/go-ethereum/blob/master/consensus/misc/eip4844/eip4844_test.go>
+        for (factor, numerator, denominator, expected) in [
+            (1u64, 0u64, 1u64, 1u64),
+            (38493, 0, 1000, 38493),
+            (0, 1234, 2345, 0),
+            (1, 2, 1, 6),
+            (1, 4, 2, 6),
+            (1, 3, 1, 16),
+            (10, 8, 2, 542),
+            (2, 5, 2, 23),
+            (1, 50000000, 2225652, 5709098764),
+        ] {
+            assert_eq!(
+                fake_exponential(factor.into(), numerator.into(), denominator.into()),
+                expected.into()
+            );
+        }
+
+        for (excess_blob_gas, expected) in
+            [(0u64, 1u64), (2314057, 1), (2314058, 2), (10 * 1024 * 1024, 23)]
+        {
+            assert_eq!(calc_blob_gasprice(excess_blob_gas.into()), expected.into());
+        }
+
+        // saturates instead of overflowing
+        assert_eq!(fake_exponential(U256::MAX, 1.into(), 2.into()), U256::MAX);
+        assert_eq!(calc_blob_gasprice(U256::from(u64::MAX)), U256::MAX);
+        assert_eq!(
+            calc_excess_blob_gas(U256::MAX, U256::MAX),
+            U256::MAX - TARGET_BLOB_GAS_PER_BLOCK
+        );
+
+        let block: Block<TxHash> = Block {
+            excess_blob_gas: Some(U256::from(2_000_000u64)),
+            blob_gas_used: Some(U256::from(786_432u64)),
+            ..Default::default()
+        };
+        assert_eq!(block.blob_fee(), Some(U256::one()));
+        assert_eq!(block.next_block_excess_blob_gas(), Some(U256::from(2_393_216u64)));
+        assert_eq!(block.next_block_blob_fee(), Some(U256::from(2u64)));
+
+        // below the target the excess blob gas is drained
+        let block: Block<TxHash> = Block {
+            excess_blob_gas: Some(U256::zero()),
+            blob_gas_used: Some(U256::from(131_072u64)),
+            ..Default::default()
+        };
+        assert_eq!(block.next_block_excess_blob_gas(), Some(U256::zero()));
+
+        // pre-Cancun blocks have no blob fee
+        let block: Block<TxHash> = Block::default();
+        assert_eq!(block.blob_fee(), None);
+        assert_eq!(block.next_block_blob_fee(), None);
+    }
+
     #[test]
     fn pending_block() {
         let json = serde_json::json!(
@@ -372,6 +372,19 @@ impl Filter {
     pub fn has_topics(&self) -> bool {
         self.topics.iter().any(|t| t.is_some())
     }
+
+    /// Returns `false` if the logs bloom, e.g. of a block or a receipt, shows that none of its
+    /// logs can match the address and topics of the filter, in which case there's no need to fetch
+    /// its logs.
+    ///
+    /// Since blooms may report false positives, a `true` result means that matching logs may
+    /// exist. The block range of the filter is not taken into account.
+    pub fn matches_bloom(&self, bloom: &Bloom) -> bool {
+        let address_filter = FilteredParams::address_filter(&self.address);
+        let topics_filter = FilteredParams::topics_filter(&Some(self.flatten()));
+        FilteredParams::matches_address(*bloom, &address_filter) &&
+            FilteredParams::matches_topics(*bloom, &topics_filter)
+    }
 }
 
 impl Serialize for Filter {
@@ -990,6 +1003,30 @@ mod tests {
         (filter, Some(filtered_params.flat_topics))
     }
 
+    #[test]
+    fn filter_matches_bloom() {
+        let address = Address::random();
+        let topic0 = H256::random();
+        let topic1 = H256::random();
+        let bloom = build_bloom(address, topic0, topic1);
+
+        assert!(Filter::new().matches_bloom(&bloom));
+        assert!(Filter::new().address(address).topic0(topic0).matches_bloom(&bloom));
+        assert!(Filter::new().address(vec![Address::random(), address]).matches_bloom(&bloom));
+        assert!(Filter::new()
+            .topic0(vec![H256::random(), topic0])
+            .topic2(topic1)
+            .matches_bloom(&bloom));
+        assert!(Filter::new()
+            .topic0(ValueOrArray::Array(vec![Some(H256::random()), None]))
+            .matches_bloom(&bloom));
+
+        assert!(!Filter::new().address(Address::random()).matches_bloom(&bloom));
+        assert!(!Filter::new().address(address).topic1(H256::random()).matches_bloom(&bloom));
+        assert!(!Filter::new().topic0(vec![H256::random(), H256::random()]).matches_bloom(&bloom));
+        assert!(!Filter::new().address(vec![Address::random()]).matches_bloom(&bloom));
+    }
+
     #[test]
     fn can_detect_different_topics() {
         let topic1 = H256::random();
@@ -0,0 +1,383 @@
+use crate::{
+    types::{Address, Block, Bloom, Bytes, H256, H64, U256, U64},
+    utils::keccak256,
+};
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Number of fields of a header before London
+const NUM_BASE_FIELDS: usize = 15;
+
+/// An error returned when verifying the link between headers.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum HeaderError {
+    /// The header does not point to the hash of the preceding header
+    #[error("header {number} has parent hash {got:?}, but its parent hashes to {expected:?}")]
+    ParentHashMismatch {
+        /// Number of the offending header
+        number: U64,
+        /// Hash of the parent header
+        expected: H256,
+        /// Parent hash stored in the header
+        got: H256,
+    },
+    /// The header number does not follow the number of the preceding header
+    #[error("header {got} does not follow its parent {parent}")]
+    NumberMismatch {
+        /// Number of the parent header
+        parent: U64,
+        /// Number of the offending header
+        got: U64,
+    },
+}
+
+/// The consensus-relevant part of a [`Block`], i.e. the fields the block hash commits to.
+///
+/// Unlike the [`Block`] returned by RPC calls, the header can be RLP-encoded and hashed locally,
+/// which allows verifying the hashes and `parent_hash` links reported by an untrusted node.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Header {
+    /// Hash of the parent
+    #[serde(default, rename = "parentHash")]
+    pub parent_hash: H256,
+    /// Hash of the uncles
+    #[serde(default, rename = "sha3Uncles")]
+    pub uncles_hash: H256,
+    /// Miner/author's address
+    #[serde(default, rename = "miner")]
+    pub author: Address,
+    /// State root hash
+    #[serde(default, rename = "stateRoot")]
+    pub state_root: H256,
+    /// Transactions root hash
+    #[serde(default, rename = "transactionsRoot")]
+    pub transactions_root: H256,
+    /// Transactions receipts root hash
+    #[serde(default, rename = "receiptsRoot")]
+    pub receipts_root: H256,
+    /// Logs bloom
+    #[serde(default, rename = "logsBloom")]
+    pub logs_bloom: Bloom,
+    /// Difficulty
+    #[serde(default)]
+    pub difficulty: U256,
+    /// Block number
+    #[serde(default)]
+    pub number: U64,
+    /// Gas Limit
+    #[serde(default, rename = "gasLimit")]
+    pub gas_limit: U256,
+    /// Gas Used
+    #[serde(default, rename = "gasUsed")]
+    pub gas_used: U256,
+    /// Timestamp
+    #[serde(default)]
+    pub timestamp: U256,
+    /// Extra data
+    #[serde(default, rename = "extraData")]
+    pub extra_data: Bytes,
+    /// Mix Hash
+    #[serde(default, rename = "mixHash")]
+    pub mix_hash: H256,
+    /// Nonce
+    #[serde(default)]
+    pub nonce: H64,
+    /// Base fee per unit of gas (if past London)
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "baseFeePerGas")]
+    pub base_fee_per_gas: Option<U256>,
+    /// Withdrawals root hash (if past Shanghai)
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "withdrawalsRoot")]
+    pub withdrawals_root: Option<H256>,
+    /// Total blob gas used by the transactions in the block (if past Cancun)
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "blobGasUsed")]
+    pub blob_gas_used: Option<U256>,
+    /// Excess blob gas carried over from the previous blocks (if past Cancun)
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "excessBlobGas")]
+    pub excess_blob_gas: Option<U256>,
+    /// Root of the parent beacon block (if past Cancun)
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "parentBeaconBlockRoot")]
+    pub parent_beacon_block_root: Option<H256>,
+}
+
+impl Header {
+    /// Returns the RLP encoding of the header
+    pub fn rlp_encode(&self) -> Bytes {
+        rlp::encode(self).freeze().into()
+    }
+
+    /// Computes the hash of the header, i.e. the keccak256 hash of its RLP encoding.
+    ///
+    /// This re-encodes the header on every call, so callers should cache the result.
+    pub fn hash_slow(&self) -> H256 {
+        keccak256(self.rlp_encode()).into()
+    }
+
+    /// Checks that this header directly follows the given `parent`, i.e. that its number is the
+    /// successor of the parent's number and that its `parent_hash` matches the parent's hash.
+    pub fn verify_parent(&self, parent: &Header) -> Result<(), HeaderError> {
+        if parent.number.checked_add(U64::one()) != Some(self.number) {
+            return Err(HeaderError::NumberMismatch { parent: parent.number, got: self.number })
+        }
+        let expected = parent.hash_slow();
+        if self.parent_hash != expected {
+            return Err(HeaderError::ParentHashMismatch {
+                number: self.number,
+                expected,
+                got: self.parent_hash,
+            })
+        }
+        Ok(())
+    }
+
+    /// Checks that the given headers, ordered by ascending number, form a chain in which each
+    /// header links to its predecessor.
+    pub fn verify_chain(headers: &[Header]) -> Result<(), HeaderError> {
+        headers.windows(2).try_for_each(|pair| pair[1].verify_parent(&pair[0]))
+    }
+
+    /// The number of optional fields which are part of the encoding. A field is included if it or
+    /// any of the fields introduced after it is set.
+    fn num_optional_fields(&self) -> usize {
+        if self.parent_beacon_block_root.is_some() {
+            5
+        } else if self.excess_blob_gas.is_some() || self.blob_gas_used.is_some() {
+            4
+        } else if self.withdrawals_root.is_some() {
+            2
+        } else if self.base_fee_per_gas.is_some() {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+/// Appends the value if set, or an empty string in its place otherwise
+fn append_opt<T: Encodable>(s: &mut RlpStream, value: &Option<T>) {
+    match value {
+        Some(value) => s.append(value),
+        None => s.append_empty_data(),
+    };
+}
+
+/// Decodes the value at `index` if the list is long enough to contain it
+fn decode_opt<T: Decodable>(rlp: &Rlp, index: usize) -> Result<Option<T>, DecoderError> {
+    if index < rlp.item_count()? {
+        rlp.val_at(index).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+impl Encodable for Header {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        let num_optional_fields = self.num_optional_fields();
+        s.begin_list(NUM_BASE_FIELDS + num_optional_fields);
+        s.append(&self.parent_hash);
+        s.append(&self.uncles_hash);
+        s.append(&self.author);
+        s.append(&self.state_root);
+        s.append(&self.transactions_root);
+        s.append(&self.receipts_root);
+        s.append(&self.logs_bloom);
+        s.append(&self.difficulty);
+        s.append(&self.number);
+        s.append(&self.gas_limit);
+        s.append(&self.gas_used);
+        s.append(&self.timestamp);
+        s.append(&self.extra_data.as_ref());
+        s.append(&self.mix_hash);
+        s.append(&self.nonce);
+
+        if num_optional_fields > 0 {
+            append_opt(s, &self.base_fee_per_gas);
+        }
+        if num_optional_fields > 1 {
+            append_opt(s, &self.withdrawals_root);
+        }
+        if num_optional_fields > 2 {
+            append_opt(s, &self.blob_gas_used);
+            append_opt(s, &self.excess_blob_gas);
+        }
+        if num_optional_fields > 4 {
+            append_opt(s, &self.parent_beacon_block_root);
+        }
+    }
+}
+
+impl Decodable for Header {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? < NUM_BASE_FIELDS {
+            return Err(DecoderError::RlpIncorrectListLen)
+        }
+        Ok(Self {
+            parent_hash: rlp.val_at(0)?,
+            uncles_hash: rlp.val_at(1)?,
+            author: rlp.val_at(2)?,
+            state_root: rlp.val_at(3)?,
+            transactions_root: rlp.val_at(4)?,
+            receipts_root: rlp.val_at(5)?,
+            logs_bloom: rlp.val_at(6)?,
+            difficulty: rlp.val_at(7)?,
+            number: rlp.val_at(8)?,
+            gas_limit: rlp.val_at(9)?,
+            gas_used: rlp.val_at(10)?,
+            timestamp: rlp.val_at(11)?,
+            extra_data: rlp.val_at::<Vec<u8>>(12)?.into(),
+            mix_hash: rlp.val_at(13)?,
+            nonce: rlp.val_at(14)?,
+            base_fee_per_gas: decode_opt(rlp, 15)?,
+            withdrawals_root: decode_opt(rlp, 16)?,
+            blob_gas_used: decode_opt(rlp, 17)?,
+            excess_blob_gas: decode_opt(rlp, 18)?,
+            parent_beacon_block_root: decode_opt(rlp, 19)?,
+        })
+    }
+}
+
+impl<TX> From<&Block<TX>> for Header {
+    /// Extracts the header of the block. Fields that are unset on pending blocks, such as the
+    /// number or the nonce, default to zero.
+    fn from(block: &Block<TX>) -> Self {
+        Self {
+            parent_hash: block.parent_hash,
+            uncles_hash: block.uncles_hash,
+            author: block.author.unwrap_or_default(),
+            state_root: block.state_root,
+            transactions_root: block.transactions_root,
+            receipts_root: block.receipts_root,
+            logs_bloom: block.logs_bloom.unwrap_or_default(),
+            difficulty: block.difficulty,
+            number: block.number.unwrap_or_default(),
+            gas_limit: block.gas_limit,
+            gas_used: block.gas_used,
+            timestamp: block.timestamp,
+            extra_data: block.extra_data.clone(),
+            mix_hash: block.mix_hash.unwrap_or_default(),
+            nonce: block.nonce.unwrap_or_default(),
+            base_fee_per_gas: block.base_fee_per_gas,
+            withdrawals_root: block.withdrawals_root,
+            blob_gas_used: block.blob_gas_used,
+            excess_blob_gas: block.excess_blob_gas,
+            parent_beacon_block_root: block.parent_beacon_block_root,
+        }
+    }
+}
+
+impl<TX> From<Block<TX>> for Header {
+    fn from(block: Block<TX>) -> Self {
+        Self::from(&block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TxHash;
+    use std::str::FromStr;
+
+    fn mainnet_genesis() -> Header {
+        Header {
+            uncles_hash: H256::from_str(
+                "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+            )
+            .unwrap(),
+            state_root: H256::from_str(
+                "0xd7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f0544",
+            )
+            .unwrap(),
+            transactions_root: H256::from_str(
+                "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+            )
+            .unwrap(),
+            receipts_root: H256::from_str(
+                "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+            )
+            .unwrap(),
+            difficulty: 0x400000000u64.into(),
+            gas_limit: 5000u64.into(),
+            extra_data: "0x11bbe8db4e347b4e8c937c1c8370e4b5ed33adb3db69cbdb7a38e1e50b1b82fa"
+                .parse()
+                .unwrap(),
+            nonce: H64::from_low_u64_be(0x42),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn hash_mainnet_genesis() {
+        let genesis = mainnet_genesis();
+        assert_eq!(
+            genesis.hash_slow(),
+            H256::from_str("0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3")
+                .unwrap()
+        );
+        assert_eq!(rlp::decode::<Header>(&genesis.rlp_encode()).unwrap(), genesis);
+    }
+
+    #[test]
+    fn hash_rpc_block() {
+        let block = r#"{"number":"0x3","hash":"0xda53da08ef6a3cbde84c33e51c04f68c3853b6a3731f10baa2324968eee63972","parentHash":"0x689c70c080ca22bc0e681694fa803c1aba16a69c8b6368fed5311d279eb9de90","mixHash":"0x0000000000000000000000000000000000000000000000000000000000000000","nonce":"0x0000000000000000","sha3Uncles":"0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347","logsBloom":"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","transactionsRoot":"0x7270c1c4440180f2bd5215809ee3d545df042b67329499e1ab97eb759d31610d","stateRoot":"0x29f32984517a7d25607da485b23cefabfd443751422ca7e603395e1de9bc8a4b","receiptsRoot":"0x056b23fbba480696b65fe5a59b8f2148a1299103c4f57df839233af2cf4ca2d2","miner":"0x0000000000000000000000000000000000000000","difficulty":"0x0","totalDifficulty":"0x0","extraData":"0x","size":"0x3e8","gasLimit":"0x6691b7","gasUsed":"0x5208","timestamp":"0x5ecedbb9","transactions":["0xc3c5f700243de37ae986082fd2af88d2a7c2752a0c0f7b9d6ac47c729d45e067"],"uncles":[]}"#;
+        let block: Block<TxHash> = serde_json::from_str(block).unwrap();
+        assert_eq!(Some(block.header().hash_slow()), block.hash);
+        assert!(block.verify_hash());
+
+        // the header can be deserialized from the same response
+        let header: Header = serde_json::from_str(&serde_json::to_string(&block).unwrap()).unwrap();
+        assert_eq!(header, block.header());
+    }
+
+    #[test]
+    fn rlp_cancun_header() {
+        let header = Header {
+            number: 19_426_587u64.into(),
+            base_fee_per_gas: Some(20_000_000_000u64.into()),
+            withdrawals_root: Some(H256::repeat_byte(0x01)),
+            blob_gas_used: Some(0x20000u64.into()),
+            excess_blob_gas: Some(0u64.into()),
+            parent_beacon_block_root: Some(H256::repeat_byte(0x02)),
+            ..mainnet_genesis()
+        };
+        let encoded = header.rlp_encode();
+        assert_eq!(Rlp::new(&encoded).item_count().unwrap(), 20);
+        assert_eq!(rlp::decode::<Header>(&encoded).unwrap(), header);
+
+        // a post-London header without Shanghai and Cancun fields
+        let london = Header {
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+            ..header
+        };
+        let encoded = london.rlp_encode();
+        assert_eq!(Rlp::new(&encoded).item_count().unwrap(), 16);
+        assert_eq!(rlp::decode::<Header>(&encoded).unwrap(), london);
+    }
+
+    #[test]
+    fn verify_header_chain() {
+        let genesis = mainnet_genesis();
+        let first =
+            Header { number: 1u64.into(), parent_hash: genesis.hash_slow(), ..genesis.clone() };
+        let second =
+            Header { number: 2u64.into(), parent_hash: first.hash_slow(), ..genesis.clone() };
+        Header::verify_chain(&[genesis.clone(), first.clone(), second.clone()]).unwrap();
+
+        assert_eq!(
+            Header::verify_chain(&[genesis.clone(), second.clone()]).unwrap_err(),
+            HeaderError::NumberMismatch { parent: 0u64.into(), got: 2u64.into() }
+        );
+
+        let forged = Header { parent_hash: H256::zero(), ..second };
+        assert_eq!(
+            forged.verify_parent(&first).unwrap_err(),
+            HeaderError::ParentHashMismatch {
+                number: 2u64.into(),
+                expected: first.hash_slow(),
+                got: H256::zero(),
+            }
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use ethabi::RawLog;
 // Adapted from https://github.com/tomusdrw/rust-web3/blob/master/src/types/log.rs
-use crate::types::{Address, Bytes, H256, U256, U64};
+use crate::{
+    abi::ethereum_types::BloomInput,
+    types::{Address, Bloom, Bytes, H256, U256, U64},
+};
 use serde::{Deserialize, Serialize};
 
 /// A log produced by a transaction.
@@ -60,6 +63,39 @@ pub struct Log {
     pub removed: Option<bool>,
 }
 
+impl Log {
+    /// Adds the address and the topics of the log to the bloom
+    pub fn accrue_bloom(&self, bloom: &mut Bloom) {
+        bloom.accrue(BloomInput::Raw(self.address.as_bytes()));
+        for topic in &self.topics {
+            bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+        }
+    }
+}
+
+/// Computes the bloom of the given logs, as found in the `logs_bloom` of receipts and blocks
+pub fn logs_bloom<'a>(logs: impl IntoIterator<Item = &'a Log>) -> Bloom {
+    let mut bloom = Bloom::zero();
+    for log in logs {
+        log.accrue_bloom(&mut bloom);
+    }
+    bloom
+}
+
+/// Returns `true` if the bloom may contain a log emitted by `address`.
+///
+/// Blooms may report false positives, but never false negatives.
+pub fn bloom_contains_address(bloom: &Bloom, address: &Address) -> bool {
+    bloom.contains_input(BloomInput::Raw(address.as_bytes()))
+}
+
+/// Returns `true` if the bloom may contain a log with `topic` at any position.
+///
+/// Blooms may report false positives, but never false negatives.
+pub fn bloom_contains_topic(bloom: &Bloom, topic: &H256) -> bool {
+    bloom.contains_input(BloomInput::Raw(topic.as_bytes()))
+}
+
 impl rlp::Encodable for Log {
     fn rlp_append(&self, s: &mut rlp::RlpStream) {
         s.begin_list(3);
@@ -76,3 +112,41 @@ impl From<Log> for RawLog {
 }
 
 // TODO: Implement more common types - or adjust this to work with all Tokenizable items
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logs_bloom_matches_receipt() {
+        // the logs and bloom of the receipt of tx
+        // 0xa3ece39ae137617669c6933b7578b94e705e765683f260fcfe30eaa41932610f
+        let address: Address = "0xd6df5935cd03a768b7b9e92637a01b25e24cb709".parse().unwrap();
+        let topics = |topics: &[&str]| topics.iter().map(|t| t.parse().unwrap()).collect();
+        let logs = vec![
+            Log {
+                address,
+                topics: topics(&[
+                    "0x8940c4b8e215f8822c5c8f0056c12652c746cbc57eedbd2a440b175971d47a77",
+                    "0x000000000000000000000000d907941c8b3b966546fc408b8c942eb10a4f98df",
+                ]),
+                ..Default::default()
+            },
+            Log {
+                address,
+                topics: topics(&[
+                    "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                    "0x0000000000000000000000000000000000000000000000000000000000000000",
+                    "0x000000000000000000000000d907941c8b3b966546fc408b8c942eb10a4f98df",
+                ]),
+                ..Default::default()
+            },
+        ];
+        let bloom: Bloom = "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000008000000000000000000000000000000000000000000000000000000000000008000000000000000000000000000000000000000000000000020000000000000000000800000000000000004010000010100000000000000000000000000000000000000000000000000040000080000000000000080000000000000000000000000000000000000000000020000000000000000000000002000000000000000000000000000000000000000000000000000020000000010000000000000000000000000000000000000000000000000000000000".parse().unwrap();
+
+        assert_eq!(logs_bloom(&logs), bloom);
+        assert!(bloom_contains_address(&bloom, &address));
+        assert!(bloom_contains_topic(&bloom, &logs[0].topics[0]));
+        assert!(!bloom_contains_address(&bloom, &Address::zero()));
+    }
+}
@@ -15,10 +15,15 @@ pub mod transaction;
 pub use transaction::{
     eip1559::Eip1559TransactionRequest,
     eip2930::Eip2930TransactionRequest,
+    eip4844::{BlobTransactionSidecar, Eip4844TransactionRequest},
     request::TransactionRequest,
     response::{Transaction, TransactionReceipt},
 };
 
+#[cfg(feature = "optimism")]
+#[cfg_attr(docsrs, doc(cfg(feature = "optimism")))]
+pub use transaction::optimism::DepositTransaction;
+
 mod address_or_bytes;
 pub use address_or_bytes::AddressOrBytes;
 
@@ -38,13 +43,18 @@ mod bytes;
 pub use self::bytes::{deserialize_bytes, serialize_bytes, Bytes, ParseBytesError};
 
 mod block;
-pub use block::{Block, BlockId, BlockNumber, TimeError};
+pub use block::{calc_blob_gasprice, calc_excess_blob_gas, Block, BlockId, BlockNumber, TimeError};
 
 #[cfg(feature = "celo")]
 pub use block::Randomness;
 
+#[cfg(not(feature = "celo"))]
+mod header;
+#[cfg(not(feature = "celo"))]
+pub use header::{Header, HeaderError};
+
 mod log;
-pub use log::Log;
+pub use log::{bloom_contains_address, bloom_contains_topic, logs_bloom, Log};
 
 mod filter;
 pub use filter::*;
@@ -84,3 +94,8 @@ pub use opcode::Opcode;
 
 mod withdrawal;
 pub use withdrawal::Withdrawal;
+
+mod user_operation;
+pub use user_operation::{
+    UserOperation, UserOperationGasEstimate, UserOperationReceipt, ENTRY_POINT_V06_ADDRESS,
+};
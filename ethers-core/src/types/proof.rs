@@ -1,4 +1,7 @@
-use crate::types::{Address, Bytes, H256, U256, U64};
+use crate::{
+    trie::{verify_account_proof, verify_storage_proof, ProofError},
+    types::{Address, Bytes, H256, U256, U64},
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -20,6 +23,21 @@ pub struct EIP1186ProofResponse {
     pub storage_proof: Vec<StorageProof>,
 }
 
+impl StorageProof {
+    /// Verifies the proof against the storage root of the account it belongs to.
+    pub fn verify(&self, storage_root: H256) -> Result<(), ProofError> {
+        verify_storage_proof(storage_root, self)
+    }
+}
+
+impl EIP1186ProofResponse {
+    /// Verifies the account proof against the `state_root` of the block the proof was requested
+    /// for, as well as all storage proofs against the account's `storage_hash`.
+    pub fn verify(&self, state_root: H256) -> Result<(), ProofError> {
+        verify_account_proof(state_root, self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
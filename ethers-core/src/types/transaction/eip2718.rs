@@ -1,6 +1,9 @@
+#[cfg(feature = "optimism")]
+use super::optimism::{DepositTransaction, DepositTransactionError, DEPOSIT_TX_TYPE};
 use super::{
     eip1559::{Eip1559RequestError, Eip1559TransactionRequest},
     eip2930::{AccessList, Eip2930RequestError, Eip2930TransactionRequest},
+    eip4844::{Eip4844RequestError, Eip4844TransactionRequest},
     request::RequestError,
 };
 use crate::{
@@ -19,6 +22,8 @@ use thiserror::Error;
 /// 1. Legacy (pre-EIP2718) [`TransactionRequest`]
 /// 2. EIP2930 (state access lists) [`Eip2930TransactionRequest`]
 /// 3. EIP1559 [`Eip1559TransactionRequest`]
+/// 4. EIP4844 (blob transactions) [`Eip4844TransactionRequest`]
+/// 5. OP-stack deposit transactions [`DepositTransaction`], if the `optimism` feature is enabled
 ///
 /// To support Kovan and other non-London-compatbile networks, please enable
 /// the `legacy` crate feature. This will disable the `type` flag in the
@@ -37,6 +42,14 @@ pub enum TypedTransaction {
     // 0x02
     #[serde(rename = "0x02")]
     Eip1559(Eip1559TransactionRequest),
+    // 0x03
+    #[serde(rename = "0x03")]
+    Eip4844(Eip4844TransactionRequest),
+    // 0x7E
+    #[cfg(feature = "optimism")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "optimism")))]
+    #[serde(rename = "0x7E", alias = "0x7e")]
+    DepositTransaction(DepositTransaction),
 }
 
 /// An error involving a typed transaction request.
@@ -51,6 +64,13 @@ pub enum TypedTransactionError {
     /// When decoding a signed Eip2930 transaction
     #[error(transparent)]
     Eip2930Error(#[from] Eip2930RequestError),
+    /// When decoding a signed Eip4844 transaction
+    #[error(transparent)]
+    Eip4844Error(#[from] Eip4844RequestError),
+    /// When decoding an OP-stack deposit transaction
+    #[cfg(feature = "optimism")]
+    #[error(transparent)]
+    DepositTransactionError(#[from] DepositTransactionError),
     /// Error decoding the transaction type from the transaction's RLP encoding
     #[error(transparent)]
     TypeDecodingError(#[from] rlp::DecoderError),
@@ -84,6 +104,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.from.as_ref(),
             Eip2930(inner) => inner.tx.from.as_ref(),
             Eip1559(inner) => inner.from.as_ref(),
+            Eip4844(inner) => inner.tx.from.as_ref(),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.from.as_ref(),
         }
     }
 
@@ -92,6 +115,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.from = Some(from),
             Eip2930(inner) => inner.tx.from = Some(from),
             Eip1559(inner) => inner.from = Some(from),
+            Eip4844(inner) => inner.tx.from = Some(from),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.from = Some(from),
         };
         self
     }
@@ -101,6 +127,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.to.as_ref(),
             Eip2930(inner) => inner.tx.to.as_ref(),
             Eip1559(inner) => inner.to.as_ref(),
+            Eip4844(inner) => inner.tx.to.as_ref(),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.to.as_ref(),
         }
     }
 
@@ -114,6 +143,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.to = Some(to),
             Eip2930(inner) => inner.tx.to = Some(to),
             Eip1559(inner) => inner.to = Some(to),
+            Eip4844(inner) => inner.tx.to = Some(to),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.to = Some(to),
         };
         self
     }
@@ -123,6 +155,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.nonce.as_ref(),
             Eip2930(inner) => inner.tx.nonce.as_ref(),
             Eip1559(inner) => inner.nonce.as_ref(),
+            Eip4844(inner) => inner.tx.nonce.as_ref(),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.nonce.as_ref(),
         }
     }
 
@@ -132,6 +167,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.nonce = Some(nonce),
             Eip2930(inner) => inner.tx.nonce = Some(nonce),
             Eip1559(inner) => inner.nonce = Some(nonce),
+            Eip4844(inner) => inner.tx.nonce = Some(nonce),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.nonce = Some(nonce),
         };
         self
     }
@@ -141,6 +179,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.value.as_ref(),
             Eip2930(inner) => inner.tx.value.as_ref(),
             Eip1559(inner) => inner.value.as_ref(),
+            Eip4844(inner) => inner.tx.value.as_ref(),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.value.as_ref(),
         }
     }
 
@@ -150,6 +191,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.value = Some(value),
             Eip2930(inner) => inner.tx.value = Some(value),
             Eip1559(inner) => inner.value = Some(value),
+            Eip4844(inner) => inner.tx.value = Some(value),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.value = Some(value),
         };
         self
     }
@@ -159,6 +203,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.gas.as_ref(),
             Eip2930(inner) => inner.tx.gas.as_ref(),
             Eip1559(inner) => inner.gas.as_ref(),
+            Eip4844(inner) => inner.tx.gas.as_ref(),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.gas.as_ref(),
         }
     }
 
@@ -167,6 +214,9 @@ impl TypedTransaction {
             Legacy(inner) => &mut inner.gas,
             Eip2930(inner) => &mut inner.tx.gas,
             Eip1559(inner) => &mut inner.gas,
+            Eip4844(inner) => &mut inner.tx.gas,
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => &mut inner.tx.gas,
         }
     }
 
@@ -176,6 +226,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.gas = Some(gas),
             Eip2930(inner) => inner.tx.gas = Some(gas),
             Eip1559(inner) => inner.gas = Some(gas),
+            Eip4844(inner) => inner.tx.gas = Some(gas),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.gas = Some(gas),
         };
         self
     }
@@ -184,7 +237,9 @@ impl TypedTransaction {
         match self {
             Legacy(inner) => inner.gas_price,
             Eip2930(inner) => inner.tx.gas_price,
-            Eip1559(inner) => {
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.gas_price,
+            Eip1559(inner) | Eip4844(Eip4844TransactionRequest { tx: inner, .. }) => {
                 match (inner.max_fee_per_gas, inner.max_priority_fee_per_gas) {
                     (Some(max_fee), Some(_)) => Some(max_fee),
                     // this also covers the None, None case
@@ -200,7 +255,9 @@ impl TypedTransaction {
         match self {
             Legacy(inner) => inner.gas_price = Some(gas_price),
             Eip2930(inner) => inner.tx.gas_price = Some(gas_price),
-            Eip1559(inner) => {
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.gas_price = Some(gas_price),
+            Eip1559(inner) | Eip4844(Eip4844TransactionRequest { tx: inner, .. }) => {
                 inner.max_fee_per_gas = Some(gas_price);
                 inner.max_priority_fee_per_gas = Some(gas_price);
             }
@@ -213,6 +270,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.chain_id,
             Eip2930(inner) => inner.tx.chain_id,
             Eip1559(inner) => inner.chain_id,
+            Eip4844(inner) => inner.tx.chain_id,
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.chain_id,
         }
     }
 
@@ -222,6 +282,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.chain_id = Some(chain_id),
             Eip2930(inner) => inner.tx.chain_id = Some(chain_id),
             Eip1559(inner) => inner.chain_id = Some(chain_id),
+            Eip4844(inner) => inner.tx.chain_id = Some(chain_id),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.chain_id = Some(chain_id),
         };
         self
     }
@@ -231,6 +294,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.data.as_ref(),
             Eip2930(inner) => inner.tx.data.as_ref(),
             Eip1559(inner) => inner.data.as_ref(),
+            Eip4844(inner) => inner.tx.data.as_ref(),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.data.as_ref(),
         }
     }
 
@@ -239,6 +305,9 @@ impl TypedTransaction {
             Legacy(_) => None,
             Eip2930(inner) => Some(&inner.access_list),
             Eip1559(inner) => Some(&inner.access_list),
+            Eip4844(inner) => Some(&inner.tx.access_list),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(_) => None,
         }
     }
 
@@ -247,6 +316,9 @@ impl TypedTransaction {
             Legacy(_) => {}
             Eip2930(inner) => inner.access_list = access_list,
             Eip1559(inner) => inner.access_list = access_list,
+            Eip4844(inner) => inner.tx.access_list = access_list,
+            #[cfg(feature = "optimism")]
+            DepositTransaction(_) => {}
         };
         self
     }
@@ -256,6 +328,9 @@ impl TypedTransaction {
             Legacy(inner) => inner.data = Some(data),
             Eip2930(inner) => inner.tx.data = Some(data),
             Eip1559(inner) => inner.data = Some(data),
+            Eip4844(inner) => inner.tx.data = Some(data),
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => inner.tx.data = Some(data),
         };
         self
     }
@@ -274,10 +349,36 @@ impl TypedTransaction {
                 encoded.extend_from_slice(&[0x2]);
                 encoded.extend_from_slice(inner.rlp_signed(signature).as_ref());
             }
+            Eip4844(inner) => {
+                encoded.extend_from_slice(&[0x3]);
+                encoded.extend_from_slice(inner.rlp_signed(signature).as_ref());
+            }
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => {
+                encoded.extend_from_slice(&[DEPOSIT_TX_TYPE]);
+                encoded.extend_from_slice(inner.rlp_signed(signature).as_ref());
+            }
         };
         encoded.into()
     }
 
+    /// Produces the encoding of the signed transaction as it is submitted to the network, e.g.
+    /// via `eth_sendRawTransaction`.
+    ///
+    /// This only differs from [`Self::rlp_signed`] for EIP-4844 transactions carrying a
+    /// [sidecar](super::eip4844::BlobTransactionSidecar), which is appended to the transaction
+    /// but not part of its hash.
+    pub fn rlp_signed_network(&self, signature: &Signature) -> Bytes {
+        match self {
+            Eip4844(inner) => {
+                let mut encoded = vec![0x3];
+                encoded.extend_from_slice(inner.rlp_signed_network(signature).as_ref());
+                encoded.into()
+            }
+            _ => self.rlp_signed(signature),
+        }
+    }
+
     pub fn rlp(&self) -> Bytes {
         let mut encoded = vec![];
         match self {
@@ -292,6 +393,15 @@ impl TypedTransaction {
                 encoded.extend_from_slice(&[0x2]);
                 encoded.extend_from_slice(inner.rlp().as_ref());
             }
+            Eip4844(inner) => {
+                encoded.extend_from_slice(&[0x3]);
+                encoded.extend_from_slice(inner.rlp().as_ref());
+            }
+            #[cfg(feature = "optimism")]
+            DepositTransaction(inner) => {
+                encoded.extend_from_slice(&[DEPOSIT_TX_TYPE]);
+                encoded.extend_from_slice(inner.rlp().as_ref());
+            }
         };
 
         encoded.into()
@@ -343,6 +453,17 @@ impl TypedTransaction {
             let decoded_request = Eip1559TransactionRequest::decode_signed_rlp(&rest)?;
             return Ok((Self::Eip1559(decoded_request.0), decoded_request.1))
         }
+        if first == 0x03 {
+            // EIP-4844 (0x03)
+            let decoded_request = Eip4844TransactionRequest::decode_signed_rlp(&rest)?;
+            return Ok((Self::Eip4844(decoded_request.0), decoded_request.1))
+        }
+        #[cfg(feature = "optimism")]
+        if first == DEPOSIT_TX_TYPE {
+            // OP-stack deposit (0x7E)
+            let decoded_request = DepositTransaction::decode_signed_rlp(&rest)?;
+            return Ok((Self::DepositTransaction(decoded_request.0), decoded_request.1))
+        }
 
         Err(rlp::DecoderError::Custom("invalid tx type").into())
     }
@@ -368,6 +489,15 @@ impl Decodable for TypedTransaction {
                 // EIP-1559 (0x02)
                 Ok(Self::Eip1559(Eip1559TransactionRequest::decode(&rest)?))
             }
+            Some(x) if x == U64::from(3) => {
+                // EIP-4844 (0x03)
+                Ok(Self::Eip4844(Eip4844TransactionRequest::decode(&rest)?))
+            }
+            #[cfg(feature = "optimism")]
+            Some(x) if x == U64::from(DEPOSIT_TX_TYPE) => {
+                // OP-stack deposit (0x7E)
+                Ok(Self::DepositTransaction(DepositTransaction::decode(&rest)?))
+            }
             _ => {
                 // Legacy (0x00)
                 // use the original rlp
@@ -395,6 +525,19 @@ impl From<Eip1559TransactionRequest> for TypedTransaction {
     }
 }
 
+impl From<Eip4844TransactionRequest> for TypedTransaction {
+    fn from(src: Eip4844TransactionRequest) -> TypedTransaction {
+        TypedTransaction::Eip4844(src)
+    }
+}
+
+#[cfg(feature = "optimism")]
+impl From<DepositTransaction> for TypedTransaction {
+    fn from(src: DepositTransaction) -> TypedTransaction {
+        TypedTransaction::DepositTransaction(src)
+    }
+}
+
 impl From<&Transaction> for TypedTransaction {
     fn from(tx: &Transaction) -> TypedTransaction {
         match tx.transaction_type {
@@ -408,6 +551,17 @@ impl From<&Transaction> for TypedTransaction {
                 let request: Eip1559TransactionRequest = tx.into();
                 request.into()
             }
+            // EIP-4844 (0x03)
+            Some(x) if x == U64::from(3) => {
+                let request: Eip4844TransactionRequest = tx.into();
+                request.into()
+            }
+            // OP-stack deposit (0x7E)
+            #[cfg(feature = "optimism")]
+            Some(x) if x == U64::from(DEPOSIT_TX_TYPE) => {
+                let request: DepositTransaction = tx.into();
+                request.into()
+            }
             // Legacy (0x00)
             _ => {
                 let request: TransactionRequest = tx.into();
@@ -436,6 +590,19 @@ impl TypedTransaction {
             _ => None,
         }
     }
+    pub fn as_eip4844_ref(&self) -> Option<&Eip4844TransactionRequest> {
+        match self {
+            Eip4844(tx) => Some(tx),
+            _ => None,
+        }
+    }
+    #[cfg(feature = "optimism")]
+    pub fn as_deposit_ref(&self) -> Option<&DepositTransaction> {
+        match self {
+            DepositTransaction(tx) => Some(tx),
+            _ => None,
+        }
+    }
 
     pub fn as_legacy_mut(&mut self) -> Option<&mut TransactionRequest> {
         match self {
@@ -455,12 +622,26 @@ impl TypedTransaction {
             _ => None,
         }
     }
+    pub fn as_eip4844_mut(&mut self) -> Option<&mut Eip4844TransactionRequest> {
+        match self {
+            Eip4844(tx) => Some(tx),
+            _ => None,
+        }
+    }
+    #[cfg(feature = "optimism")]
+    pub fn as_deposit_mut(&mut self) -> Option<&mut DepositTransaction> {
+        match self {
+            DepositTransaction(tx) => Some(tx),
+            _ => None,
+        }
+    }
 }
 
 impl TypedTransaction {
     fn into_eip1559(self) -> Eip1559TransactionRequest {
         match self {
             Eip1559(tx) => tx,
+            Eip4844(tx) => tx.tx,
             _ => Eip1559TransactionRequest {
                 from: self.from().copied(),
                 to: self.to().cloned(),
@@ -487,7 +668,9 @@ impl TypedTransaction {
         match self {
             Legacy(tx) => tx,
             Eip2930(tx) => tx.tx,
-            Eip1559(_) => TransactionRequest {
+            #[cfg(feature = "optimism")]
+            DepositTransaction(tx) => tx.tx,
+            Eip1559(_) | Eip4844(_) => TransactionRequest {
                 from: self.from().copied(),
                 to: self.to().cloned(),
                 nonce: self.nonce().copied(),
@@ -523,7 +706,9 @@ impl TypedTransaction {
         match self {
             Eip2930(tx) => tx,
             Legacy(tx) => Eip2930TransactionRequest { tx, access_list },
-            Eip1559(_) => Eip2930TransactionRequest {
+            #[cfg(feature = "optimism")]
+            DepositTransaction(tx) => Eip2930TransactionRequest { tx: tx.tx, access_list },
+            Eip1559(_) | Eip4844(_) => Eip2930TransactionRequest {
                 tx: TransactionRequest {
                     from: self.from().copied(),
                     to: self.to().cloned(),
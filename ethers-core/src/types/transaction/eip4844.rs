@@ -0,0 +1,484 @@
+//! [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844) blob transactions
+use super::{eip1559::Eip1559TransactionRequest, eip2718::TypedTransaction, normalize_v, rlp_opt};
+use crate::types::{Bytes, Signature, SignatureError, Transaction, H256, U256, U64};
+use rlp::{Decodable, DecoderError, RlpStream};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// EIP-4844 transactions have 11 fields
+const NUM_TX_FIELDS: usize = 11;
+
+/// The network encoding of a blob transaction wraps the transaction and the 3 sidecar fields
+const NUM_NETWORK_FIELDS: usize = 4;
+
+/// The version byte of a versioned hash derived from a KZG commitment
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// The number of field elements in a blob
+pub const FIELD_ELEMENTS_PER_BLOB: usize = 4096;
+
+/// The size of a blob in bytes
+pub const BYTES_PER_BLOB: usize = FIELD_ELEMENTS_PER_BLOB * 32;
+
+/// The size of a KZG commitment in bytes
+pub const BYTES_PER_COMMITMENT: usize = 48;
+
+/// The size of a KZG proof in bytes
+pub const BYTES_PER_PROOF: usize = 48;
+
+/// The gas consumed by a single blob
+pub const GAS_PER_BLOB: U256 = U256([131_072u64, 0, 0, 0]);
+
+/// The multiplier applied to the next block's blob fee when filling `max_fee_per_blob_gas`, which
+/// keeps the transaction includable through a few consecutive blob fee increases.
+pub const BLOB_FEE_SURGE_MULTIPLIER: u64 = 2;
+
+/// An error involving an EIP4844 transaction request.
+#[derive(Debug, Error)]
+pub enum Eip4844RequestError {
+    /// When decoding a transaction request from RLP
+    #[error(transparent)]
+    DecodingError(#[from] rlp::DecoderError),
+    /// When recovering the address from a signature
+    #[error(transparent)]
+    RecoveryError(#[from] SignatureError),
+}
+
+/// An error returned by [`BlobTransactionSidecar::validate`].
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BlobSidecarError {
+    /// The number of blobs, commitments and proofs differ
+    #[error("sidecar has {blobs} blobs, {commitments} commitments and {proofs} proofs")]
+    LengthMismatch { blobs: usize, commitments: usize, proofs: usize },
+    /// A blob does not have the size of [`BYTES_PER_BLOB`]
+    #[error("blob {0} has an invalid size of {1} bytes")]
+    InvalidBlob(usize, usize),
+    /// A commitment does not have the size of [`BYTES_PER_COMMITMENT`]
+    #[error("commitment {0} has an invalid size of {1} bytes")]
+    InvalidCommitment(usize, usize),
+    /// A proof does not have the size of [`BYTES_PER_PROOF`]
+    #[error("proof {0} has an invalid size of {1} bytes")]
+    InvalidProof(usize, usize),
+    /// The versioned hashes of the transaction do not match the sidecar's commitments
+    #[error("blob versioned hashes do not match the sidecar's commitments")]
+    VersionedHashMismatch,
+}
+
+/// Computes the versioned hash of a KZG commitment, i.e. `0x01 || sha256(commitment)[1..]`
+pub fn kzg_to_versioned_hash(commitment: &[u8]) -> H256 {
+    let mut hash: [u8; 32] = Sha256::digest(commitment).into();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    H256(hash)
+}
+
+/// The blobs, KZG commitments and KZG proofs of a blob transaction.
+///
+/// The sidecar is not part of the signed transaction and does not affect its hash, it is only
+/// included in the network encoding used when submitting the transaction, see
+/// [`Eip4844TransactionRequest::rlp_signed_network`].
+///
+/// Note: the KZG commitments and proofs are not verified against the blobs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobTransactionSidecar {
+    /// The blobs, each [`BYTES_PER_BLOB`] bytes long
+    pub blobs: Vec<Bytes>,
+    /// The KZG commitments of the blobs
+    pub commitments: Vec<Bytes>,
+    /// The KZG proofs of the blobs
+    pub proofs: Vec<Bytes>,
+}
+
+impl BlobTransactionSidecar {
+    /// Creates a new sidecar from the provided blobs, commitments and proofs
+    pub fn new(blobs: Vec<Bytes>, commitments: Vec<Bytes>, proofs: Vec<Bytes>) -> Self {
+        Self { blobs, commitments, proofs }
+    }
+
+    /// Returns the versioned hashes of the sidecar's commitments
+    pub fn versioned_hashes(&self) -> Vec<H256> {
+        self.commitments.iter().map(|c| kzg_to_versioned_hash(c.as_ref())).collect()
+    }
+
+    /// Checks that the sidecar is well-formed, i.e. it has as many blobs as commitments and
+    /// proofs, and all of them have the expected size.
+    pub fn validate(&self) -> Result<(), BlobSidecarError> {
+        let (blobs, commitments, proofs) =
+            (self.blobs.len(), self.commitments.len(), self.proofs.len());
+        if blobs != commitments || blobs != proofs {
+            return Err(BlobSidecarError::LengthMismatch { blobs, commitments, proofs })
+        }
+        if let Some((i, blob)) =
+            self.blobs.iter().enumerate().find(|(_, b)| b.len() != BYTES_PER_BLOB)
+        {
+            return Err(BlobSidecarError::InvalidBlob(i, blob.len()))
+        }
+        if let Some((i, c)) =
+            self.commitments.iter().enumerate().find(|(_, c)| c.len() != BYTES_PER_COMMITMENT)
+        {
+            return Err(BlobSidecarError::InvalidCommitment(i, c.len()))
+        }
+        if let Some((i, p)) =
+            self.proofs.iter().enumerate().find(|(_, p)| p.len() != BYTES_PER_PROOF)
+        {
+            return Err(BlobSidecarError::InvalidProof(i, p.len()))
+        }
+        Ok(())
+    }
+
+    fn rlp_append(&self, rlp: &mut RlpStream) {
+        for list in [&self.blobs, &self.commitments, &self.proofs] {
+            rlp.begin_list(list.len());
+            for item in list {
+                rlp.append(&item.as_ref());
+            }
+        }
+    }
+
+    /// Decodes the sidecar fields starting at the RLP offset passed. Increments the offset for
+    /// each element parsed.
+    fn decode_rlp(rlp: &rlp::Rlp, offset: &mut usize) -> Result<Self, DecoderError> {
+        let mut decode_list = || -> Result<Vec<Bytes>, DecoderError> {
+            let list: Vec<Vec<u8>> = rlp.list_at(*offset)?;
+            *offset += 1;
+            Ok(list.into_iter().map(Bytes::from).collect())
+        };
+        Ok(Self { blobs: decode_list()?, commitments: decode_list()?, proofs: decode_list()? })
+    }
+}
+
+/// An EIP-4844 transaction is an EIP-1559 transaction which additionally carries blobs,
+/// referenced by their [versioned hashes](kzg_to_versioned_hash).
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct Eip4844TransactionRequest {
+    #[serde(flatten)]
+    pub tx: Eip1559TransactionRequest,
+
+    /// The maximum fee per unit of blob gas the sender is willing to pay
+    #[serde(rename = "maxFeePerBlobGas", default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_blob_gas: Option<U256>,
+
+    /// The versioned hashes of the blobs carried by the transaction
+    #[serde(rename = "blobVersionedHashes", default)]
+    pub blob_versioned_hashes: Vec<H256>,
+
+    /// The blobs, commitments and proofs of the transaction. Not part of the signed transaction
+    #[serde(flatten, default, skip_serializing_if = "Option::is_none")]
+    pub sidecar: Option<BlobTransactionSidecar>,
+}
+
+impl Eip4844TransactionRequest {
+    /// Creates a blob transaction request from the provided EIP-1559 transaction request
+    pub fn new(tx: Eip1559TransactionRequest) -> Self {
+        Self { tx, ..Default::default() }
+    }
+
+    // Builder pattern helpers
+
+    /// Sets the `max_fee_per_blob_gas` field in the transaction to the provided value
+    #[must_use]
+    pub fn max_fee_per_blob_gas<T: Into<U256>>(mut self, max_fee_per_blob_gas: T) -> Self {
+        self.max_fee_per_blob_gas = Some(max_fee_per_blob_gas.into());
+        self
+    }
+
+    /// Sets the `blob_versioned_hashes` field in the transaction to the provided value
+    #[must_use]
+    pub fn blob_versioned_hashes<T: Into<Vec<H256>>>(mut self, blob_versioned_hashes: T) -> Self {
+        self.blob_versioned_hashes = blob_versioned_hashes.into();
+        self
+    }
+
+    /// Sets the sidecar of the transaction and the `blob_versioned_hashes` derived from its
+    /// commitments
+    #[must_use]
+    pub fn sidecar(mut self, sidecar: BlobTransactionSidecar) -> Self {
+        self.blob_versioned_hashes = sidecar.versioned_hashes();
+        self.sidecar = Some(sidecar);
+        self
+    }
+
+    /// The total amount of blob gas consumed by the transaction
+    pub fn blob_gas(&self) -> U256 {
+        GAS_PER_BLOB * self.blob_versioned_hashes.len()
+    }
+
+    /// Validates the sidecar, if any, and checks that it matches the `blob_versioned_hashes`
+    pub fn validate_sidecar(&self) -> Result<(), BlobSidecarError> {
+        if let Some(ref sidecar) = self.sidecar {
+            sidecar.validate()?;
+            if sidecar.versioned_hashes() != self.blob_versioned_hashes {
+                return Err(BlobSidecarError::VersionedHashMismatch)
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets the unsigned transaction's RLP encoding
+    pub fn rlp(&self) -> Bytes {
+        let mut rlp = RlpStream::new();
+        rlp.begin_list(NUM_TX_FIELDS);
+        self.rlp_base(&mut rlp);
+        rlp.out().freeze().into()
+    }
+
+    /// Produces the RLP encoding of the transaction with the provided signature.
+    ///
+    /// This does not include the sidecar, see [`Self::rlp_signed_network`].
+    pub fn rlp_signed(&self, signature: &Signature) -> Bytes {
+        let mut rlp = RlpStream::new();
+        self.rlp_append_signed(&mut rlp, signature);
+        rlp.out().freeze().into()
+    }
+
+    /// Produces the network encoding of the transaction with the provided signature, i.e. the
+    /// signed transaction followed by the blobs, commitments and proofs of its sidecar.
+    ///
+    /// Falls back to [`Self::rlp_signed`] if the transaction has no sidecar.
+    pub fn rlp_signed_network(&self, signature: &Signature) -> Bytes {
+        let Some(ref sidecar) = self.sidecar else { return self.rlp_signed(signature) };
+
+        let mut rlp = RlpStream::new();
+        rlp.begin_list(NUM_NETWORK_FIELDS);
+        self.rlp_append_signed(&mut rlp, signature);
+        sidecar.rlp_append(&mut rlp);
+        rlp.out().freeze().into()
+    }
+
+    fn rlp_append_signed(&self, rlp: &mut RlpStream, signature: &Signature) {
+        rlp.begin_list(NUM_TX_FIELDS + 3);
+        self.rlp_base(rlp);
+
+        // if the chain_id is none we assume mainnet and choose one
+        let chain_id = self.tx.chain_id.unwrap_or_else(U64::one);
+
+        // append the signature
+        let v = normalize_v(signature.v, chain_id);
+        rlp.append(&v);
+        rlp.append(&signature.r);
+        rlp.append(&signature.s);
+    }
+
+    pub(crate) fn rlp_base(&self, rlp: &mut RlpStream) {
+        self.tx.rlp_base(rlp);
+        rlp_opt(rlp, &self.max_fee_per_blob_gas);
+        rlp.append_list(&self.blob_versioned_hashes);
+    }
+
+    /// Decodes fields of the request starting at the RLP offset passed. Increments the offset for
+    /// each element parsed.
+    #[inline]
+    pub fn decode_base_rlp(rlp: &rlp::Rlp, offset: &mut usize) -> Result<Self, DecoderError> {
+        let tx = Eip1559TransactionRequest::decode_base_rlp(rlp, offset)?;
+        let max_fee_per_blob_gas = Some(rlp.val_at(*offset)?);
+        *offset += 1;
+        let blob_versioned_hashes = rlp.list_at(*offset)?;
+        *offset += 1;
+        Ok(Self { tx, max_fee_per_blob_gas, blob_versioned_hashes, sidecar: None })
+    }
+
+    /// Decodes the given RLP into a transaction, attempting to decode its signature as well.
+    ///
+    /// Accepts both the signed transaction and its network encoding, in which case the sidecar
+    /// is decoded as well.
+    pub fn decode_signed_rlp(rlp: &rlp::Rlp) -> Result<(Self, Signature), Eip4844RequestError> {
+        if rlp.at(0)?.is_list() {
+            // network encoding: rlp([tx_payload_body, blobs, commitments, proofs])
+            let (mut txn, sig) = Self::decode_signed_rlp(&rlp.at(0)?)?;
+            txn.sidecar = Some(BlobTransactionSidecar::decode_rlp(rlp, &mut 1)?);
+            return Ok((txn, sig))
+        }
+
+        let mut offset = 0;
+        let mut txn = Self::decode_base_rlp(rlp, &mut offset)?;
+
+        let v = rlp.val_at(offset)?;
+        offset += 1;
+        let r = rlp.val_at(offset)?;
+        offset += 1;
+        let s = rlp.val_at(offset)?;
+
+        let sig = Signature { r, s, v };
+        txn.tx.from = Some(sig.recover(TypedTransaction::Eip4844(txn.clone()).sighash())?);
+
+        Ok((txn, sig))
+    }
+}
+
+impl Decodable for Eip4844TransactionRequest {
+    fn decode(rlp: &rlp::Rlp) -> Result<Self, rlp::DecoderError> {
+        Self::decode_base_rlp(rlp, &mut 0)
+    }
+}
+
+impl From<Eip1559TransactionRequest> for Eip4844TransactionRequest {
+    fn from(tx: Eip1559TransactionRequest) -> Self {
+        Self::new(tx)
+    }
+}
+
+impl From<&Transaction> for Eip4844TransactionRequest {
+    fn from(tx: &Transaction) -> Eip4844TransactionRequest {
+        Eip4844TransactionRequest {
+            tx: tx.into(),
+            max_fee_per_blob_gas: tx.max_fee_per_blob_gas,
+            blob_versioned_hashes: tx.blob_versioned_hashes.clone().unwrap_or_default(),
+            sidecar: None,
+        }
+    }
+}
+
+impl Eip1559TransactionRequest {
+    /// Attaches the provided blob sidecar to the transaction (converts the
+    /// [`Eip1559TransactionRequest`] to an [`Eip4844TransactionRequest`])
+    pub fn with_blobs(self, sidecar: BlobTransactionSidecar) -> Eip4844TransactionRequest {
+        Eip4844TransactionRequest::new(self).sidecar(sidecar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        types::{transaction::eip2930::AccessListItem, Address},
+        utils::keccak256,
+    };
+    use k256::ecdsa::SigningKey;
+    use rlp::Rlp;
+
+    fn sign(tx: &TypedTransaction, key: &SigningKey) -> Signature {
+        let (sig, recid) = key.sign_prehash_recoverable(tx.sighash().as_bytes()).unwrap();
+        let bytes = sig.to_bytes();
+        Signature {
+            r: U256::from_big_endian(&bytes[..32]),
+            s: U256::from_big_endian(&bytes[32..]),
+            v: recid.to_byte() as u64,
+        }
+    }
+
+    fn sidecar() -> BlobTransactionSidecar {
+        BlobTransactionSidecar::new(
+            vec![vec![1u8; BYTES_PER_BLOB].into(), vec![2u8; BYTES_PER_BLOB].into()],
+            vec![vec![3u8; BYTES_PER_COMMITMENT].into(), vec![4u8; BYTES_PER_COMMITMENT].into()],
+            vec![vec![5u8; BYTES_PER_PROOF].into(), vec![6u8; BYTES_PER_PROOF].into()],
+        )
+    }
+
+    fn blob_tx() -> Eip4844TransactionRequest {
+        Eip1559TransactionRequest::new()
+            .chain_id(1u64)
+            .nonce(7u64)
+            .max_priority_fee_per_gas(1_000_000_000u64)
+            .max_fee_per_gas(30_000_000_000u64)
+            .gas(21_000u64)
+            .to(Address::repeat_byte(0x11))
+            .value(1u64)
+            .data(vec![0xde, 0xad])
+            .access_list(vec![AccessListItem {
+                address: Address::repeat_byte(0x22),
+                storage_keys: vec![H256::zero()],
+            }])
+            .with_blobs(sidecar())
+            .max_fee_per_blob_gas(10u64)
+    }
+
+    #[test]
+    fn versioned_hash() {
+        let commitment = [0u8; BYTES_PER_COMMITMENT];
+        let hash = kzg_to_versioned_hash(&commitment);
+        assert_eq!(hash.0[0], VERSIONED_HASH_VERSION_KZG);
+        assert_eq!(hash.0[1..], Sha256::digest(commitment)[1..]);
+    }
+
+    #[test]
+    fn validates_sidecar() {
+        let tx = blob_tx();
+        assert_eq!(tx.blob_versioned_hashes.len(), 2);
+        assert_eq!(tx.blob_gas(), U256::from(262_144u64));
+        tx.validate_sidecar().unwrap();
+
+        let mut bad = tx.clone();
+        bad.blob_versioned_hashes.pop();
+        assert_eq!(bad.validate_sidecar(), Err(BlobSidecarError::VersionedHashMismatch));
+
+        let mut bad = sidecar();
+        bad.proofs.pop();
+        assert_eq!(
+            bad.validate(),
+            Err(BlobSidecarError::LengthMismatch { blobs: 2, commitments: 2, proofs: 1 })
+        );
+
+        let mut bad = sidecar();
+        bad.blobs[1] = vec![0u8; 10].into();
+        assert_eq!(bad.validate(), Err(BlobSidecarError::InvalidBlob(1, 10)));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "legacy", ignore)]
+    fn serde_eip4844_tx() {
+        let mut tx = blob_tx();
+        // the chain id is not serialized
+        tx.tx.chain_id = None;
+        let tx: TypedTransaction = tx.into();
+        let serialized = serde_json::to_value(&tx).unwrap();
+        assert_eq!(serialized["type"], "0x03");
+        assert_eq!(serialized["maxFeePerBlobGas"], "0xa");
+        assert_eq!(serialized["blobs"].as_array().unwrap().len(), 2);
+
+        let de: TypedTransaction = serde_json::from_value(serialized.clone()).unwrap();
+        assert_eq!(tx, de);
+
+        let de: Eip4844TransactionRequest = serde_json::from_value(serialized).unwrap();
+        assert_eq!(tx, TypedTransaction::Eip4844(de));
+
+        // the sidecar is optional
+        let mut tx = tx.as_eip4844_ref().unwrap().clone();
+        tx.sidecar = None;
+        let serialized = serde_json::to_value(&tx).unwrap();
+        assert!(serialized.get("blobs").is_none());
+        let de: Eip4844TransactionRequest = serde_json::from_value(serialized).unwrap();
+        assert_eq!(tx, de);
+    }
+
+    #[test]
+    fn rlp_roundtrip() {
+        let key = SigningKey::from_bytes(&[0x42; 32].into()).unwrap();
+        let from = crate::utils::secret_key_to_address(&key);
+        let tx = blob_tx();
+        let typed: TypedTransaction = tx.clone().into();
+        let sig = sign(&typed, &key);
+
+        // unsigned
+        let unsigned = typed.rlp();
+        assert_eq!(unsigned[0], 0x03);
+        let decoded = TypedTransaction::decode(&Rlp::new(&unsigned)).unwrap();
+        assert_eq!(decoded.sighash(), typed.sighash());
+
+        // signed, without sidecar
+        let signed = typed.rlp_signed(&sig);
+        assert_eq!(signed[0], 0x03);
+        let (decoded, decoded_sig) =
+            TypedTransaction::decode_signed(&Rlp::new(signed.as_ref())).unwrap();
+        assert_eq!(decoded_sig, sig);
+        let decoded = decoded.as_eip4844_ref().unwrap();
+        assert_eq!(decoded.tx.from, Some(from));
+        assert_eq!(decoded.sidecar, None);
+        assert_eq!(decoded.blob_versioned_hashes, tx.blob_versioned_hashes);
+        assert_eq!(decoded.max_fee_per_blob_gas, tx.max_fee_per_blob_gas);
+
+        // network encoding including the sidecar, which does not affect the hash
+        let network = typed.rlp_signed_network(&sig);
+        assert!(network.len() > 2 * BYTES_PER_BLOB);
+        let (decoded, _) = TypedTransaction::decode_signed(&Rlp::new(network.as_ref())).unwrap();
+        assert_eq!(decoded.as_eip4844_ref().unwrap().sidecar, tx.sidecar);
+        assert_eq!(decoded.hash(&sig), typed.hash(&sig));
+        assert_eq!(typed.hash(&sig), H256(keccak256(signed)));
+
+        // decoding the network encoding into a response yields the canonical hash
+        let response: Transaction = rlp::decode(&rlp::encode(&network.as_ref())).unwrap();
+        assert_eq!(response.hash, typed.hash(&sig));
+        assert_eq!(response.transaction_type, Some(3u64.into()));
+        assert_eq!(response.rlp(), typed.rlp_signed(&sig));
+        assert_eq!(response.recover_from().unwrap(), from);
+    }
+}
@@ -4,6 +4,11 @@ pub mod response;
 pub mod eip1559;
 pub mod eip2718;
 pub mod eip2930;
+pub mod eip4844;
+
+#[cfg(feature = "optimism")]
+#[cfg_attr(docsrs, doc(cfg(feature = "optimism")))]
+pub mod optimism;
 
 pub mod eip712;
 
@@ -0,0 +1,228 @@
+//! OP-stack [deposit transactions](https://specs.optimism.io/protocol/deposits.html)
+use super::{decode_to, rlp_opt};
+use crate::types::{Bytes, NameOrAddress, Signature, Transaction, TransactionRequest, H256, U256};
+use rlp::{Decodable, DecoderError, RlpStream};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The transaction type of deposit transactions
+pub const DEPOSIT_TX_TYPE: u8 = 0x7E;
+
+/// Deposit transactions have 8 fields
+const NUM_TX_FIELDS: usize = 8;
+
+/// An error involving an OP-stack deposit transaction request.
+#[derive(Debug, Error)]
+pub enum DepositTransactionError {
+    /// When decoding a transaction request from RLP
+    #[error(transparent)]
+    DecodingError(#[from] rlp::DecoderError),
+}
+
+/// A deposit transaction is derived from L1 by the OP-stack rollup node. It is not signed, its
+/// sender is part of the transaction instead.
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct DepositTransaction {
+    #[serde(flatten)]
+    pub tx: TransactionRequest,
+
+    /// The hash which uniquely identifies the origin of the deposit
+    #[serde(rename = "sourceHash")]
+    pub source_hash: H256,
+
+    /// The ETH value to mint on L2
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mint: Option<U256>,
+
+    /// Whether the transaction is exempt from the L2 gas limit
+    #[serde(rename = "isSystemTx", default)]
+    pub is_system_tx: bool,
+}
+
+impl DepositTransaction {
+    /// Creates a deposit transaction from the provided request and deposit fields
+    pub fn new(
+        tx: TransactionRequest,
+        source_hash: H256,
+        mint: Option<U256>,
+        is_system_tx: bool,
+    ) -> Self {
+        Self { tx, source_hash, mint, is_system_tx }
+    }
+
+    /// Gets the transaction's RLP encoding. Deposit transactions are not signed, so this is also
+    /// the encoding the transaction hash commits to.
+    pub fn rlp(&self) -> Bytes {
+        let mut rlp = RlpStream::new();
+        rlp.begin_list(NUM_TX_FIELDS);
+        rlp.append(&self.source_hash);
+        rlp_opt(&mut rlp, &self.tx.from);
+        rlp_opt(&mut rlp, &self.tx.to.as_ref());
+        rlp_opt(&mut rlp, &self.mint);
+        rlp_opt(&mut rlp, &self.tx.value);
+        rlp_opt(&mut rlp, &self.tx.gas);
+        rlp.append(&self.is_system_tx);
+        rlp_opt(&mut rlp, &self.tx.data.as_ref().map(|d| d.as_ref()));
+        rlp.out().freeze().into()
+    }
+
+    /// Produces the RLP encoding of the transaction, ignoring the provided signature since deposit
+    /// transactions are not signed
+    pub fn rlp_signed(&self, _signature: &Signature) -> Bytes {
+        self.rlp()
+    }
+
+    /// Decodes fields based on the RLP offset passed. Increments the offset for each element
+    /// parsed.
+    fn decode_base_rlp(rlp: &rlp::Rlp, offset: &mut usize) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != NUM_TX_FIELDS {
+            return Err(DecoderError::RlpIncorrectListLen)
+        }
+
+        let source_hash = rlp.val_at(*offset)?;
+        *offset += 1;
+
+        let mut tx = TransactionRequest::new();
+        tx.from = Some(rlp.val_at(*offset)?);
+        *offset += 1;
+        tx.to = decode_to(rlp, offset)?.map(NameOrAddress::Address);
+        // `None` is encoded as an empty string, like zero, so this decodes both as `None`
+        let mint = {
+            let mint = rlp.at(*offset)?;
+            if mint.is_data() && mint.is_empty() {
+                None
+            } else {
+                Some(mint.as_val()?)
+            }
+        };
+        *offset += 1;
+        tx.value = Some(rlp.val_at(*offset)?);
+        *offset += 1;
+        tx.gas = Some(rlp.val_at(*offset)?);
+        *offset += 1;
+        let is_system_tx = rlp.val_at(*offset)?;
+        *offset += 1;
+        let data = rlp::Rlp::new(rlp.at(*offset)?.as_raw()).data()?;
+        tx.data = match data.len() {
+            0 => None,
+            _ => Some(Bytes::from(data.to_vec())),
+        };
+        *offset += 1;
+
+        Ok(Self { tx, source_hash, mint, is_system_tx })
+    }
+
+    /// Decodes the given RLP into a transaction.
+    ///
+    /// Deposit transactions are not signed, so the returned signature is always empty.
+    pub fn decode_signed_rlp(rlp: &rlp::Rlp) -> Result<(Self, Signature), DepositTransactionError> {
+        let tx = Self::decode_base_rlp(rlp, &mut 0)?;
+        Ok((tx, Signature { r: U256::zero(), s: U256::zero(), v: 0 }))
+    }
+}
+
+/// Get a DepositTransaction from a rlp encoded byte stream
+impl Decodable for DepositTransaction {
+    fn decode(rlp: &rlp::Rlp) -> Result<Self, rlp::DecoderError> {
+        Self::decode_base_rlp(rlp, &mut 0)
+    }
+}
+
+impl From<&Transaction> for DepositTransaction {
+    fn from(tx: &Transaction) -> DepositTransaction {
+        DepositTransaction {
+            tx: tx.into(),
+            source_hash: tx.source_hash.unwrap_or_default(),
+            mint: tx.mint,
+            is_system_tx: tx.is_system_tx.unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        types::{transaction::eip2718::TypedTransaction, Address},
+        utils::keccak256,
+    };
+
+    fn deposit() -> DepositTransaction {
+        DepositTransaction::new(
+            TransactionRequest::new()
+                .from(Address::repeat_byte(0x22))
+                .to(Address::repeat_byte(0x33))
+                .value(1u64)
+                .gas(21_000u64),
+            H256::repeat_byte(0x11),
+            None,
+            false,
+        )
+    }
+
+    #[test]
+    fn rlp_deposit_tx() {
+        let tx: TypedTransaction = deposit().into();
+
+        let mut expected = hex::decode("7ef852a0").unwrap();
+        expected.extend([0x11; 32]);
+        expected.push(0x94);
+        expected.extend([0x22; 20]);
+        expected.push(0x94);
+        expected.extend([0x33; 20]);
+        // mint, value, gas, is_system_tx, data
+        expected.extend(hex::decode("80018252088080").unwrap());
+
+        let sig = Signature { r: U256::zero(), s: U256::zero(), v: 0 };
+        assert_eq!(tx.rlp(), Bytes::from(expected.clone()));
+        assert_eq!(tx.rlp_signed(&sig), Bytes::from(expected.clone()));
+        assert_eq!(tx.hash(&sig), H256(keccak256(&expected)));
+
+        let (decoded, decoded_sig) =
+            TypedTransaction::decode_signed(&rlp::Rlp::new(&expected)).unwrap();
+        assert_eq!(decoded, tx);
+        assert_eq!(decoded_sig, sig);
+
+        let decoded: Transaction = rlp::decode(&expected).unwrap();
+        assert_eq!(decoded.hash, tx.hash(&sig));
+        assert_eq!(decoded.hash(), tx.hash(&sig));
+        assert_eq!(decoded.source_hash, Some(H256::repeat_byte(0x11)));
+        assert_eq!(decoded.recover_from().unwrap(), Address::repeat_byte(0x22));
+    }
+
+    #[test]
+    fn rlp_deposit_tx_mint() {
+        let mut tx = deposit();
+        let decoded: DepositTransaction = rlp::decode(&tx.rlp()).unwrap();
+        assert_eq!(decoded.mint, None);
+
+        tx.mint = Some(U256::exp10(18));
+        let decoded: DepositTransaction = rlp::decode(&tx.rlp()).unwrap();
+        assert_eq!(decoded, tx);
+    }
+
+    #[test]
+    fn rlp_deposit_tx_rejects_extra_fields() {
+        let encoded = deposit().rlp();
+        let mut rlp = RlpStream::new_list(NUM_TX_FIELDS + 1);
+        for item in rlp::Rlp::new(&encoded).iter() {
+            rlp.append_raw(item.as_raw(), 1);
+        }
+        rlp.append(&0u8);
+
+        let err = DepositTransaction::decode(&rlp::Rlp::new(&rlp.out())).unwrap_err();
+        assert_eq!(err, DecoderError::RlpIncorrectListLen);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "legacy", ignore)]
+    fn serde_deposit_tx() {
+        let tx: TypedTransaction = deposit().into();
+        let serialized = serde_json::to_value(&tx).unwrap();
+        assert_eq!(serialized["type"], "0x7E");
+        assert_eq!(serialized["sourceHash"], format!("{:?}", H256::repeat_byte(0x11)));
+
+        let de: TypedTransaction = serde_json::from_value(serialized).unwrap();
+        assert_eq!(tx, de);
+    }
+}
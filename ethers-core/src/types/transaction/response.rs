@@ -1,4 +1,6 @@
 //! Transaction types
+#[cfg(feature = "optimism")]
+use super::optimism::{DepositTransaction, DEPOSIT_TX_TYPE};
 use super::{
     decode_signature, decode_to, eip2718::TypedTransaction, eip2930::AccessList, normalize_v,
     rlp_opt, rlp_opt_list,
@@ -85,7 +87,7 @@ pub struct Transaction {
     pub gateway_fee: Option<U256>,
 
     // EIP2718
-    /// Transaction type, Some(2) for EIP-1559 transaction,
+    /// Transaction type, Some(3) for EIP-4844 transaction, Some(2) for EIP-1559 transaction,
     /// Some(1) for AccessList transaction, None for Legacy
     #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
     pub transaction_type: Option<U64>,
@@ -115,6 +117,35 @@ pub struct Transaction {
     #[serde(rename = "chainId", default, skip_serializing_if = "Option::is_none")]
     pub chain_id: Option<U256>,
 
+    // EIP4844
+    /// The maximum fee per unit of blob gas the sender is willing to pay, only set for EIP-4844
+    /// transactions
+    #[serde(rename = "maxFeePerBlobGas", default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_blob_gas: Option<U256>,
+
+    /// The versioned hashes of the blobs carried by an EIP-4844 transaction
+    #[serde(rename = "blobVersionedHashes", default, skip_serializing_if = "Option::is_none")]
+    pub blob_versioned_hashes: Option<Vec<H256>>,
+
+    /////////////////  Optimism-specific transaction fields //////////////
+    /// The hash which uniquely identifies the origin of a deposit transaction
+    #[cfg(feature = "optimism")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "optimism")))]
+    #[serde(rename = "sourceHash", default, skip_serializing_if = "Option::is_none")]
+    pub source_hash: Option<H256>,
+
+    /// The ETH value minted on L2 by a deposit transaction
+    #[cfg(feature = "optimism")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "optimism")))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mint: Option<U256>,
+
+    /// Whether a deposit transaction is exempt from the L2 gas limit
+    #[cfg(feature = "optimism")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "optimism")))]
+    #[serde(rename = "isSystemTx", default, skip_serializing_if = "Option::is_none")]
+    pub is_system_tx: Option<bool>,
+
     /// Captures unknown fields such as additional fields used by L2s
     #[cfg(not(feature = "celo"))]
     #[serde(flatten)]
@@ -137,6 +168,13 @@ impl Transaction {
     }
 
     pub fn rlp(&self) -> Bytes {
+        // deposit transactions are not signed
+        #[cfg(feature = "optimism")]
+        if self.transaction_type == Some(U64::from(DEPOSIT_TX_TYPE)) {
+            let tx: TypedTransaction = self.into();
+            return tx.rlp()
+        }
+
         let mut rlp = RlpStream::new();
         rlp.begin_unbounded_list();
 
@@ -174,6 +212,23 @@ impl Transaction {
                     rlp.append(&normalize_v(self.v.as_u64(), U64::from(chain_id.as_u64())));
                 }
             }
+            // EIP-4844 (0x03)
+            Some(x) if x == U64::from(3) => {
+                rlp_opt(&mut rlp, &self.chain_id);
+                rlp.append(&self.nonce);
+                rlp_opt(&mut rlp, &self.max_priority_fee_per_gas);
+                rlp_opt(&mut rlp, &self.max_fee_per_gas);
+                rlp.append(&self.gas);
+                rlp_opt(&mut rlp, &self.to);
+                rlp.append(&self.value);
+                rlp.append(&self.input.as_ref());
+                rlp_opt_list(&mut rlp, &self.access_list);
+                rlp_opt(&mut rlp, &self.max_fee_per_blob_gas);
+                rlp.append_list(self.blob_versioned_hashes.as_deref().unwrap_or_default());
+                if let Some(chain_id) = self.chain_id {
+                    rlp.append(&normalize_v(self.v.as_u64(), U64::from(chain_id.as_u64())));
+                }
+            }
             // Legacy (0x00)
             _ => {
                 rlp.append(&self.nonce);
@@ -208,6 +263,11 @@ impl Transaction {
                 encoded.extend_from_slice(rlp_bytes.as_ref());
                 encoded.into()
             }
+            Some(x) if x == U64::from(3) => {
+                encoded.extend_from_slice(&[0x3]);
+                encoded.extend_from_slice(rlp_bytes.as_ref());
+                encoded.into()
+            }
             _ => rlp_bytes,
         }
     }
@@ -259,6 +319,22 @@ impl Transaction {
         Ok(())
     }
 
+    /// Decodes fields of the type 3 transaction response starting at the RLP offset passed.
+    /// Increments the offset for each element parsed.
+    #[inline]
+    fn decode_base_eip4844(
+        &mut self,
+        rlp: &rlp::Rlp,
+        offset: &mut usize,
+    ) -> Result<(), DecoderError> {
+        self.decode_base_eip1559(rlp, offset)?;
+        self.max_fee_per_blob_gas = Some(rlp.val_at(*offset)?);
+        *offset += 1;
+        self.blob_versioned_hashes = Some(rlp.list_at(*offset)?);
+        *offset += 1;
+        Ok(())
+    }
+
     /// Decodes fields of the type 1 transaction response based on the RLP offset passed.
     /// Increments the offset for each element parsed.
     fn decode_base_eip2930(
@@ -290,6 +366,22 @@ impl Transaction {
         Ok(())
     }
 
+    /// Decodes an OP-stack deposit transaction.
+    #[cfg(feature = "optimism")]
+    fn decode_deposit(&mut self, rlp: &rlp::Rlp) -> Result<(), DecoderError> {
+        let deposit = DepositTransaction::decode(rlp)?;
+        self.source_hash = Some(deposit.source_hash);
+        self.from = deposit.tx.from.unwrap_or_default();
+        self.to = deposit.tx.to.as_ref().and_then(|to| to.as_address().copied());
+        self.mint = deposit.mint;
+        self.value = deposit.tx.value.unwrap_or_default();
+        self.gas = deposit.tx.gas.unwrap_or_default();
+        self.is_system_tx = Some(deposit.is_system_tx);
+        self.input = deposit.tx.data.unwrap_or_default();
+        self.transaction_type = Some(DEPOSIT_TX_TYPE.into());
+        Ok(())
+    }
+
     /// Decodes a legacy transaction starting at the RLP offset passed.
     /// Increments the offset for each element parsed.
     #[inline]
@@ -318,7 +410,13 @@ impl Transaction {
     }
 
     /// Recover the sender of the tx from signature
+    ///
+    /// Deposit transactions are not signed, their sender is returned as is.
     pub fn recover_from(&self) -> Result<Address, SignatureError> {
+        #[cfg(feature = "optimism")]
+        if self.transaction_type == Some(U64::from(DEPOSIT_TX_TYPE)) {
+            return Ok(self.from)
+        }
         let signature = Signature { r: self.r, s: self.s, v: self.v.as_u64() };
         let typed_tx: TypedTransaction = self.into();
         signature.recover(typed_tx.sighash())
@@ -362,7 +460,13 @@ impl Decodable for Transaction {
             };
 
             let bytes = data.get(1..).ok_or(DecoderError::Custom("no tx body"))?;
-            let rest = rlp::Rlp::new(bytes);
+            let mut rest = rlp::Rlp::new(bytes);
+            if first == 0x03 && rest.at(0)?.is_list() {
+                // network encoding of a blob transaction, the hash only commits to the
+                // transaction without its sidecar
+                rest = rest.at(0)?;
+                txn.hash = H256(keccak256([&[first], rest.as_raw()].concat()));
+            }
             match first {
                 0x01 => {
                     txn.decode_base_eip2930(&rest, &mut offset)?;
@@ -372,6 +476,16 @@ impl Decodable for Transaction {
                     txn.decode_base_eip1559(&rest, &mut offset)?;
                     txn.transaction_type = Some(2u64.into());
                 }
+                0x03 => {
+                    txn.decode_base_eip4844(&rest, &mut offset)?;
+                    txn.transaction_type = Some(3u64.into());
+                }
+                #[cfg(feature = "optimism")]
+                DEPOSIT_TX_TYPE => {
+                    // deposit transactions are not signed
+                    txn.decode_deposit(&rest)?;
+                    return Ok(txn)
+                }
                 _ => return Err(DecoderError::Custom("invalid tx type")),
             }
 
@@ -433,6 +547,32 @@ pub struct TransactionReceipt {
     /// amount that's actually paid by users can only be determined post-execution
     #[serde(rename = "effectiveGasPrice", default, skip_serializing_if = "Option::is_none")]
     pub effective_gas_price: Option<U256>,
+    /// The amount of blob gas used by an EIP-4844 transaction
+    #[serde(rename = "blobGasUsed", default, skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<U256>,
+    /// The price paid per unit of blob gas by an EIP-4844 transaction
+    #[serde(rename = "blobGasPrice", default, skip_serializing_if = "Option::is_none")]
+    pub blob_gas_price: Option<U256>,
+    /// The fee paid for posting the transaction's data to L1
+    #[cfg(feature = "optimism")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "optimism")))]
+    #[serde(rename = "l1Fee", default, skip_serializing_if = "Option::is_none")]
+    pub l1_fee: Option<U256>,
+    /// The amount of L1 gas used to post the transaction's data
+    #[cfg(feature = "optimism")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "optimism")))]
+    #[serde(rename = "l1GasUsed", default, skip_serializing_if = "Option::is_none")]
+    pub l1_gas_used: Option<U256>,
+    /// The L1 gas price used to compute the L1 fee
+    #[cfg(feature = "optimism")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "optimism")))]
+    #[serde(rename = "l1GasPrice", default, skip_serializing_if = "Option::is_none")]
+    pub l1_gas_price: Option<U256>,
+    /// The scalar applied to the L1 fee, as a decimal string such as `"0.684"`
+    #[cfg(feature = "optimism")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "optimism")))]
+    #[serde(rename = "l1FeeScalar", default, skip_serializing_if = "Option::is_none")]
+    pub l1_fee_scalar: Option<String>,
     /// Captures unknown fields such as additional fields used by L2s
     #[cfg(not(feature = "celo"))]
     #[serde(flatten)]
@@ -442,7 +582,13 @@ pub struct TransactionReceipt {
 impl rlp::Encodable for TransactionReceipt {
     fn rlp_append(&self, s: &mut RlpStream) {
         s.begin_list(4);
-        rlp_opt(s, &self.status);
+        // receipts before Byzantium commit to the intermediate state root instead of the status
+        match (self.status, self.root) {
+            (None, Some(root)) => {
+                s.append(&root);
+            }
+            (status, _) => rlp_opt(s, &status),
+        }
         s.append(&self.cumulative_gas_used);
         s.append(&self.logs_bloom);
         s.append_list(&self.logs);
@@ -531,6 +677,52 @@ mod tests {
         assert_eq!(receipt.effective_gas_price.unwrap().as_u64(), 0x3b9aca07);
     }
 
+    #[test]
+    fn decode_cancun_receipt_and_tx() {
+        let receipt: TransactionReceipt = serde_json::from_value(serde_json::json!({"blobGasPrice":"0x1","blobGasUsed":"0x20000","blockHash":"0x55ae43d3511e327dc532855510d110676d340aa1bbba369b4b98896d86559586","blockNumber":"0xa3d322","contractAddress":null,"cumulativeGasUsed":"0x5208","effectiveGasPrice":"0x3b9aca07","from":"0x541d6a0e9ca9e7a083e41e2e178eef9f22d7492e","gasUsed":"0x5208","logs":[],"logsBloom":"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","status":"0x1","to":"0x8210357f377e901f18e45294e86a2a32215cc3c9","transactionHash":"0x824384376c5972498c6fcafe71fd8cad1689f64e7d5e270d025a898638c0c34d","transactionIndex":"0xd","type":"0x3"})).unwrap();
+        assert_eq!(receipt.transaction_type.unwrap().as_u64(), 3);
+        assert_eq!(receipt.blob_gas_used, Some(0x20000u64.into()));
+        assert_eq!(receipt.blob_gas_price, Some(1u64.into()));
+
+        let json = serde_json::json!({"accessList":[],"blobVersionedHashes":["0x01a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8"],"blockHash":"0x55ae43d3511e327dc532855510d110676d340aa1bbba369b4b98896d86559586","blockNumber":"0xa3d322","chainId":"0x1","from":"0x541d6a0e9ca9e7a083e41e2e178eef9f22d7492e","gas":"0x5208","gasPrice":"0x3b9aca07","hash":"0x824384376c5972498c6fcafe71fd8cad1689f64e7d5e270d025a898638c0c34d","input":"0x","maxFeePerBlobGas":"0x3","maxFeePerGas":"0x3b9aca0e","maxPriorityFeePerGas":"0x3b9aca00","nonce":"0x0","r":"0xf13b5088108f783f4b6048d4be456971118aabfb88be96bb541d734b6c2b20dc","s":"0x13fb7eb25a7d5df42a176cd4c6a086e19163ed7cd8ffba015f939d24f66bc17a","to":"0x8210357f377e901f18e45294e86a2a32215cc3c9","transactionIndex":"0xd","type":"0x3","v":"0x1","value":"0x7b"});
+        let tx: Transaction = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(tx.max_fee_per_blob_gas, Some(3u64.into()));
+        assert_eq!(tx.blob_versioned_hashes.as_ref().unwrap().len(), 1);
+        assert_eq!(serde_json::to_value(&tx).unwrap(), json);
+
+        let typed: TypedTransaction = (&tx).into();
+        let typed = typed.as_eip4844_ref().unwrap();
+        assert_eq!(typed.max_fee_per_blob_gas, tx.max_fee_per_blob_gas);
+        assert_eq!(Some(&typed.blob_versioned_hashes), tx.blob_versioned_hashes.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature = "optimism")]
+    fn decode_optimism_receipt_and_deposit_tx() {
+        let receipt: TransactionReceipt = serde_json::from_value(serde_json::json!({"blockHash":"0x55ae43d3511e327dc532855510d110676d340aa1bbba369b4b98896d86559586","blockNumber":"0xa3d322","contractAddress":null,"cumulativeGasUsed":"0x5208","effectiveGasPrice":"0x3b9aca07","from":"0x541d6a0e9ca9e7a083e41e2e178eef9f22d7492e","gasUsed":"0x5208","l1Fee":"0x1d1a94a20","l1FeeScalar":"0.684","l1GasPrice":"0x3b9aca00","l1GasUsed":"0x640","logs":[],"logsBloom":"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","status":"0x1","to":"0x8210357f377e901f18e45294e86a2a32215cc3c9","transactionHash":"0x824384376c5972498c6fcafe71fd8cad1689f64e7d5e270d025a898638c0c34d","transactionIndex":"0xd","type":"0x2"})).unwrap();
+        assert_eq!(receipt.l1_fee, Some(0x1d1a94a20u64.into()));
+        assert_eq!(receipt.l1_gas_used, Some(0x640u64.into()));
+        assert_eq!(receipt.l1_gas_price, Some(0x3b9aca00u64.into()));
+        assert_eq!(receipt.l1_fee_scalar.as_deref(), Some("0.684"));
+
+        let json = serde_json::json!({"blockHash":"0x55ae43d3511e327dc532855510d110676d340aa1bbba369b4b98896d86559586","blockNumber":"0xa3d322","from":"0xdeaddeaddeaddeaddeaddeaddeaddeaddead0001","gas":"0xf4240","gasPrice":"0x0","hash":"0x824384376c5972498c6fcafe71fd8cad1689f64e7d5e270d025a898638c0c34d","input":"0x015d8eb9","isSystemTx":false,"mint":"0x0","nonce":"0x0","r":"0x0","s":"0x0","sourceHash":"0x1111111111111111111111111111111111111111111111111111111111111111","to":"0x4200000000000000000000000000000000000015","transactionIndex":"0x0","type":"0x7e","v":"0x0","value":"0x0"});
+        let tx: Transaction = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(tx.source_hash, Some(H256::repeat_byte(0x11)));
+        assert_eq!(tx.mint, Some(U256::zero()));
+        assert_eq!(tx.is_system_tx, Some(false));
+        assert_eq!(serde_json::to_value(&tx).unwrap(), json);
+
+        // re-hashing follows the deposit transaction encoding
+        let typed: TypedTransaction = (&tx).into();
+        assert_eq!(typed.as_deposit_ref().unwrap().source_hash, H256::repeat_byte(0x11));
+        assert_eq!(tx.rlp(), typed.rlp());
+        let decoded: Transaction = rlp::decode(&tx.rlp()).unwrap();
+        assert_eq!(decoded.hash(), decoded.hash);
+        assert_eq!(decoded.from, tx.from);
+        assert_eq!(decoded.input, tx.input);
+        assert_eq!(tx.recover_from().unwrap(), tx.from);
+    }
+
     #[test]
     fn decode_london_tx() {
         let tx: Transaction = serde_json::from_value(serde_json::json!({"accessList":[{"address":"0x8ba1f109551bd432803012645ac136ddd64dba72","storageKeys":["0x0000000000000000000000000000000000000000000000000000000000000000","0x0000000000000000000000000000000000000000000000000000000000000042"]}],"blockHash":"0x55ae43d3511e327dc532855510d110676d340aa1bbba369b4b98896d86559586","blockNumber":"0xa3d322","chainId":"0x3","from":"0x541d6a0e9ca9e7a083e41e2e178eef9f22d7492e","gas":"0x6a40","gasPrice":"0x3b9aca07","hash":"0x824384376c5972498c6fcafe71fd8cad1689f64e7d5e270d025a898638c0c34d","input":"0x","maxFeePerGas":"0x3b9aca0e","maxPriorityFeePerGas":"0x3b9aca00","nonce":"0x2","r":"0xf13b5088108f783f4b6048d4be456971118aabfb88be96bb541d734b6c2b20dc","s":"0x13fb7eb25a7d5df42a176cd4c6a086e19163ed7cd8ffba015f939d24f66bc17a","to":"0x8210357f377e901f18e45294e86a2a32215cc3c9","transactionIndex":"0xd","type":"0x2","v":"0x1","value":"0x7b"})).unwrap();
@@ -594,6 +786,14 @@ mod tests {
                 16,
             )
             .unwrap(),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: None,
+            #[cfg(feature = "optimism")]
+            source_hash: None,
+            #[cfg(feature = "optimism")]
+            mint: None,
+            #[cfg(feature = "optimism")]
+            is_system_tx: None,
             other: Default::default(),
         };
         println!("0x{}", hex::encode(&tx.rlp()));
@@ -638,6 +838,14 @@ mod tests {
                 16,
             )
             .unwrap(),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: None,
+            #[cfg(feature = "optimism")]
+            source_hash: None,
+            #[cfg(feature = "optimism")]
+            mint: None,
+            #[cfg(feature = "optimism")]
+            is_system_tx: None,
             other: Default::default(),
         };
         println!("0x{}", hex::encode(&tx.rlp()));
@@ -673,6 +881,14 @@ mod tests {
             access_list: None,
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: None,
+            #[cfg(feature = "optimism")]
+            source_hash: None,
+            #[cfg(feature = "optimism")]
+            mint: None,
+            #[cfg(feature = "optimism")]
+            is_system_tx: None,
             other: Default::default()
         };
         assert_eq!(
@@ -719,6 +935,14 @@ mod tests {
             max_priority_fee_per_gas: Some(1500000000.into()),
             max_fee_per_gas: Some(1500000009.into()),
             chain_id: Some(5.into()),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: None,
+            #[cfg(feature = "optimism")]
+            source_hash: None,
+            #[cfg(feature = "optimism")]
+            mint: None,
+            #[cfg(feature = "optimism")]
+            is_system_tx: None,
             other: Default::default(),
         };
         assert_eq!(
@@ -766,6 +990,14 @@ mod tests {
             max_priority_fee_per_gas: Some(1500000000.into()),
             max_fee_per_gas: Some(1500000009.into()),
             chain_id: Some(5.into()),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: None,
+            #[cfg(feature = "optimism")]
+            source_hash: None,
+            #[cfg(feature = "optimism")]
+            mint: None,
+            #[cfg(feature = "optimism")]
+            is_system_tx: None,
             other: Default::default(),
         };
 
@@ -829,6 +1061,14 @@ mod tests {
             access_list: None,
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: None,
+            #[cfg(feature = "optimism")]
+            source_hash: None,
+            #[cfg(feature = "optimism")]
+            mint: None,
+            #[cfg(feature = "optimism")]
+            is_system_tx: None,
             other: Default::default()
         };
 
@@ -864,6 +1104,14 @@ mod tests {
             access_list: None,
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: None,
+            #[cfg(feature = "optimism")]
+            source_hash: None,
+            #[cfg(feature = "optimism")]
+            mint: None,
+            #[cfg(feature = "optimism")]
+            is_system_tx: None,
             other: Default::default()
         };
 
@@ -930,6 +1178,14 @@ mod tests {
             max_priority_fee_per_gas: Some(1500000000.into()),
             max_fee_per_gas: Some(1500000009.into()),
             chain_id: Some(5.into()),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: None,
+            #[cfg(feature = "optimism")]
+            source_hash: None,
+            #[cfg(feature = "optimism")]
+            mint: None,
+            #[cfg(feature = "optimism")]
+            is_system_tx: None,
             other: Default::default(),
         };
 
@@ -0,0 +1,336 @@
+//! ERC-4337 account abstraction types.
+//!
+//! See <https://eips.ethereum.org/EIPS/eip-4337>
+use crate::{
+    abi::{encode, Token},
+    types::{Address, Bytes, Log, TransactionReceipt, H160, H256, U256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+
+/// The address of the v0.6 `EntryPoint` contract:
+/// [`0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789`](https://etherscan.io/address/0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789)
+pub const ENTRY_POINT_V06_ADDRESS: H160 = H160([
+    0x5f, 0xf1, 0x37, 0xd4, 0xb0, 0xfd, 0xcd, 0x49, 0xdc, 0xa3, 0x0c, 0x7c, 0xf5, 0x7e, 0x57, 0x8a,
+    0x02, 0x6d, 0x27, 0x89,
+]);
+
+/// A user operation as defined by version 0.6 of the ERC-4337 `EntryPoint`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperation {
+    /// The account making the operation
+    pub sender: Address,
+
+    /// Anti-replay parameter, also used as the salt for first-time account creation
+    pub nonce: U256,
+
+    /// The factory address followed by its calldata, only set if the account is not yet deployed
+    pub init_code: Bytes,
+
+    /// The data to pass to the sender during the main execution call
+    pub call_data: Bytes,
+
+    /// The amount of gas to allocate for the main execution call
+    pub call_gas_limit: U256,
+
+    /// The amount of gas to allocate for the verification step
+    pub verification_gas_limit: U256,
+
+    /// The amount of gas paid to the bundler for pre-verification execution and calldata
+    pub pre_verification_gas: U256,
+
+    /// Maximum fee per gas, similar to EIP-1559 `max_fee_per_gas`
+    pub max_fee_per_gas: U256,
+
+    /// Maximum priority fee per gas, similar to EIP-1559 `max_priority_fee_per_gas`
+    pub max_priority_fee_per_gas: U256,
+
+    /// The paymaster address followed by its data, empty if the account pays for itself
+    pub paymaster_and_data: Bytes,
+
+    /// Data passed to the account along with the nonce during the verification step
+    pub signature: Bytes,
+}
+
+impl UserOperation {
+    /// Creates an empty user operation for the given `sender`
+    pub fn new<T: Into<Address>>(sender: T) -> Self {
+        Self { sender: sender.into(), ..Default::default() }
+    }
+
+    // Builder pattern helpers
+
+    /// Sets the `nonce` field in the user operation to the provided value
+    #[must_use]
+    pub fn nonce<T: Into<U256>>(mut self, nonce: T) -> Self {
+        self.nonce = nonce.into();
+        self
+    }
+
+    /// Sets the `init_code` field in the user operation to the provided value
+    #[must_use]
+    pub fn init_code<T: Into<Bytes>>(mut self, init_code: T) -> Self {
+        self.init_code = init_code.into();
+        self
+    }
+
+    /// Sets the `call_data` field in the user operation to the provided value
+    #[must_use]
+    pub fn call_data<T: Into<Bytes>>(mut self, call_data: T) -> Self {
+        self.call_data = call_data.into();
+        self
+    }
+
+    /// Sets the `call_gas_limit` field in the user operation to the provided value
+    #[must_use]
+    pub fn call_gas_limit<T: Into<U256>>(mut self, call_gas_limit: T) -> Self {
+        self.call_gas_limit = call_gas_limit.into();
+        self
+    }
+
+    /// Sets the `verification_gas_limit` field in the user operation to the provided value
+    #[must_use]
+    pub fn verification_gas_limit<T: Into<U256>>(mut self, verification_gas_limit: T) -> Self {
+        self.verification_gas_limit = verification_gas_limit.into();
+        self
+    }
+
+    /// Sets the `pre_verification_gas` field in the user operation to the provided value
+    #[must_use]
+    pub fn pre_verification_gas<T: Into<U256>>(mut self, pre_verification_gas: T) -> Self {
+        self.pre_verification_gas = pre_verification_gas.into();
+        self
+    }
+
+    /// Sets the `max_fee_per_gas` field in the user operation to the provided value
+    #[must_use]
+    pub fn max_fee_per_gas<T: Into<U256>>(mut self, max_fee_per_gas: T) -> Self {
+        self.max_fee_per_gas = max_fee_per_gas.into();
+        self
+    }
+
+    /// Sets the `max_priority_fee_per_gas` field in the user operation to the provided value
+    #[must_use]
+    pub fn max_priority_fee_per_gas<T: Into<U256>>(mut self, max_priority_fee_per_gas: T) -> Self {
+        self.max_priority_fee_per_gas = max_priority_fee_per_gas.into();
+        self
+    }
+
+    /// Sets the `paymaster_and_data` field in the user operation to the provided value
+    #[must_use]
+    pub fn paymaster_and_data<T: Into<Bytes>>(mut self, paymaster_and_data: T) -> Self {
+        self.paymaster_and_data = paymaster_and_data.into();
+        self
+    }
+
+    /// Sets the `signature` field in the user operation to the provided value
+    #[must_use]
+    pub fn signature<T: Into<Bytes>>(mut self, signature: T) -> Self {
+        self.signature = signature.into();
+        self
+    }
+
+    /// Sets the gas fields to the values estimated by a bundler
+    #[must_use]
+    pub fn gas_estimate(mut self, estimate: &UserOperationGasEstimate) -> Self {
+        self.call_gas_limit = estimate.call_gas_limit;
+        self.verification_gas_limit = estimate.verification_gas_limit;
+        self.pre_verification_gas = estimate.pre_verification_gas;
+        self
+    }
+
+    /// ABI encodes all fields except the signature, with the dynamic fields replaced by their
+    /// hashes, as done by `UserOperationLib.pack` of the v0.6 `EntryPoint`.
+    pub fn pack(&self) -> Bytes {
+        encode(&[
+            Token::Address(self.sender),
+            Token::Uint(self.nonce),
+            Token::FixedBytes(keccak256(&self.init_code).to_vec()),
+            Token::FixedBytes(keccak256(&self.call_data).to_vec()),
+            Token::Uint(self.call_gas_limit),
+            Token::Uint(self.verification_gas_limit),
+            Token::Uint(self.pre_verification_gas),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Uint(self.max_priority_fee_per_gas),
+            Token::FixedBytes(keccak256(&self.paymaster_and_data).to_vec()),
+        ])
+        .into()
+    }
+
+    /// Computes the hash returned by `EntryPoint.getUserOpHash`, which binds the operation to the
+    /// given `entry_point` and `chain_id`.
+    ///
+    /// Accounts usually expect a signature over this hash, prefixed as a personal message.
+    pub fn hash(&self, entry_point: Address, chain_id: u64) -> H256 {
+        keccak256(encode(&[
+            Token::FixedBytes(keccak256(self.pack()).to_vec()),
+            Token::Address(entry_point),
+            Token::Uint(chain_id.into()),
+        ]))
+        .into()
+    }
+}
+
+/// The gas values returned by `eth_estimateUserOperationGas`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperationGasEstimate {
+    /// Gas overhead of the operation paid to the bundler
+    pub pre_verification_gas: U256,
+
+    /// Gas required for the verification step
+    pub verification_gas_limit: U256,
+
+    /// Gas required for the main execution call
+    pub call_gas_limit: U256,
+}
+
+/// The receipt of an included user operation, as returned by `eth_getUserOperationReceipt`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperationReceipt {
+    /// The hash of the user operation
+    pub user_op_hash: H256,
+
+    /// The `EntryPoint` which executed the operation
+    pub entry_point: Address,
+
+    /// The account which made the operation
+    pub sender: Address,
+
+    /// The nonce of the operation
+    pub nonce: U256,
+
+    /// The paymaster which paid for the operation, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paymaster: Option<Address>,
+
+    /// The amount paid for the operation, by the paymaster or the account
+    pub actual_gas_cost: U256,
+
+    /// The total gas used by the operation, including pre-verification gas
+    pub actual_gas_used: U256,
+
+    /// Whether the main execution call succeeded
+    pub success: bool,
+
+    /// The revert reason, if the main execution call reverted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    /// The logs emitted by the operation
+    pub logs: Vec<Log>,
+
+    /// The receipt of the bundle transaction which included the operation
+    pub receipt: TransactionReceipt,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn user_operation() -> UserOperation {
+        UserOperation::new("0x9406cc6185a346906296840746125a0e44976454".parse::<Address>().unwrap())
+            .nonce(7)
+            .init_code(vec![0xaa; 20])
+            .call_data(vec![0xb6, 0x1d, 0x27, 0xf6])
+            .call_gas_limit(35_000)
+            .verification_gas_limit(70_000)
+            .pre_verification_gas(21_000)
+            .max_fee_per_gas(1_000_000_000)
+            .max_priority_fee_per_gas(100_000_000)
+    }
+
+    #[test]
+    fn serde_user_operation() {
+        let op = user_operation().signature(vec![0x01, 0x02]);
+        let value = serde_json::to_value(&op).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "sender": "0x9406cc6185a346906296840746125a0e44976454",
+                "nonce": "0x7",
+                "initCode": format!("0x{}", "aa".repeat(20)),
+                "callData": "0xb61d27f6",
+                "callGasLimit": "0x88b8",
+                "verificationGasLimit": "0x11170",
+                "preVerificationGas": "0x5208",
+                "maxFeePerGas": "0x3b9aca00",
+                "maxPriorityFeePerGas": "0x5f5e100",
+                "paymasterAndData": "0x",
+                "signature": "0x0102"
+            })
+        );
+        assert_eq!(serde_json::from_value::<UserOperation>(value).unwrap(), op);
+    }
+
+    #[test]
+    fn pack_hashes_dynamic_fields() {
+        let op = user_operation();
+        let packed = op.pack();
+        assert_eq!(packed.len(), 10 * 32);
+        assert_eq!(&packed[64..96], &keccak256(&op.init_code)[..]);
+        assert_eq!(&packed[96..128], &keccak256(&op.call_data)[..]);
+        assert_eq!(&packed[288..320], &keccak256(b"")[..]);
+
+        // the signature is not part of the hash
+        assert_eq!(op.clone().signature(vec![0x01]).pack(), packed);
+    }
+
+    #[test]
+    fn hash_binds_entry_point_and_chain() {
+        let op = user_operation();
+        let hash = op.hash(ENTRY_POINT_V06_ADDRESS, 1);
+
+        // `EntryPoint.getUserOpHash` of the v0.6 reference implementation
+        let expected: H256 =
+            "0x2f8887d806114e0940a47c6a5670efda88006795a9d4fb50ba75fcf0cbb61e72".parse().unwrap();
+        assert_eq!(hash, expected);
+        let expected: H256 =
+            "0xc658f2018bfc2b68faffa249e1f14e2336502d98c68488d982fd40c0897572a1".parse().unwrap();
+        assert_eq!(op.hash(ENTRY_POINT_V06_ADDRESS, 5), expected);
+
+        assert_ne!(hash, op.hash(Address::zero(), 1));
+        assert_ne!(hash, op.clone().nonce(8).hash(ENTRY_POINT_V06_ADDRESS, 1));
+    }
+
+    #[test]
+    fn deserialize_receipt() {
+        let receipt: UserOperationReceipt = serde_json::from_value(json!({
+            "userOpHash": "0x93c06f3f5909cc2b192713ed9bf93e3e1fde4b22fcd2466304fa404f9b80ff90",
+            "entryPoint": "0x5ff137d4b0fdcd49dca30c7cf57e578a026d2789",
+            "sender": "0x9406cc6185a346906296840746125a0e44976454",
+            "nonce": "0x7",
+            "paymaster": "0x0000000000000000000000000000000000000000",
+            "actualGasCost": "0x4b3b147f788",
+            "actualGasUsed": "0x1b6d5",
+            "success": true,
+            "logs": [],
+            "receipt": {
+                "transactionHash": "0x57465d20d634421008a167cfcfcde94847dba9d6b5d3652b071d4b84e5ce74ff",
+                "transactionIndex": "0x0",
+                "blockHash": "0xa7d3d4bdfdd2b3ca1be3bbd75b4ab44bd6b13b1d5d1bd1e2b4bf36e26fd7c2f6",
+                "blockNumber": "0x1",
+                "from": "0x43378ff8c70109ee4dbe85af34428ab0615ebd23",
+                "to": "0x5ff137d4b0fdcd49dca30c7cf57e578a026d2789",
+                "cumulativeGasUsed": "0x2a7e3",
+                "gasUsed": "0x2a7e3",
+                "contractAddress": null,
+                "logs": [],
+                "status": "0x1",
+                "logsBloom": format!("0x{}", "00".repeat(256)),
+                "type": "0x2",
+                "effectiveGasPrice": "0x2c3d9b1"
+            }
+        }))
+        .unwrap();
+        assert!(receipt.success);
+        assert_eq!(receipt.entry_point, ENTRY_POINT_V06_ADDRESS);
+        assert_eq!(receipt.actual_gas_used, 0x1b6d5.into());
+        assert_eq!(receipt.paymaster, Some(Address::zero()));
+        assert_eq!(receipt.reason, None);
+        assert_eq!(receipt.receipt.block_number, Some(1.into()));
+    }
+}
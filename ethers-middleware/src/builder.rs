@@ -1,6 +1,6 @@
 use crate::{
     gas_oracle::{GasOracle, GasOracleMiddleware},
-    NonceManagerMiddleware, SignerMiddleware,
+    MultiNonceManagerMiddleware, MultiSignerMiddleware, NonceManagerMiddleware, SignerMiddleware,
 };
 use ethers_core::types::Address;
 use ethers_providers::Middleware;
@@ -70,6 +70,13 @@ pub trait MiddlewareBuilder: Middleware + Sized + 'static {
         SignerMiddleware::new(self, s)
     }
 
+    /// Wraps `self` inside a [`MultiSignerMiddleware`](crate::MultiSignerMiddleware) without
+    /// any signer, to be added with
+    /// [`MultiSignerMiddleware::with_signer`](crate::MultiSignerMiddleware::with_signer).
+    fn multi_signer(self) -> MultiSignerMiddleware<Self> {
+        MultiSignerMiddleware::new(self)
+    }
+
     /// Wraps `self` inside a [`NonceManagerMiddleware`](crate::NonceManagerMiddleware).
     ///
     /// [`Address`](ethers_core::types::Address)
@@ -77,6 +84,12 @@ pub trait MiddlewareBuilder: Middleware + Sized + 'static {
         NonceManagerMiddleware::new(self, address)
     }
 
+    /// Wraps `self` inside a
+    /// [`MultiNonceManagerMiddleware`](crate::MultiNonceManagerMiddleware).
+    fn multi_nonce_manager(self) -> MultiNonceManagerMiddleware<Self> {
+        MultiNonceManagerMiddleware::new(self)
+    }
+
     /// Wraps `self` inside a [`GasOracleMiddleware`](crate::gas_oracle::GasOracleMiddleware).
     ///
     /// [`GasOracle`](crate::gas_oracle::GasOracle)
@@ -14,14 +14,16 @@ use thiserror::Error;
 use tracing_futures::Instrument;
 
 use ethers_core::types::{
-    transaction::eip2718::TypedTransaction, BlockId, TransactionRequest, TxHash, U256,
+    transaction::{eip2718::TypedTransaction, eip4844::Eip4844TransactionRequest},
+    BlockId, TxHash, U256,
 };
 use ethers_providers::{interval, Middleware, MiddlewareError, PendingTransaction, StreamExt};
 
 #[cfg(not(target_arch = "wasm32"))]
 use tokio::spawn;
 
-type ToEscalate = Arc<Mutex<Vec<(TxHash, TransactionRequest, Instant, Option<BlockId>)>>>;
+type ToEscalate =
+    Arc<Mutex<Vec<(TxHash, TypedTransaction, Instant, Option<BlockId>, Option<U256>)>>>;
 
 #[cfg(target_arch = "wasm32")]
 type WatcherFuture<'a> = Pin<Box<dyn futures_util::stream::Stream<Item = ()> + 'a>>;
@@ -34,6 +36,83 @@ pub trait GasEscalator: Send + Sync + std::fmt::Debug {
     /// Given the initial gas price and the time elapsed since the transaction's
     /// first broadcast, it returns the new gas price
     fn get_gas_price(&self, initial_price: U256, time_elapsed: u64) -> U256;
+
+    /// Given the initial EIP-1559 fees and the time elapsed since the
+    /// transaction's first broadcast, it returns the new `max_fee_per_gas` and
+    /// `max_priority_fee_per_gas`. Both fees are escalated with
+    /// [`GasEscalator::get_gas_price`] by default.
+    fn get_eip1559_fees(
+        &self,
+        initial_max_fee: U256,
+        initial_priority_fee: U256,
+        time_elapsed: u64,
+    ) -> (U256, U256) {
+        (
+            self.get_gas_price(initial_max_fee, time_elapsed),
+            self.get_gas_price(initial_priority_fee, time_elapsed),
+        )
+    }
+}
+
+/// Returns the minimum fee a replacement transaction must pay, as nodes reject
+/// replacements which do not bump the fees by at least 10%
+pub fn replacement_fee(fee: U256) -> U256 {
+    fee + (fee + 9) / 10
+}
+
+/// Escalates the fees of the transaction, capped at `fee_cap`. Returns false if
+/// the fees do not need to be escalated yet, or if the escalated fees would not
+/// be accepted as a replacement.
+pub(crate) fn escalate_fees<E: GasEscalator>(
+    escalator: &E,
+    tx: &mut TypedTransaction,
+    time_elapsed: u64,
+    fee_cap: Option<U256>,
+) -> bool {
+    let fee_cap = fee_cap.unwrap_or(U256::MAX);
+    match tx {
+        TypedTransaction::Eip1559(inner) |
+        TypedTransaction::Eip4844(Eip4844TransactionRequest { tx: inner, .. }) => {
+            let (Some(max_fee), Some(priority_fee)) =
+                (inner.max_fee_per_gas, inner.max_priority_fee_per_gas)
+            else {
+                return false
+            };
+            let (new_max_fee, new_priority_fee) =
+                escalator.get_eip1559_fees(max_fee, priority_fee, time_elapsed);
+            if new_max_fee == max_fee && new_priority_fee == priority_fee {
+                return false
+            }
+
+            // both fees must be bumped, and the priority fee can't exceed the max fee
+            let new_priority_fee = new_priority_fee.max(replacement_fee(priority_fee));
+            let new_max_fee =
+                new_max_fee.max(replacement_fee(max_fee)).max(new_priority_fee).min(fee_cap);
+            let new_priority_fee = new_priority_fee.min(new_max_fee);
+            if new_max_fee < replacement_fee(max_fee) ||
+                new_priority_fee < replacement_fee(priority_fee)
+            {
+                return false
+            }
+
+            inner.max_fee_per_gas = Some(new_max_fee);
+            inner.max_priority_fee_per_gas = Some(new_priority_fee);
+        }
+        _ => {
+            let Some(gas_price) = tx.gas_price() else { return false };
+            let new_gas_price = escalator.get_gas_price(gas_price, time_elapsed);
+            if new_gas_price == gas_price {
+                return false
+            }
+
+            let new_gas_price = new_gas_price.max(replacement_fee(gas_price)).min(fee_cap);
+            if new_gas_price < replacement_fee(gas_price) {
+                return false
+            }
+            tx.set_gas_price(new_gas_price);
+        }
+    }
+    true
 }
 
 #[derive(Error, Debug)]
@@ -43,7 +122,7 @@ pub enum GasEscalatorError<M: Middleware> {
     /// Thrown when an internal middleware errors
     MiddlewareError(M::Error),
 
-    #[error("Gas escalation is only supported for EIP2930 or Legacy transactions")]
+    #[error("Gas escalation is not supported for this transaction type")]
     UnsupportedTxType,
 }
 
@@ -139,6 +218,7 @@ pub(crate) struct GasEscalatorMiddlewareInternal<M> {
 /// ```
 pub struct GasEscalatorMiddleware<M> {
     pub(crate) inner: Arc<GasEscalatorMiddlewareInternal<M>>,
+    fee_cap: Option<U256>,
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
@@ -160,7 +240,7 @@ where
         tx: T,
         block: Option<BlockId>,
     ) -> Result<PendingTransaction<'_, Self::Provider>, Self::Error> {
-        self.inner.send_transaction(tx, block).await
+        self.inner.send_transaction(tx, block, self.fee_cap).await
     }
 }
 
@@ -172,8 +252,23 @@ where
         &self,
         tx: T,
         block: Option<BlockId>,
+        fee_cap: Option<U256>,
     ) -> Result<PendingTransaction<'_, M::Provider>, GasEscalatorError<M>> {
-        let tx = tx.into();
+        let mut tx = tx.into();
+        // deposit transactions are paid for on L1 and can't be escalated
+        match tx {
+            TypedTransaction::Legacy(_) |
+            TypedTransaction::Eip2930(_) |
+            TypedTransaction::Eip1559(_) |
+            TypedTransaction::Eip4844(_) => {}
+            #[cfg(feature = "optimism")]
+            TypedTransaction::DepositTransaction(_) => {
+                return Err(GasEscalatorError::UnsupportedTxType)
+            }
+        }
+
+        // the fees must be known in order to escalate them
+        self.inner.fill_transaction(&mut tx, block).await.map_err(MiddlewareError::from_err)?;
 
         let pending_tx = self
             .inner
@@ -181,15 +276,9 @@ where
             .await
             .map_err(MiddlewareError::from_err)?;
 
-        let tx = match tx {
-            TypedTransaction::Legacy(inner) => inner,
-            TypedTransaction::Eip2930(inner) => inner.tx,
-            _ => return Err(GasEscalatorError::UnsupportedTxType),
-        };
-
         // insert the tx in the pending txs
         let mut lock = self.txs.lock().await;
-        lock.push((*pending_tx, tx, Instant::now(), block));
+        lock.push((*pending_tx, tx, Instant::now(), block, fee_cap));
 
         Ok(pending_tx)
     }
@@ -225,7 +314,17 @@ where
             spawn(esc.escalate().instrument(tracing::trace_span!("gas-escalation")));
         }
 
-        Self { inner: this }
+        Self { inner: this, fee_cap: None }
+    }
+
+    /// Caps the gas price, or the `max_fee_per_gas` of EIP-1559 transactions,
+    /// that the transactions sent through this instance are escalated to.
+    ///
+    /// The cap is recorded per transaction, so clones of the middleware may
+    /// use different caps while sharing the same background task.
+    pub fn with_fee_cap<T: Into<U256>>(mut self, fee_cap: T) -> Self {
+        self.fee_cap = Some(fee_cap.into());
+        self
     }
 }
 
@@ -289,7 +388,7 @@ impl<M, E> EscalationTask<M, E> {
                 // Pop all transactions and re-insert those that have not been included yet
                 for _ in 0..len {
                     // this must never panic as we're explicitly within bounds
-                    let (tx_hash, mut replacement_tx, time, priority) =
+                    let (tx_hash, mut replacement_tx, time, priority, fee_cap) =
                         txs.pop().expect("should have element in vector");
 
                     let receipt = self
@@ -301,18 +400,20 @@ impl<M, E> EscalationTask<M, E> {
                     tracing::trace!(tx_hash = ?tx_hash, "checking if exists");
 
                     if receipt.is_none() {
-                        let old_gas_price = replacement_tx.gas_price.expect("gas price must be set");
-                        // Get the new gas price based on how much time passed since the
+                        let old_gas_price = replacement_tx.gas_price();
+                        // Get the new fees based on how much time passed since the
                         // tx was last broadcast
-                        let new_gas_price = self
-                            .escalator
-                            .get_gas_price(old_gas_price, now.duration_since(time).as_secs());
-
-                        let new_txhash = if new_gas_price == old_gas_price {
+                        let escalated = escalate_fees(
+                            &self.escalator,
+                            &mut replacement_tx,
+                            now.duration_since(time).as_secs(),
+                            fee_cap,
+                        );
+
+                        let new_txhash = if !escalated {
                              tx_hash
                         } else {
-                            // bump the gas price
-                            replacement_tx.gas_price = Some(new_gas_price);
+                            let new_gas_price = replacement_tx.gas_price();
 
                             // the tx hash will be different so we need to update it
                             match self.inner.send_transaction(replacement_tx.clone(), priority).await {
@@ -345,7 +446,7 @@ impl<M, E> EscalationTask<M, E> {
                                 }
                             }
                         };
-                        txs.push((new_txhash, replacement_tx, time, priority));
+                        txs.push((new_txhash, replacement_tx, time, priority, fee_cap));
                     }
                 }
                 // after this big ugly loop, we dump everything back in
@@ -356,3 +457,79 @@ impl<M, E> EscalationTask<M, E> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_core::types::{Eip1559TransactionRequest, TransactionRequest};
+
+    #[test]
+    fn escalates_legacy_gas_price() {
+        let escalator = LinearGasPrice::new(50, 10u64, None);
+        let mut tx: TypedTransaction = TransactionRequest::new().gas_price(1000).into();
+
+        // no escalation is due yet
+        assert!(!escalate_fees(&escalator, &mut tx, 5, None));
+        assert_eq!(tx.gas_price(), Some(1000.into()));
+
+        // a 5% bump would be rejected by the node, so the price is bumped by 10%
+        assert!(escalate_fees(&escalator, &mut tx, 10, None));
+        assert_eq!(tx.gas_price(), Some(1100.into()));
+
+        // the escalator's price is used when it is higher
+        assert!(escalate_fees(&escalator, &mut tx, 60, None));
+        assert_eq!(tx.gas_price(), Some(1400.into()));
+
+        // the bump can't reach the cap
+        assert!(!escalate_fees(&escalator, &mut tx, 60, Some(1500.into())));
+        assert_eq!(tx.gas_price(), Some(1400.into()));
+    }
+
+    #[test]
+    fn escalates_eip1559_fees() {
+        let escalator = GeometricGasPrice::new(2.0, 10u64, None::<u64>);
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .max_fee_per_gas(1000)
+            .max_priority_fee_per_gas(5)
+            .into();
+
+        assert!(escalate_fees(&escalator, &mut tx, 10, None));
+        let inner = tx.as_eip1559_ref().unwrap();
+        assert_eq!(inner.max_fee_per_gas, Some(2000.into()));
+        assert_eq!(inner.max_priority_fee_per_gas, Some(10.into()));
+
+        // the max fee is capped, and still bumped by 10%
+        assert!(escalate_fees(&escalator, &mut tx, 10, Some(2500.into())));
+        let inner = tx.as_eip1559_ref().unwrap();
+        assert_eq!(inner.max_fee_per_gas, Some(2500.into()));
+        assert_eq!(inner.max_priority_fee_per_gas, Some(20.into()));
+
+        // no replacement can be sent once the cap is reached
+        assert!(!escalate_fees(&escalator, &mut tx, 10, Some(2500.into())));
+    }
+
+    #[test]
+    fn priority_fee_does_not_exceed_max_fee() {
+        let escalator = LinearGasPrice::new(100, 10u64, None);
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .max_fee_per_gas(100)
+            .max_priority_fee_per_gas(100)
+            .into();
+
+        assert!(escalate_fees(&escalator, &mut tx, 10, None));
+        let inner = tx.as_eip1559_ref().unwrap();
+        assert_eq!(inner.max_fee_per_gas, Some(200.into()));
+        assert_eq!(inner.max_priority_fee_per_gas, Some(200.into()));
+
+        // the priority fee can't be bumped enough under the cap
+        assert!(!escalate_fees(&escalator, &mut tx, 10, Some(210.into())));
+    }
+
+    #[test]
+    fn replacement_fee_rounds_up() {
+        assert_eq!(replacement_fee(0.into()), 0.into());
+        assert_eq!(replacement_fee(1.into()), 2.into());
+        assert_eq!(replacement_fee(100.into()), 110.into());
+        assert_eq!(replacement_fee(101.into()), 112.into());
+    }
+}
@@ -71,28 +71,33 @@ where
         block: Option<BlockId>,
     ) -> Result<(), Self::Error> {
         match tx {
-            TypedTransaction::Legacy(ref mut tx) => {
-                if tx.gas_price.is_none() {
-                    tx.gas_price = Some(self.get_gas_price().await?);
-                }
+            TypedTransaction::Legacy(ref mut tx) if tx.gas_price.is_none() => {
+                tx.gas_price = Some(self.get_gas_price().await?);
             }
-            TypedTransaction::Eip2930(ref mut inner) => {
-                if inner.tx.gas_price.is_none() {
-                    inner.tx.gas_price = Some(self.get_gas_price().await?);
-                }
+            TypedTransaction::Eip2930(ref mut inner) if inner.tx.gas_price.is_none() => {
+                inner.tx.gas_price = Some(self.get_gas_price().await?);
             }
-            TypedTransaction::Eip1559(ref mut inner) => {
-                if inner.max_priority_fee_per_gas.is_none() || inner.max_fee_per_gas.is_none() {
-                    let (max_fee_per_gas, max_priority_fee_per_gas) =
-                        self.estimate_eip1559_fees(None).await?;
-                    if inner.max_priority_fee_per_gas.is_none() {
-                        inner.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
-                    }
-                    if inner.max_fee_per_gas.is_none() {
-                        inner.max_fee_per_gas = Some(max_fee_per_gas);
-                    }
+            TypedTransaction::Eip1559(ref mut inner) |
+            TypedTransaction::Eip4844(Eip4844TransactionRequest { tx: ref mut inner, .. })
+                if inner.max_priority_fee_per_gas.is_none() || inner.max_fee_per_gas.is_none() =>
+            {
+                let (max_fee_per_gas, max_priority_fee_per_gas) =
+                    self.estimate_eip1559_fees(None).await?;
+                if inner.max_priority_fee_per_gas.is_none() {
+                    inner.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+                }
+                if inner.max_fee_per_gas.is_none() {
+                    inner.max_fee_per_gas = Some(max_fee_per_gas);
                 }
             }
+            // the fees are already set
+            TypedTransaction::Legacy(_) |
+            TypedTransaction::Eip2930(_) |
+            TypedTransaction::Eip1559(_) |
+            TypedTransaction::Eip4844(_) => {}
+            // deposit transactions are paid for on L1
+            #[cfg(feature = "optimism")]
+            TypedTransaction::DepositTransaction(_) => {}
         };
 
         self.inner().fill_transaction(tx, block).await.map_err(METrait::from_err)
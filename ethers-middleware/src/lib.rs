@@ -13,9 +13,11 @@ pub mod gas_escalator;
 pub mod gas_oracle;
 
 /// The [Nonce Manager](crate::NonceManagerMiddleware) is used to locally calculate nonces instead
-/// of using eth_getTransactionCount
+/// of using eth_getTransactionCount. The
+/// [Multi Nonce Manager](crate::MultiNonceManagerMiddleware) does the same for any number of
+/// senders.
 pub mod nonce_manager;
-pub use nonce_manager::NonceManagerMiddleware;
+pub use nonce_manager::{MultiNonceManagerMiddleware, NonceManagerMiddleware};
 
 /// The [Transformer](crate::transformer::TransformerMiddleware) is used to intercept transactions
 /// and transform them to be sent via various supported transformers, e.g.,
@@ -27,6 +29,11 @@ pub mod transformer;
 pub mod signer;
 pub use signer::SignerMiddleware;
 
+/// The [MultiSigner](crate::MultiSignerMiddleware) locally signs transactions and messages with
+/// the signer of their `from` address, out of any number of signers
+pub mod multi_signer;
+pub use multi_signer::MultiSignerMiddleware;
+
 /// The [Policy](crate::PolicyMiddleware) is used to ensure transactions comply with the rules
 /// configured in the `PolicyMiddleware` before sending them.
 pub mod policy;
@@ -37,6 +44,20 @@ pub use policy::PolicyMiddleware;
 pub mod timelag;
 pub use timelag::TimeLag;
 
+/// The [VerifyingMiddleware](crate::VerifyingMiddleware) checks the account state returned by an
+/// untrusted node against `eth_getProof` proofs and the state root of a trusted block header
+#[cfg(not(feature = "celo"))]
+pub mod verifying;
+#[cfg(not(feature = "celo"))]
+pub use verifying::VerifyingMiddleware;
+
+/// The [TxManagerMiddleware](crate::TxManagerMiddleware) records the signed transactions in a
+/// persistent store, so that they can be re-broadcast, escalated or cancelled after a restart
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tx_manager;
+#[cfg(not(target_arch = "wasm32"))]
+pub use tx_manager::TxManagerMiddleware;
+
 /// The [MiddlewareBuilder](crate::MiddlewareBuilder) provides a way to compose many
 /// [`Middleware`](ethers_providers::Middleware) in a concise way
 pub mod builder;
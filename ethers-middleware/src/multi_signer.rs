@@ -0,0 +1,382 @@
+use async_trait::async_trait;
+use ethers_core::types::{
+    transaction::{eip2718::TypedTransaction, eip2930::AccessListWithGasUsed},
+    Address, BlockId, Bytes, Chain, Signature, TransactionRequest, U256,
+};
+use ethers_providers::{maybe, Middleware, MiddlewareError, PendingTransaction};
+use ethers_signers::Signer;
+use std::{collections::HashMap, convert::TryFrom, fmt::Debug, sync::Arc};
+use thiserror::Error;
+
+/// A boxed signer error
+type BoxedSignerError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Object safe subset of [`Signer`], so that signers of different types can be stored together
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+trait DynSigner: Debug + Send + Sync {
+    async fn sign_message(&self, message: Bytes) -> Result<Signature, BoxedSignerError>;
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, BoxedSignerError>;
+
+    fn chain_id(&self) -> u64;
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<S> DynSigner for S
+where
+    S: Signer + 'static,
+    S::Error: 'static,
+{
+    async fn sign_message(&self, message: Bytes) -> Result<Signature, BoxedSignerError> {
+        Ok(Signer::sign_message(self, message).await?)
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, BoxedSignerError> {
+        Ok(Signer::sign_transaction(self, tx).await?)
+    }
+
+    fn chain_id(&self) -> u64 {
+        Signer::chain_id(self)
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Middleware used for locally signing transactions and messages with any number of signers,
+/// which may be of different types, e.g. a [`LocalWallet`] and a [`Ledger`].
+///
+/// The signer is picked by the `from` address of the transaction. Transactions without a `from`
+/// address are sent by the default sender, which is the first signer added unless set with
+/// [`MultiSignerMiddleware::with_default_sender`]. Transactions from an address without a signer
+/// are delegated to the inner middleware.
+///
+/// # Example
+///
+/// ```no_run
+/// use ethers_core::types::{Address, TransactionRequest};
+/// use ethers_middleware::MultiSignerMiddleware;
+/// use ethers_providers::{Http, Middleware, Provider};
+/// use ethers_signers::LocalWallet;
+/// use std::convert::TryFrom;
+///
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// let provider = Provider::<Http>::try_from("http://localhost:8545")?;
+/// let alice: LocalWallet = "380eb0f3d505f087e438eca80bc4df9a7faa24f868e69fc0440261a0fc0567dc"
+///     .parse()?;
+/// let bob: LocalWallet = "cd8c407233c0560f6de24bb2dc60a8b02335c959a1a17f749ce6c1ccf63d74a7"
+///     .parse()?;
+/// let bob_address = ethers_signers::Signer::address(&bob);
+///
+/// let client = MultiSignerMiddleware::new(provider).with_signer(alice).with_signer(bob);
+///
+/// // sent by alice, the default sender
+/// let tx = TransactionRequest::pay("vitalik.eth", 100);
+/// client.send_transaction(tx, None).await?;
+///
+/// // signed by bob
+/// let tx = TransactionRequest::pay("vitalik.eth", 100).from(bob_address);
+/// client.send_transaction(tx, None).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`LocalWallet`]: ethers_signers::LocalWallet
+/// [`Ledger`]: https://docs.rs/ethers-signers/latest/ethers_signers/struct.Ledger.html
+pub struct MultiSignerMiddleware<M> {
+    inner: M,
+    signers: HashMap<Address, Arc<dyn DynSigner>>,
+    default_sender: Option<Address>,
+}
+
+#[derive(Error, Debug)]
+/// Error thrown when the client interacts with the blockchain
+pub enum MultiSignerMiddlewareError<M: Middleware> {
+    #[error("{0}")]
+    /// Thrown when the internal call to a signer fails
+    SignerError(BoxedSignerError),
+
+    #[error("{0}")]
+    /// Thrown when an internal middleware errors
+    MiddlewareError(M::Error),
+
+    /// Thrown if a signature is requested from an address without a signer
+    #[error("no signer for address {0:?}")]
+    UnknownSigner(Address),
+
+    /// Thrown if the signer's chain_id is different than the chain_id of the transaction
+    #[error("specified chain_id is different than the signer's chain_id")]
+    DifferentChainID,
+}
+
+impl<M: Middleware> MiddlewareError for MultiSignerMiddlewareError<M> {
+    type Inner = M::Error;
+
+    fn from_err(src: M::Error) -> Self {
+        MultiSignerMiddlewareError::MiddlewareError(src)
+    }
+
+    fn as_inner(&self) -> Option<&Self::Inner> {
+        match self {
+            MultiSignerMiddlewareError::MiddlewareError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<M> MultiSignerMiddleware<M>
+where
+    M: Middleware,
+{
+    /// Creates a new client without any signer
+    pub fn new(inner: M) -> Self {
+        Self { inner, signers: HashMap::new(), default_sender: None }
+    }
+
+    /// Adds a signer, replacing any signer with the same address. The first signer added becomes
+    /// the default sender.
+    #[must_use]
+    pub fn with_signer<S>(mut self, signer: S) -> Self
+    where
+        S: Signer + 'static,
+        S::Error: 'static,
+    {
+        self.add_signer(signer);
+        self
+    }
+
+    /// Adds a signer, replacing any signer with the same address, and returns its address. The
+    /// first signer added becomes the default sender.
+    pub fn add_signer<S>(&mut self, signer: S) -> Address
+    where
+        S: Signer + 'static,
+        S::Error: 'static,
+    {
+        let address = signer.address();
+        self.signers.insert(address, Arc::new(signer));
+        self.default_sender.get_or_insert(address);
+        address
+    }
+
+    /// Removes the signer of `address`, returning whether there was one. If it was the default
+    /// sender, there is no default sender anymore.
+    pub fn remove_signer(&mut self, address: &Address) -> bool {
+        if self.default_sender.as_ref() == Some(address) {
+            self.default_sender = None;
+        }
+        self.signers.remove(address).is_some()
+    }
+
+    /// Sets the sender of the transactions without a `from` address.
+    #[must_use]
+    pub fn with_default_sender(mut self, address: Address) -> Self {
+        self.default_sender = Some(address);
+        self
+    }
+
+    /// Returns whether there is a signer for `address`
+    pub fn has_signer(&self, address: &Address) -> bool {
+        self.signers.contains_key(address)
+    }
+
+    /// Returns the addresses of the signers, sorted
+    pub fn addresses(&self) -> Vec<Address> {
+        let mut addresses: Vec<_> = self.signers.keys().copied().collect();
+        addresses.sort();
+        addresses
+    }
+
+    fn signer(&self, address: &Address) -> Result<&dyn DynSigner, MultiSignerMiddlewareError<M>> {
+        self.signers
+            .get(address)
+            .map(|signer| signer.as_ref())
+            .ok_or(MultiSignerMiddlewareError::UnknownSigner(*address))
+    }
+
+    /// Signs the transaction with the signer of `from` and returns its RLP encoding.
+    /// If the transaction does not have a chain id set, it sets it to the signer's chain id.
+    async fn sign_raw(
+        &self,
+        mut tx: TypedTransaction,
+        from: &Address,
+    ) -> Result<Bytes, MultiSignerMiddlewareError<M>> {
+        let signer = self.signer(from)?;
+        let chain_id = signer.chain_id();
+        match tx.chain_id() {
+            Some(id) if id.as_u64() != chain_id => {
+                return Err(MultiSignerMiddlewareError::DifferentChainID)
+            }
+            None => {
+                tx.set_chain_id(chain_id);
+            }
+            _ => {}
+        }
+
+        let signature =
+            signer.sign_transaction(&tx).await.map_err(MultiSignerMiddlewareError::SignerError)?;
+        Ok(tx.rlp_signed_network(&signature))
+    }
+
+    fn set_tx_from_if_none(&self, tx: &TypedTransaction) -> TypedTransaction {
+        let mut tx = tx.clone();
+        if let (None, Some(from)) = (tx.from(), self.default_sender) {
+            tx.set_from(from);
+        }
+        tx
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<M> Middleware for MultiSignerMiddleware<M>
+where
+    M: Middleware,
+{
+    type Error = MultiSignerMiddlewareError<M>;
+    type Provider = M::Provider;
+    type Inner = M;
+
+    fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    fn default_sender(&self) -> Option<Address> {
+        self.default_sender.or_else(|| self.inner.default_sender())
+    }
+
+    async fn is_signer(&self) -> bool {
+        !self.signers.is_empty()
+    }
+
+    /// Returns the addresses of the signers
+    async fn get_accounts(&self) -> Result<Vec<Address>, Self::Error> {
+        Ok(self.addresses())
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &TypedTransaction,
+        from: Address,
+    ) -> Result<Signature, Self::Error> {
+        self.signer(&from)?
+            .sign_transaction(tx)
+            .await
+            .map_err(MultiSignerMiddlewareError::SignerError)
+    }
+
+    /// Helper for filling a transaction's nonce and chain id using the signer of its `from`
+    /// address
+    async fn fill_transaction(
+        &self,
+        tx: &mut TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<(), Self::Error> {
+        if tx.from().is_none() {
+            if let Some(from) = self.default_sender {
+                tx.set_from(from);
+            }
+        }
+
+        if let Some(signer) = tx.from().and_then(|from| self.signers.get(from)) {
+            if tx.chain_id().is_none() {
+                tx.set_chain_id(signer.chain_id());
+            }
+
+            // If a chain_id is matched to a known chain that doesn't support EIP-1559,
+            // automatically change transaction to be Legacy type.
+            if let Some(chain_id) = tx.chain_id() {
+                let chain = Chain::try_from(chain_id.as_u64());
+                if chain.unwrap_or_default().is_legacy() {
+                    if let TypedTransaction::Eip1559(inner) = tx {
+                        let tx_req: TransactionRequest = inner.clone().into();
+                        *tx = TypedTransaction::Legacy(tx_req);
+                    }
+                }
+            }
+
+            let from = *tx.from().unwrap();
+            let nonce = maybe(tx.nonce().cloned(), self.get_transaction_count(from, block)).await?;
+            tx.set_nonce(nonce);
+        }
+
+        self.inner()
+            .fill_transaction(tx, block)
+            .await
+            .map_err(MultiSignerMiddlewareError::MiddlewareError)
+    }
+
+    /// Signs and broadcasts the transaction with the signer of its `from` address, or delegates
+    /// it to the inner middleware if there is no such signer.
+    async fn send_transaction<T: Into<TypedTransaction> + Send + Sync>(
+        &self,
+        tx: T,
+        block: Option<BlockId>,
+    ) -> Result<PendingTransaction<'_, Self::Provider>, Self::Error> {
+        let mut tx = tx.into();
+
+        // fill any missing fields
+        self.fill_transaction(&mut tx, block).await?;
+
+        let from = match tx.from() {
+            Some(from) if self.has_signer(from) => *from,
+            _ => {
+                return self
+                    .inner
+                    .send_transaction(tx, block)
+                    .await
+                    .map_err(MultiSignerMiddlewareError::MiddlewareError)
+            }
+        };
+
+        let signed_tx = self.sign_raw(tx, &from).await?;
+        self.inner
+            .send_raw_transaction(signed_tx)
+            .await
+            .map_err(MultiSignerMiddlewareError::MiddlewareError)
+    }
+
+    /// Signs a message with the signer of `from`
+    async fn sign<T: Into<Bytes> + Send + Sync>(
+        &self,
+        data: T,
+        from: &Address,
+    ) -> Result<Signature, Self::Error> {
+        self.signer(from)?
+            .sign_message(data.into())
+            .await
+            .map_err(MultiSignerMiddlewareError::SignerError)
+    }
+
+    async fn estimate_gas(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<U256, Self::Error> {
+        let tx = self.set_tx_from_if_none(tx);
+        self.inner
+            .estimate_gas(&tx, block)
+            .await
+            .map_err(MultiSignerMiddlewareError::MiddlewareError)
+    }
+
+    async fn create_access_list(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<AccessListWithGasUsed, Self::Error> {
+        let tx = self.set_tx_from_if_none(tx);
+        self.inner
+            .create_access_list(&tx, block)
+            .await
+            .map_err(MultiSignerMiddlewareError::MiddlewareError)
+    }
+
+    async fn call(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<Bytes, Self::Error> {
+        let tx = self.set_tx_from_if_none(tx);
+        self.inner.call(&tx, block).await.map_err(MultiSignerMiddlewareError::MiddlewareError)
+    }
+}
@@ -1,7 +1,13 @@
 use async_trait::async_trait;
 use ethers_core::types::{transaction::eip2718::TypedTransaction, *};
 use ethers_providers::{Middleware, MiddlewareError, PendingTransaction};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
 use thiserror::Error;
 
 #[derive(Debug)]
@@ -164,3 +170,246 @@ where
         }
     }
 }
+
+/// Messages of the errors returned by nodes when the nonce of a transaction is already used, which
+/// means that the local nonces are behind the node
+const NONCE_ERRORS: &[&str] = &["nonce too low", "replacement transaction underpriced"];
+
+/// Messages of the errors returned by nodes when the same signed transaction is already in their
+/// pool
+const ALREADY_KNOWN_ERRORS: &[&str] = &["already known", "known transaction"];
+
+fn error_matches<E: MiddlewareError>(err: &E, messages: &[&str]) -> bool {
+    let message = match err.as_error_response() {
+        Some(response) => response.message.to_lowercase(),
+        None => err.to_string().to_lowercase(),
+    };
+    messages.iter().any(|expected| message.contains(expected))
+}
+
+/// Returns `true` if the node rejected a transaction because its nonce is already used
+pub(crate) fn is_nonce_error<E: MiddlewareError>(err: &E) -> bool {
+    error_matches(err, NONCE_ERRORS)
+}
+
+/// Returns `true` if the node rejected a transaction because it already has it in its pool
+pub(crate) fn is_already_known<E: MiddlewareError>(err: &E) -> bool {
+    error_matches(err, ALREADY_KNOWN_ERRORS)
+}
+
+#[derive(Debug, Default)]
+struct AccountNonces {
+    // the next nonce to reserve, `None` until synchronized with the node
+    next: Option<U256>,
+    // nonces below `next` which were released, and are reserved again first
+    released: BTreeSet<U256>,
+    // nonces which were reserved and not released, and are not known to be mined
+    pending: BTreeSet<U256>,
+}
+
+#[derive(Debug)]
+/// Middleware calculating nonces locally for any number of sender addresses.
+///
+/// Unlike the [`NonceManagerMiddleware`], the nonces are tracked per sender, i.e. the `from` of
+/// each transaction, or the default sender of the inner middleware. Transactions which already
+/// have a nonce are sent untouched.
+///
+/// The nonces of an address are synchronized with the node when the first nonce is reserved, and
+/// again whenever the node rejects a transaction because its nonce was already used ("nonce too
+/// low" or "replacement transaction underpriced"), in which case the transaction is retried once
+/// with a fresh nonce. If the node reports the transaction as "already known", the same signed
+/// transaction is already pending, so it is not resent: the nonces are synchronized and the error
+/// is returned. When the inner middleware fails to send a transaction for
+/// another reason, its nonce is released and reused by the next transaction of the sender, so
+/// that no gap is left.
+pub struct MultiNonceManagerMiddleware<M> {
+    inner: M,
+    accounts: Mutex<HashMap<Address, Arc<futures_locks::Mutex<AccountNonces>>>>,
+}
+
+impl<M> MultiNonceManagerMiddleware<M>
+where
+    M: Middleware,
+{
+    /// Instantiates the nonce manager, without any tracked address
+    pub fn new(inner: M) -> Self {
+        Self { inner, accounts: Default::default() }
+    }
+
+    fn account(&self, address: Address) -> Arc<futures_locks::Mutex<AccountNonces>> {
+        self.accounts.lock().unwrap().entry(address).or_default().clone()
+    }
+
+    /// Returns the addresses whose nonces are tracked
+    pub fn accounts(&self) -> Vec<Address> {
+        self.accounts.lock().unwrap().keys().copied().collect()
+    }
+
+    /// Returns the nonces of `address` which were reserved and are not known to be mined yet
+    pub async fn pending(&self, address: Address) -> BTreeSet<U256> {
+        self.account(address).lock().await.pending.clone()
+    }
+
+    /// Returns the next nonce which will be reserved for `address`, or `None` if the nonces of
+    /// the address were not synchronized with the node yet
+    pub async fn next_nonce(&self, address: Address) -> Option<U256> {
+        let account = self.account(address);
+        let account = account.lock().await;
+        account.released.iter().next().copied().or(account.next)
+    }
+
+    async fn sync_account(
+        &self,
+        address: Address,
+        account: &mut AccountNonces,
+        block: Option<BlockId>,
+    ) -> Result<U256, NonceManagerError<M>> {
+        let mined = self
+            .inner
+            .get_transaction_count(address, Some(BlockNumber::Latest.into()))
+            .await
+            .map_err(MiddlewareError::from_err)?;
+        let next = self
+            .inner
+            .get_transaction_count(address, block.or(Some(BlockNumber::Pending.into())))
+            .await
+            .map_err(MiddlewareError::from_err)?;
+
+        account.pending.retain(|nonce| *nonce >= mined && *nonce < next);
+        account.released.clear();
+        account.next = Some(next);
+        Ok(next)
+    }
+
+    /// Synchronizes the nonces of `address` with the node, dropping the pending nonces which were
+    /// mined or which the node does not know about. Returns the next nonce.
+    pub async fn resync(
+        &self,
+        address: Address,
+        block: Option<BlockId>,
+    ) -> Result<U256, NonceManagerError<M>> {
+        let account = self.account(address);
+        let mut account = account.lock().await;
+        self.sync_account(address, &mut account, block).await
+    }
+
+    /// Reserves the next nonce of `address`, synchronizing the nonces with the node on first use
+    pub async fn reserve(
+        &self,
+        address: Address,
+        block: Option<BlockId>,
+    ) -> Result<U256, NonceManagerError<M>> {
+        let account = self.account(address);
+        let mut account = account.lock().await;
+
+        let released = account.released.iter().next().copied();
+        let nonce = match released {
+            Some(nonce) => {
+                account.released.remove(&nonce);
+                nonce
+            }
+            None => {
+                let next = match account.next {
+                    Some(next) => next,
+                    None => self.sync_account(address, &mut account, block).await?,
+                };
+                account.next = Some(next + 1);
+                next
+            }
+        };
+        account.pending.insert(nonce);
+        Ok(nonce)
+    }
+
+    /// Releases a nonce reserved for a transaction which was not broadcast, so that it is reserved
+    /// again by the next transaction of `address` instead of leaving a gap.
+    pub async fn release(&self, address: Address, nonce: U256) {
+        let account = self.account(address);
+        let mut account = account.lock().await;
+        if !account.pending.remove(&nonce) {
+            return
+        }
+
+        account.released.insert(nonce);
+        // released nonces right below the next one don't need to be tracked
+        while let Some(next) = account.next {
+            if next.is_zero() || !account.released.remove(&(next - 1)) {
+                break
+            }
+            account.next = Some(next - 1);
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<M> Middleware for MultiNonceManagerMiddleware<M>
+where
+    M: Middleware,
+{
+    type Error = NonceManagerError<M>;
+    type Provider = M::Provider;
+    type Inner = M;
+
+    fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    async fn fill_transaction(
+        &self,
+        tx: &mut TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<(), Self::Error> {
+        if tx.nonce().is_none() {
+            if let Some(from) = tx.from().copied().or_else(|| self.default_sender()) {
+                tx.set_nonce(self.reserve(from, block).await?);
+            }
+        }
+
+        Ok(self.inner().fill_transaction(tx, block).await.map_err(MiddlewareError::from_err)?)
+    }
+
+    /// Reserves the next nonce of the sender, then signs and broadcasts the transaction. The
+    /// transaction is retried once with a fresh nonce if the node rejects its nonce, but never if
+    /// the node already knows the transaction. The nonce is released if the inner middleware fails
+    /// for another reason.
+    async fn send_transaction<T: Into<TypedTransaction> + Send + Sync>(
+        &self,
+        tx: T,
+        block: Option<BlockId>,
+    ) -> Result<PendingTransaction<'_, Self::Provider>, Self::Error> {
+        let mut tx = tx.into();
+
+        let from = tx.from().copied().or_else(|| self.default_sender());
+        let (Some(from), None) = (from, tx.nonce()) else {
+            return self.inner.send_transaction(tx, block).await.map_err(MiddlewareError::from_err)
+        };
+        tx.set_from(from);
+
+        let mut resynced = false;
+        loop {
+            let nonce = self.reserve(from, block).await?;
+            tx.set_nonce(nonce);
+
+            match self.inner.send_transaction(tx.clone(), block).await {
+                Ok(pending) => return Ok(pending),
+                // resending with another nonce would duplicate the pending transaction
+                Err(err) if is_already_known(&err) => {
+                    self.resync(from, block).await?;
+                    return Err(MiddlewareError::from_err(err))
+                }
+                Err(err) if is_nonce_error(&err) => {
+                    self.resync(from, block).await?;
+                    if resynced {
+                        return Err(MiddlewareError::from_err(err))
+                    }
+                    resynced = true;
+                }
+                Err(err) => {
+                    self.release(from, nonce).await;
+                    return Err(MiddlewareError::from_err(err))
+                }
+            }
+        }
+    }
+}
@@ -155,7 +155,7 @@ where
             self.signer.sign_transaction(&tx).await.map_err(SignerMiddlewareError::SignerError)?;
 
         // Return the raw rlp-encoded signed transaction
-        Ok(tx.rlp_signed(&signature))
+        Ok(tx.rlp_signed_network(&signature))
     }
 
     /// Returns the client's address
@@ -0,0 +1,473 @@
+use super::{Transformer, TransformerError};
+use ethers_contract::{builders::ContractCall, AbiError, BaseContract, ContractError};
+use ethers_core::{
+    abi::{encode, parse_abi, Token},
+    types::{
+        transaction::{
+            eip2718::TypedTransaction,
+            eip712::{EIP712Domain, Eip712},
+        },
+        Address, Bytes, Signature, H256, U256,
+    },
+    utils::keccak256,
+};
+use ethers_providers::Middleware;
+use ethers_signers::Signer;
+use std::{collections::BTreeMap, convert::Infallible, sync::Arc};
+
+/// The function signature of the Safe's execTransaction function
+const SAFE_EXEC_TRANSACTION: &str =
+    "function execTransaction(address to, uint256 value, bytes data, uint8 operation, uint256 safeTxGas, uint256 baseGas, uint256 gasPrice, address gasToken, address refundReceiver, bytes signatures) public payable returns (bool success)";
+/// The function signature of the Safe's nonce getter
+const SAFE_NONCE: &str = "function nonce() public view returns (uint256)";
+
+/// The EIP-712 type of a Safe transaction
+const SAFE_TX_TYPE: &str =
+    "SafeTx(address to,uint256 value,bytes data,uint8 operation,uint256 safeTxGas,uint256 baseGas,uint256 gasPrice,address gasToken,address refundReceiver,uint256 nonce)";
+
+/// The kind of call a Safe transaction makes to its target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SafeOperation {
+    /// A regular call
+    #[default]
+    Call = 0,
+    /// A delegate call, executing the target's code in the context of the Safe
+    DelegateCall = 1,
+}
+
+/// A transaction to be executed by a Safe, once signed by enough owners
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SafeTransaction {
+    /// The target of the transaction
+    pub to: Address,
+    /// The ether value sent by the Safe
+    pub value: U256,
+    /// The calldata
+    pub data: Bytes,
+    /// Whether to call or delegate call the target
+    pub operation: SafeOperation,
+    /// The gas available to the transaction, 0 to use all the available gas
+    pub safe_tx_gas: U256,
+    /// The gas costs independent of the transaction execution, refunded to the relayer
+    pub base_gas: U256,
+    /// The gas price used for the refund, 0 for no refund
+    pub gas_price: U256,
+    /// The token used for the refund, the zero address for ether
+    pub gas_token: Address,
+    /// The receiver of the refund, the zero address for `tx.origin`
+    pub refund_receiver: Address,
+    /// The nonce of the Safe
+    pub nonce: U256,
+}
+
+impl SafeTransaction {
+    /// Creates a transaction calling `to` with `value` and `data`, without refund
+    pub fn call(to: Address, value: U256, data: Bytes, nonce: U256) -> Self {
+        Self { to, value, data, nonce, ..Default::default() }
+    }
+}
+
+/// The EIP-712 payload signed by the owners of a Safe to approve a [`SafeTransaction`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SafeTx<'a> {
+    /// The address of the Safe
+    pub safe: Address,
+    /// The chain id of the Safe
+    pub chain_id: U256,
+    /// The transaction
+    pub tx: &'a SafeTransaction,
+}
+
+impl<'a> Eip712 for SafeTx<'a> {
+    type Error = Infallible;
+
+    /// The domain of Safes from version 1.3.0 on
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(EIP712Domain {
+            name: None,
+            version: None,
+            chain_id: Some(self.chain_id),
+            verifying_contract: Some(self.safe),
+            salt: None,
+        })
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(SAFE_TX_TYPE))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        let tx = self.tx;
+        Ok(keccak256(encode(&[
+            Token::FixedBytes(Self::type_hash()?.to_vec()),
+            Token::Address(tx.to),
+            Token::Uint(tx.value),
+            Token::FixedBytes(keccak256(&tx.data).to_vec()),
+            Token::Uint((tx.operation as u8).into()),
+            Token::Uint(tx.safe_tx_gas),
+            Token::Uint(tx.base_gas),
+            Token::Uint(tx.gas_price),
+            Token::Address(tx.gas_token),
+            Token::Address(tx.refund_receiver),
+            Token::Uint(tx.nonce),
+        ])))
+    }
+}
+
+/// The owner signatures approving a [`SafeTransaction`], encoded in the ascending order of the
+/// owner addresses as the Safe requires.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SafeSignatures {
+    signatures: BTreeMap<Address, Signature>,
+}
+
+impl SafeSignatures {
+    /// Creates an empty set of signatures
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the signature of `owner`, e.g. from [`Safe::sign`], replacing any previous one
+    pub fn insert(&mut self, owner: Address, signature: Signature) {
+        self.signatures.insert(owner, signature);
+    }
+
+    /// Adds a pre-validated signature of `owner`, which is valid if `owner` sends the execution
+    /// transaction or approved the transaction hash on-chain with `approveHash`
+    pub fn insert_pre_validated(&mut self, owner: Address) {
+        let r = U256::from_big_endian(H256::from(owner).as_bytes());
+        self.insert(owner, Signature { r, s: U256::zero(), v: 1 });
+    }
+
+    /// Returns the owners which signed, in ascending order
+    pub fn owners(&self) -> impl Iterator<Item = &Address> + '_ {
+        self.signatures.keys()
+    }
+
+    /// Returns the number of signatures
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    /// Returns whether there are no signatures
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    /// Returns the signatures as expected by `execTransaction`
+    pub fn encode(&self) -> Bytes {
+        self.signatures.values().flat_map(<[u8; 65]>::from).collect::<Vec<u8>>().into()
+    }
+}
+
+/// Represents a [Safe](https://safe.global) smart-contract account, from version 1.3.0 on. It
+/// computes the EIP-712 hash of [`SafeTransaction`]s, signs them with any [`Signer`] and builds
+/// the `execTransaction` call executing them once enough owners signed.
+///
+/// It also implements the [Transformer](super::Transformer) trait, routing transactions through
+/// the Safe with a pre-validated signature of their `from` address, which must be an owner of a
+/// Safe with a threshold of 1.
+///
+/// # Example
+///
+/// ```no_run
+/// use ethers_core::types::{Address, Bytes, U256};
+/// use ethers_middleware::transformer::{Safe, SafeSignatures, SafeTransaction};
+/// use ethers_providers::{Http, Middleware, Provider};
+/// use ethers_signers::LocalWallet;
+/// use std::{convert::TryFrom, sync::Arc};
+///
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// type HttpProvider = Provider<Http>;
+///
+/// let provider = Arc::new(HttpProvider::try_from("http://localhost:8545")?);
+/// let owners: Vec<LocalWallet> = vec![/* ... */];
+/// # let safe_addr = Address::random();
+/// let safe = Safe::new(safe_addr, provider.get_chainid().await?);
+///
+/// let nonce = safe.nonce::<HttpProvider, _>(provider.clone()).await?;
+/// let tx = SafeTransaction::call(Address::random(), U256::exp10(18), Bytes::new(), nonce);
+///
+/// let mut signatures = SafeSignatures::new();
+/// for owner in &owners {
+///     signatures.insert(ethers_signers::Signer::address(owner), safe.sign(&tx, owner).await?);
+/// }
+///
+/// // anyone can execute the transaction once it is signed
+/// let call = safe.exec_transaction::<HttpProvider, _>(provider, &tx, &signatures)?;
+/// let receipt = call.send().await?.await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Safe {
+    address: Address,
+    chain_id: U256,
+    contract: BaseContract,
+}
+
+impl Safe {
+    /// Creates a new instance of Safe by providing the address of the Safe and the chain it was
+    /// deployed to.
+    pub fn new(address: Address, chain_id: impl Into<U256>) -> Self {
+        let contract =
+            parse_abi(&[SAFE_EXEC_TRANSACTION, SAFE_NONCE]).expect("could not parse ABI").into();
+        Self { address, chain_id: chain_id.into(), contract }
+    }
+
+    /// The address of the Safe.
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Returns the current nonce of the Safe, to be used by the next transaction.
+    pub async fn nonce<M: Middleware, C: Into<Arc<M>>>(
+        &self,
+        client: C,
+    ) -> Result<U256, ContractError<M>> {
+        let safe = self.contract.clone().into_contract(self.address, client.into());
+        safe.method::<_, U256>("nonce", ())?.call().await
+    }
+
+    /// Returns the EIP-712 payload of `tx`
+    pub fn typed_data<'a>(&self, tx: &'a SafeTransaction) -> SafeTx<'a> {
+        SafeTx { safe: self.address, chain_id: self.chain_id, tx }
+    }
+
+    /// Returns the hash of `tx` signed by the owners
+    pub fn transaction_hash(&self, tx: &SafeTransaction) -> H256 {
+        self.typed_data(tx).encode_eip712().unwrap_or_else(|err| match err {}).into()
+    }
+
+    /// Signs `tx` with the EIP-712 signature of `signer`.
+    pub async fn sign<S: Signer>(
+        &self,
+        tx: &SafeTransaction,
+        signer: &S,
+    ) -> Result<Signature, S::Error> {
+        let mut signature = signer.sign_typed_data(&self.typed_data(tx)).await?;
+        // the Safe only accepts the 'Electrum' notation
+        if signature.v < 27 {
+            signature.v += 27;
+        }
+        Ok(signature)
+    }
+
+    /// Signs `tx` with the `eth_sign` signature of `signer`, i.e. by signing the EIP-191 prefixed
+    /// transaction hash, for signers which cannot sign EIP-712 payloads.
+    pub async fn sign_message<S: Signer>(
+        &self,
+        tx: &SafeTransaction,
+        signer: &S,
+    ) -> Result<Signature, S::Error> {
+        let mut signature = signer.sign_message(self.transaction_hash(tx)).await?;
+        if signature.v < 27 {
+            signature.v += 27;
+        }
+        // the Safe identifies `eth_sign` signatures by a `v` greater than 30
+        signature.v += 4;
+        Ok(signature)
+    }
+
+    /// Returns the calldata of the `execTransaction` call executing `tx`
+    pub fn exec_transaction_data(
+        &self,
+        tx: &SafeTransaction,
+        signatures: &SafeSignatures,
+    ) -> Result<Bytes, AbiError> {
+        self.contract.encode("execTransaction", self.exec_transaction_args(tx, signatures))
+    }
+
+    /// Executes `tx` with the given owner signatures.
+    pub fn exec_transaction<M: Middleware, C: Into<Arc<M>>>(
+        &self,
+        client: C,
+        tx: &SafeTransaction,
+        signatures: &SafeSignatures,
+    ) -> Result<ContractCall<M, bool>, ContractError<M>> {
+        let safe = self.contract.clone().into_contract(self.address, client.into());
+        Ok(safe.method("execTransaction", self.exec_transaction_args(tx, signatures))?)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn exec_transaction_args(
+        &self,
+        tx: &SafeTransaction,
+        signatures: &SafeSignatures,
+    ) -> (Address, U256, Bytes, u8, U256, U256, U256, Address, Address, Bytes) {
+        (
+            tx.to,
+            tx.value,
+            tx.data.clone(),
+            tx.operation as u8,
+            tx.safe_tx_gas,
+            tx.base_gas,
+            tx.gas_price,
+            tx.gas_token,
+            tx.refund_receiver,
+            signatures.encode(),
+        )
+    }
+}
+
+impl Transformer for Safe {
+    fn transform(&self, tx: &mut TypedTransaction) -> Result<(), TransformerError> {
+        // the target address and the owner sending the transaction cannot be None.
+        let target =
+            *tx.to_addr().ok_or_else(|| TransformerError::MissingField("to".to_string()))?;
+        let owner = *tx.from().ok_or_else(|| TransformerError::MissingField("from".to_string()))?;
+
+        // the nonce is not part of the calldata, the Safe uses its current one.
+        let safe_tx = SafeTransaction::call(
+            target,
+            tx.value().cloned().unwrap_or_default(),
+            tx.data().cloned().unwrap_or_default(),
+            U256::zero(),
+        );
+        let mut signatures = SafeSignatures::new();
+        signatures.insert_pre_validated(owner);
+
+        // update appropriate fields of the proxy tx, the value being paid by the Safe.
+        tx.set_data(self.exec_transaction_data(&safe_tx, &signatures)?);
+        tx.set_to(self.address);
+        tx.set_value(U256::zero());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_core::types::transaction::eip712::TypedData;
+    use ethers_signers::LocalWallet;
+
+    fn transaction() -> SafeTransaction {
+        SafeTransaction {
+            to: "0x5FbDB2315678afecb367f032d93F642f64180aa3".parse().unwrap(),
+            value: U256::exp10(18),
+            data: vec![0xde, 0xad, 0xbe, 0xef].into(),
+            operation: SafeOperation::DelegateCall,
+            safe_tx_gas: 50_000.into(),
+            base_gas: 21_000.into(),
+            gas_price: 1_000_000_000.into(),
+            gas_token: Address::repeat_byte(0x11),
+            refund_receiver: Address::repeat_byte(0x22),
+            nonce: 7.into(),
+        }
+    }
+
+    #[test]
+    fn safe_tx_hash_matches_typed_data() {
+        let safe = Safe::new(Address::repeat_byte(0x33), 5u64);
+        let tx = transaction();
+
+        let typed_data: TypedData = serde_json::from_value(serde_json::json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" }
+                ],
+                "SafeTx": [
+                    { "name": "to", "type": "address" },
+                    { "name": "value", "type": "uint256" },
+                    { "name": "data", "type": "bytes" },
+                    { "name": "operation", "type": "uint8" },
+                    { "name": "safeTxGas", "type": "uint256" },
+                    { "name": "baseGas", "type": "uint256" },
+                    { "name": "gasPrice", "type": "uint256" },
+                    { "name": "gasToken", "type": "address" },
+                    { "name": "refundReceiver", "type": "address" },
+                    { "name": "nonce", "type": "uint256" }
+                ]
+            },
+            "primaryType": "SafeTx",
+            "domain": { "chainId": 5, "verifyingContract": safe.address() },
+            "message": {
+                "to": tx.to,
+                "value": tx.value.to_string(),
+                "data": tx.data,
+                "operation": 1,
+                "safeTxGas": 50000,
+                "baseGas": 21000,
+                "gasPrice": "1000000000",
+                "gasToken": tx.gas_token,
+                "refundReceiver": tx.refund_receiver,
+                "nonce": 7
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(safe.transaction_hash(&tx), H256::from(typed_data.encode_eip712().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn encodes_ordered_owner_signatures() {
+        let safe = Safe::new(Address::repeat_byte(0x33), 1u64);
+        let tx = transaction();
+        let hash = safe.transaction_hash(&tx);
+
+        let mut signatures = SafeSignatures::new();
+        let mut owners = Vec::new();
+        for _ in 0..3 {
+            let wallet = LocalWallet::new(&mut rand::thread_rng());
+            let signature = safe.sign(&tx, &wallet).await.unwrap();
+            signature.verify(hash, wallet.address()).unwrap();
+            signatures.insert(wallet.address(), signature);
+            owners.push(wallet.address());
+        }
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let signature = safe.sign_message(&tx, &wallet).await.unwrap();
+        assert!(signature.v > 30);
+        signatures.insert(wallet.address(), signature);
+        owners.push(wallet.address());
+        owners.sort();
+
+        assert_eq!(signatures.owners().copied().collect::<Vec<_>>(), owners);
+        let encoded = signatures.encode();
+        assert_eq!(encoded.len(), 4 * 65);
+        for (owner, signature) in owners.iter().zip(encoded.chunks(65)) {
+            let mut signature = Signature::try_from(signature).unwrap();
+            if signature.v > 30 {
+                signature.v -= 4;
+                signature.verify(hash.as_bytes(), *owner).unwrap();
+            } else {
+                signature.verify(hash, *owner).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn transforms_into_exec_transaction() {
+        let safe = Safe::new(Address::repeat_byte(0x33), 1u64);
+        let owner = Address::repeat_byte(0x44);
+        let target = Address::repeat_byte(0x55);
+        let mut tx: TypedTransaction = ethers_core::types::TransactionRequest::new()
+            .from(owner)
+            .to(target)
+            .value(100)
+            .data(vec![1, 2, 3])
+            .into();
+        safe.transform(&mut tx).unwrap();
+
+        assert_eq!(tx.to_addr(), Some(&safe.address()));
+        assert_eq!(tx.value(), Some(&U256::zero()));
+        let (to, value, data, operation, .., signatures): (
+            Address,
+            U256,
+            Bytes,
+            u8,
+            U256,
+            U256,
+            U256,
+            Address,
+            Address,
+            Bytes,
+        ) = safe.contract.decode("execTransaction", tx.data().unwrap()).unwrap();
+        assert_eq!((to, value, data.to_vec(), operation), (target, 100.into(), vec![1, 2, 3], 0));
+        let mut expected = [0u8; 65];
+        expected[12..32].copy_from_slice(owner.as_bytes());
+        expected[64] = 1;
+        assert_eq!(signatures.to_vec(), expected.to_vec());
+    }
+}
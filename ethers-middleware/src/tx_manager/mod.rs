@@ -0,0 +1,492 @@
+mod store;
+pub use store::{FileStore, FileStoreError, MemoryStore, TxStore};
+
+use crate::{
+    gas_escalator::{escalate_fees, replacement_fee, GasEscalator},
+    nonce_manager::{is_already_known, is_nonce_error},
+};
+use async_trait::async_trait;
+use ethers_core::{
+    types::{
+        transaction::eip2718::TypedTransaction, Address, BlockId, BlockNumber, Bytes,
+        Eip1559TransactionRequest, TxHash, U256, U64,
+    },
+    utils::keccak256,
+};
+use ethers_providers::{Middleware, MiddlewareError, PendingTransaction};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+
+/// The status of a transaction recorded by the [`TxManagerMiddleware`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum TxStatus {
+    /// The transaction was broadcast and is not mined yet
+    Pending,
+    /// The transaction was mined
+    #[serde(rename_all = "camelCase")]
+    Confirmed {
+        /// The block in which the transaction was mined
+        block_number: U64,
+    },
+    /// Another transaction with the same nonce was mined
+    Replaced,
+    /// The node rejected the transaction
+    Failed,
+}
+
+/// A signed transaction recorded by the [`TxManagerMiddleware`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxRecord {
+    /// The hash of the signed transaction
+    pub hash: TxHash,
+    /// The sender of the transaction
+    pub from: Address,
+    /// The nonce of the transaction
+    pub nonce: U256,
+    /// The transaction, including its fees. Fields which are not serialized, such as the chain id
+    /// of legacy transactions, are lost when the record is reloaded, while `raw` is preserved.
+    /// See [`TxRecord::transaction`].
+    pub tx: TypedTransaction,
+    /// The chain id the transaction was signed for
+    pub chain_id: Option<U64>,
+    /// The signed transaction, as broadcast to the node
+    pub raw: Bytes,
+    /// The status of the transaction
+    #[serde(flatten)]
+    pub status: TxStatus,
+    /// The time at which a transaction with this nonce was first broadcast, in seconds since the
+    /// unix epoch. Replacements keep the time of the transaction they replace.
+    pub first_sent_at: u64,
+}
+
+/// The changes applied by [`TxManagerMiddleware::sync`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// The transactions which were mined
+    pub confirmed: Vec<TxRecord>,
+    /// The transactions whose nonce was used by another mined transaction
+    pub replaced: Vec<TxRecord>,
+    /// The pending transactions the node did not know about, which were broadcast again
+    pub rebroadcast: Vec<TxRecord>,
+}
+
+impl TxRecord {
+    /// Returns the transaction, with the chain id it was signed for
+    pub fn transaction(&self) -> TypedTransaction {
+        let mut tx = self.tx.clone();
+        if let Some(chain_id) = self.chain_id {
+            tx.set_chain_id(chain_id);
+        }
+        tx
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Returns the latest record of each nonce, which is the one to escalate or rebroadcast
+fn latest_by_nonce(records: Vec<TxRecord>) -> BTreeMap<U256, TxRecord> {
+    records.into_iter().map(|record| (record.nonce, record)).collect()
+}
+
+/// Bumps the fees of the transaction enough for the node to accept it as a replacement
+fn bump_eip1559_fees(tx: &mut Eip1559TransactionRequest) {
+    tx.max_fee_per_gas = tx.max_fee_per_gas.map(replacement_fee);
+    tx.max_priority_fee_per_gas = tx.max_priority_fee_per_gas.map(replacement_fee);
+}
+
+/// Middleware which signs the transactions of an address, and records each of them in a
+/// persistent [`TxStore`] before broadcasting it, so that pending transactions can be tracked
+/// across restarts.
+///
+/// Nonces are assigned locally, starting from the highest of the node's pending transaction count
+/// and of the stored pending transactions. After a restart, [`TxManagerMiddleware::sync`] updates
+/// the status of the stored transactions and re-broadcasts the ones the node forgot about.
+/// Pending transactions can be escalated with any [`GasEscalator`], and nonces which are stuck
+/// or skipped can be unblocked with zero-value [cancel](TxManagerMiddleware::cancel)
+/// transactions.
+///
+/// The inner middleware must be able to sign transactions of `address`, e.g. a
+/// [`SignerMiddleware`](crate::SignerMiddleware). Transactions sent from other addresses are
+/// delegated to the inner middleware.
+///
+/// ```no_run
+/// use ethers_middleware::{
+///     gas_escalator::GeometricGasPrice,
+///     tx_manager::{FileStore, TxManagerMiddleware},
+///     SignerMiddleware,
+/// };
+/// use ethers_providers::{Http, Middleware, Provider};
+/// use ethers_signers::{LocalWallet, Signer};
+/// use ethers_core::types::TransactionRequest;
+/// use std::{convert::TryFrom, time::Duration};
+///
+/// # async fn foo(wallet: LocalWallet) -> Result<(), Box<dyn std::error::Error>> {
+/// let provider = Provider::<Http>::try_from("http://localhost:8545")?;
+/// let address = wallet.address();
+/// let store = FileStore::open("transactions.jsonl")?;
+/// let client = TxManagerMiddleware::new(SignerMiddleware::new(provider, wallet), address, store);
+///
+/// // recover the transactions sent before the last restart
+/// let report = client.sync().await?;
+///
+/// let tx = TransactionRequest::pay("vitalik.eth", 100);
+/// client.send_transaction(tx, None).await?;
+///
+/// // periodically escalate the pending transactions, and cancel the stuck ones
+/// let escalator = GeometricGasPrice::new(1.125, 60u64, None::<u64>);
+/// client.escalate(&escalator, None).await?;
+/// for nonce in client.stuck_nonces(Duration::from_secs(600)).await? {
+///     client.cancel(nonce).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TxManagerMiddleware<M, S> {
+    inner: M,
+    address: Address,
+    store: S,
+    // the next nonce, initialized on first use
+    nonce: futures_locks::Mutex<Option<U256>>,
+}
+
+impl<M, S> TxManagerMiddleware<M, S>
+where
+    M: Middleware,
+    S: TxStore,
+{
+    /// Creates a new manager of the transactions sent by `address`, recorded in `store`
+    pub fn new(inner: M, address: Address, store: S) -> Self {
+        Self { inner, address, store, nonce: Default::default() }
+    }
+
+    /// Returns the address whose transactions are managed
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Returns the store of the transactions
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns the pending transactions, ordered by nonce
+    pub async fn pending(&self) -> Result<Vec<TxRecord>, TxManagerError<M, S>> {
+        self.store.pending(self.address).await.map_err(TxManagerError::StoreError)
+    }
+
+    async fn transaction_count(&self, block: BlockNumber) -> Result<U256, TxManagerError<M, S>> {
+        self.inner
+            .get_transaction_count(self.address, Some(block.into()))
+            .await
+            .map_err(MiddlewareError::from_err)
+    }
+
+    /// Signs the transaction, records it and broadcasts it
+    async fn broadcast(
+        &self,
+        tx: TypedTransaction,
+        first_sent_at: u64,
+    ) -> Result<(TxRecord, PendingTransaction<'_, M::Provider>), TxManagerError<M, S>> {
+        let signature = self
+            .inner
+            .sign_transaction(&tx, self.address)
+            .await
+            .map_err(MiddlewareError::from_err)?;
+        let raw = tx.rlp_signed_network(&signature);
+        let mut record = TxRecord {
+            hash: keccak256(&raw).into(),
+            from: self.address,
+            nonce: tx.nonce().copied().unwrap_or_default(),
+            chain_id: tx.chain_id(),
+            raw,
+            tx,
+            status: TxStatus::Pending,
+            first_sent_at,
+        };
+
+        // the transaction is recorded before it is broadcast, so that it can't be lost
+        self.store.save(&record).await.map_err(TxManagerError::StoreError)?;
+        match self.inner.send_raw_transaction(record.raw.clone()).await {
+            Ok(pending) => Ok((record, pending)),
+            Err(err) => {
+                record.status = TxStatus::Failed;
+                self.store.save(&record).await.map_err(TxManagerError::StoreError)?;
+                Err(MiddlewareError::from_err(err))
+            }
+        }
+    }
+
+    /// Updates the status of the pending transactions, and re-broadcasts the latest transaction
+    /// of each pending nonce if the node does not know about it, e.g. after a restart.
+    pub async fn sync(&self) -> Result<SyncReport, TxManagerError<M, S>> {
+        let mut report = SyncReport::default();
+        let mined_count = self.transaction_count(BlockNumber::Latest).await?;
+
+        let mut pending = Vec::new();
+        for mut record in self.pending().await? {
+            let receipt = self
+                .inner
+                .get_transaction_receipt(record.hash)
+                .await
+                .map_err(MiddlewareError::from_err)?;
+            match receipt.and_then(|receipt| receipt.block_number) {
+                Some(block_number) => record.status = TxStatus::Confirmed { block_number },
+                None if record.nonce < mined_count => record.status = TxStatus::Replaced,
+                None => {
+                    pending.push(record);
+                    continue
+                }
+            }
+            self.store.save(&record).await.map_err(TxManagerError::StoreError)?;
+            if record.status == TxStatus::Replaced {
+                report.replaced.push(record);
+            } else {
+                report.confirmed.push(record);
+            }
+        }
+
+        // transactions sharing the nonce of a mined transaction were replaced by it
+        for mut record in pending {
+            if report.confirmed.iter().any(|confirmed| confirmed.nonce == record.nonce) {
+                record.status = TxStatus::Replaced;
+                self.store.save(&record).await.map_err(TxManagerError::StoreError)?;
+                report.replaced.push(record);
+            }
+        }
+
+        for record in latest_by_nonce(self.pending().await?).into_values() {
+            let known = self
+                .inner
+                .get_transaction(record.hash)
+                .await
+                .map_err(MiddlewareError::from_err)?
+                .is_some();
+            if !known {
+                if let Err(err) = self.inner.send_raw_transaction(record.raw.clone()).await {
+                    tracing::warn!(hash = ?record.hash, %err, "failed to rebroadcast transaction");
+                    continue
+                }
+                report.rebroadcast.push(record);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Re-sends the latest transaction of each pending nonce with the fees returned by the
+    /// escalator, given the time elapsed since the nonce was first broadcast. The fees are bumped
+    /// by at least 10% so that nodes accept the replacements, and are capped at `fee_cap`.
+    ///
+    /// Returns the replacement transactions.
+    pub async fn escalate<E: GasEscalator>(
+        &self,
+        escalator: &E,
+        fee_cap: Option<U256>,
+    ) -> Result<Vec<TxRecord>, TxManagerError<M, S>> {
+        let now = now();
+        let mut replacements = Vec::new();
+        for record in latest_by_nonce(self.pending().await?).into_values() {
+            let mut tx = record.transaction();
+            let elapsed = now.saturating_sub(record.first_sent_at);
+            if !escalate_fees(escalator, &mut tx, elapsed, fee_cap) {
+                continue
+            }
+            match self.broadcast(tx, record.first_sent_at).await {
+                Ok((replacement, _)) => replacements.push(replacement),
+                // the nonce was mined or replaced in the meantime
+                Err(err) if is_nonce_error(&err) || is_already_known(&err) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(replacements)
+    }
+
+    /// Returns the nonces which were skipped: nonces which are not mined yet and have no pending
+    /// transaction, while higher nonces do. Transactions after a gap are never mined until the
+    /// gap is filled, e.g. with [`TxManagerMiddleware::cancel`].
+    pub async fn nonce_gaps(&self) -> Result<Vec<U256>, TxManagerError<M, S>> {
+        let pending = latest_by_nonce(self.pending().await?);
+        let Some(&highest) = pending.keys().next_back() else { return Ok(vec![]) };
+
+        let mut nonce = self.transaction_count(BlockNumber::Latest).await?;
+        let mut gaps = Vec::new();
+        while nonce < highest {
+            if !pending.contains_key(&nonce) {
+                gaps.push(nonce);
+            }
+            nonce += U256::one();
+        }
+        Ok(gaps)
+    }
+
+    /// Returns the nonces whose transactions have been pending for longer than `max_age` since
+    /// they were first broadcast.
+    pub async fn stuck_nonces(&self, max_age: Duration) -> Result<Vec<U256>, TxManagerError<M, S>> {
+        let now = now();
+        Ok(latest_by_nonce(self.pending().await?)
+            .into_values()
+            .filter(|record| now.saturating_sub(record.first_sent_at) >= max_age.as_secs())
+            .map(|record| record.nonce)
+            .collect())
+    }
+
+    /// Sends a zero-value transaction to the managed address with the given nonce, in order to
+    /// replace the pending transaction with this nonce, or to fill a nonce gap.
+    ///
+    /// If a transaction with this nonce is pending, its fees are bumped by 10% so that the node
+    /// accepts the replacement. Otherwise the fees are filled by the inner middleware.
+    pub async fn cancel(&self, nonce: U256) -> Result<TxRecord, TxManagerError<M, S>> {
+        let pending = latest_by_nonce(self.pending().await?).remove(&nonce);
+
+        let (mut tx, first_sent_at) = match pending {
+            Some(record) => {
+                let mut tx = record.transaction();
+                match &mut tx {
+                    TypedTransaction::Eip1559(inner) => bump_eip1559_fees(inner),
+                    TypedTransaction::Eip4844(inner) => {
+                        bump_eip1559_fees(&mut inner.tx);
+                        inner.max_fee_per_blob_gas =
+                            inner.max_fee_per_blob_gas.map(replacement_fee);
+                    }
+                    _ => {
+                        if let Some(gas_price) = tx.gas_price() {
+                            tx.set_gas_price(replacement_fee(gas_price));
+                        }
+                    }
+                }
+                (tx, record.first_sent_at)
+            }
+            None => {
+                let mut tx = TypedTransaction::default();
+                tx.set_from(self.address).set_nonce(nonce);
+                (tx, now())
+            }
+        };
+
+        tx.set_to(self.address)
+            .set_value(0)
+            .set_data(Bytes::new())
+            .set_gas(21_000)
+            .set_access_list(Default::default());
+        self.inner.fill_transaction(&mut tx, None).await.map_err(MiddlewareError::from_err)?;
+
+        let (record, _) = self.broadcast(tx, first_sent_at).await?;
+        Ok(record)
+    }
+
+    /// Returns the nonce to use for the next transaction, initializing it on first use
+    async fn next_nonce(
+        &self,
+        nonce: &mut Option<U256>,
+        block: Option<BlockId>,
+    ) -> Result<U256, TxManagerError<M, S>> {
+        if let Some(nonce) = *nonce {
+            return Ok(nonce)
+        }
+        let count = self
+            .inner
+            .get_transaction_count(self.address, block.or(Some(BlockNumber::Pending.into())))
+            .await
+            .map_err(MiddlewareError::from_err)?;
+        let stored =
+            self.pending().await?.last().map(|record| record.nonce + 1).unwrap_or_default();
+        Ok(*nonce.insert(count.max(stored)))
+    }
+}
+
+/// Error thrown when the client interacts with the transaction manager.
+#[derive(Error, Debug)]
+pub enum TxManagerError<M: Middleware, S: TxStore> {
+    /// Thrown when the internal middleware errors
+    #[error("{0}")]
+    MiddlewareError(M::Error),
+    /// Thrown when the store errors
+    #[error("{0}")]
+    StoreError(S::Error),
+}
+
+impl<M: Middleware, S: TxStore> MiddlewareError for TxManagerError<M, S> {
+    type Inner = M::Error;
+
+    fn from_err(src: M::Error) -> Self {
+        TxManagerError::MiddlewareError(src)
+    }
+
+    fn as_inner(&self) -> Option<&Self::Inner> {
+        match self {
+            TxManagerError::MiddlewareError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+impl<M, S> Middleware for TxManagerMiddleware<M, S>
+where
+    M: Middleware,
+    S: TxStore,
+{
+    type Error = TxManagerError<M, S>;
+    type Provider = M::Provider;
+    type Inner = M;
+
+    fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    /// Returns the managed address
+    fn default_sender(&self) -> Option<Address> {
+        Some(self.address)
+    }
+
+    /// Assigns the next nonce, then signs, records and broadcasts the transaction. Transactions
+    /// sent from other addresses are delegated to the inner middleware.
+    async fn send_transaction<T: Into<TypedTransaction> + Send + Sync>(
+        &self,
+        tx: T,
+        block: Option<BlockId>,
+    ) -> Result<PendingTransaction<'_, Self::Provider>, Self::Error> {
+        let mut tx = tx.into();
+        match tx.from() {
+            Some(from) if *from != self.address => {
+                return self
+                    .inner
+                    .send_transaction(tx, block)
+                    .await
+                    .map_err(MiddlewareError::from_err)
+            }
+            Some(_) => {}
+            None => {
+                tx.set_from(self.address);
+            }
+        }
+
+        // the lock is held until the transaction is broadcast, so that nonces are used in order
+        let mut nonce = self.nonce.lock().await;
+        let next = self.next_nonce(&mut nonce, block).await?;
+        let tx_nonce = tx.nonce().copied().unwrap_or(next);
+        tx.set_nonce(tx_nonce);
+        self.inner.fill_transaction(&mut tx, block).await.map_err(MiddlewareError::from_err)?;
+
+        match self.broadcast(tx, now()).await {
+            Ok((_, pending)) => {
+                *nonce = Some(next.max(tx_nonce + 1));
+                Ok(pending)
+            }
+            Err(err) => {
+                // the node may disagree with the local nonce, which is fetched again on next use
+                *nonce = None;
+                Err(err)
+            }
+        }
+    }
+}
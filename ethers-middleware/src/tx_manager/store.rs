@@ -0,0 +1,225 @@
+use super::{TxRecord, TxStatus};
+use async_trait::async_trait;
+use ethers_core::types::Address;
+use std::{
+    convert::Infallible,
+    fmt::Debug,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+
+/// Storage of the transactions sent by a [`TxManagerMiddleware`](super::TxManagerMiddleware),
+/// which must outlive the process in order to recover the pending transactions after a restart.
+#[async_trait]
+pub trait TxStore: Send + Sync + Debug {
+    type Error: std::error::Error + Send + Sync;
+
+    /// Inserts the record, or replaces the record of the transaction with the same hash
+    async fn save(&self, record: &TxRecord) -> Result<(), Self::Error>;
+
+    /// Returns the pending transactions sent by `from`, ordered by nonce and then by the order in
+    /// which they were first saved
+    async fn pending(&self, from: Address) -> Result<Vec<TxRecord>, Self::Error>;
+}
+
+/// Inserts the record in the list, or replaces the record with the same hash
+fn upsert(records: &mut Vec<TxRecord>, record: TxRecord) {
+    match records.iter_mut().find(|existing| existing.hash == record.hash) {
+        Some(existing) => *existing = record,
+        None => records.push(record),
+    }
+}
+
+/// Returns the pending records sent by `from`, ordered by nonce
+fn pending(records: &[TxRecord], from: Address) -> Vec<TxRecord> {
+    let mut pending: Vec<_> = records
+        .iter()
+        .filter(|record| record.from == from && record.status == TxStatus::Pending)
+        .cloned()
+        .collect();
+    pending.sort_by_key(|record| record.nonce);
+    pending
+}
+
+/// A store keeping the records in memory, which does not survive restarts.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    records: Mutex<Vec<TxRecord>>,
+}
+
+impl MemoryStore {
+    /// Returns all the records, including the ones which are not pending anymore
+    pub fn records(&self) -> Vec<TxRecord> {
+        self.records.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl TxStore for MemoryStore {
+    type Error = Infallible;
+
+    async fn save(&self, record: &TxRecord) -> Result<(), Self::Error> {
+        upsert(&mut self.records.lock().unwrap(), record.clone());
+        Ok(())
+    }
+
+    async fn pending(&self, from: Address) -> Result<Vec<TxRecord>, Self::Error> {
+        Ok(pending(&self.records.lock().unwrap(), from))
+    }
+}
+
+/// Error thrown when reading or writing a [`FileStore`]
+#[derive(Error, Debug)]
+pub enum FileStoreError {
+    /// Thrown when the file can't be read or written
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// Thrown when a line of the file is not a valid record
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// A store appending the records to a file, one JSON object per line.
+///
+/// Each update of a record is appended as a new line, and the last line of each transaction wins
+/// when the file is loaded. [`FileStore::compact`] rewrites the file with the pending records
+/// only.
+#[derive(Debug)]
+pub struct FileStore {
+    path: PathBuf,
+    inner: Arc<Mutex<FileStoreInner>>,
+}
+
+#[derive(Debug)]
+struct FileStoreInner {
+    file: File,
+    records: Vec<TxRecord>,
+}
+
+impl FileStore {
+    /// Opens the store at `path`, creating the file if it does not exist, and loads its records
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FileStoreError> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).read(true).append(true).open(&path)?;
+
+        let mut records = Vec::new();
+        for line in BufReader::new(&file).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                upsert(&mut records, serde_json::from_str(&line)?);
+            }
+        }
+
+        Ok(Self { path, inner: Arc::new(Mutex::new(FileStoreInner { file, records })) })
+    }
+
+    /// Returns the path of the file backing the store
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns all the records, including the ones which are not pending anymore
+    pub fn records(&self) -> Vec<TxRecord> {
+        self.inner.lock().unwrap().records.clone()
+    }
+
+    /// Rewrites the file with the pending records only, dropping the history of the transactions
+    /// which are not pending anymore
+    pub fn compact(&self) -> Result<(), FileStoreError> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.records.retain(|record| record.status == TxStatus::Pending);
+
+        // write to a temporary file first, so that the store is never left half written
+        let tmp = self.path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        for record in &inner.records {
+            writeln!(file, "{}", serde_json::to_string(record)?)?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+
+        inner.file = OpenOptions::new().append(true).open(&self.path)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TxStore for FileStore {
+    type Error = FileStoreError;
+
+    async fn save(&self, record: &TxRecord) -> Result<(), Self::Error> {
+        let line = serde_json::to_string(record)?;
+        let record = record.clone();
+        let inner = self.inner.clone();
+        // the file is written and synced on the blocking pool, so that the executor is not stalled
+        tokio::task::spawn_blocking(move || {
+            let mut inner = inner.lock().unwrap();
+            writeln!(inner.file, "{line}")?;
+            inner.file.sync_data()?;
+            upsert(&mut inner.records, record);
+            Ok(())
+        })
+        .await
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?
+    }
+
+    async fn pending(&self, from: Address) -> Result<Vec<TxRecord>, Self::Error> {
+        Ok(pending(&self.inner.lock().unwrap().records, from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_core::types::{Bytes, TransactionRequest, H256, U64};
+
+    fn record(from: Address, nonce: u64, status: TxStatus) -> TxRecord {
+        TxRecord {
+            hash: H256::random(),
+            from,
+            nonce: nonce.into(),
+            tx: TransactionRequest::new().nonce(nonce).gas_price(1).into(),
+            chain_id: Some(U64::one()),
+            raw: Bytes::from(vec![nonce as u8]),
+            status,
+            first_sent_at: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn file_store_reloads_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("txs.jsonl");
+        let from = Address::random();
+
+        let store = FileStore::open(&path).unwrap();
+        let mut first = record(from, 1, TxStatus::Pending);
+        let second = record(from, 0, TxStatus::Pending);
+        let other = record(Address::random(), 0, TxStatus::Pending);
+        for record in [&first, &second, &other] {
+            store.save(record).await.unwrap();
+        }
+        assert_eq!(store.pending(from).await.unwrap(), vec![second.clone(), first.clone()]);
+
+        // the last saved version of a record wins
+        first.status = TxStatus::Confirmed { block_number: U64::one() };
+        store.save(&first).await.unwrap();
+        drop(store);
+
+        let store = FileStore::open(&path).unwrap();
+        assert_eq!(store.pending(from).await.unwrap(), vec![second.clone()]);
+        assert_eq!(store.records().len(), 3);
+        assert_eq!(store.records()[0].transaction().chain_id(), Some(U64::one()));
+
+        store.compact().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        let third = record(from, 2, TxStatus::Pending);
+        store.save(&third).await.unwrap();
+
+        let store = FileStore::open(&path).unwrap();
+        assert_eq!(store.pending(from).await.unwrap(), vec![second, third]);
+    }
+}
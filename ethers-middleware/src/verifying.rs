@@ -0,0 +1,293 @@
+use async_trait::async_trait;
+use ethers_core::{
+    trie::{ProofError, KECCAK_EMPTY},
+    types::{
+        Address, BlockId, BlockNumber, Bytes, EIP1186ProofResponse, Header, NameOrAddress, H256,
+        U256,
+    },
+    utils::keccak256,
+};
+use ethers_providers::{Middleware, MiddlewareError};
+use std::fmt::Debug;
+use thiserror::Error;
+
+/// Source of block headers whose state root is trusted by the [`VerifyingMiddleware`].
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait TrustedHeaders: Sync + Send + Debug {
+    type Error: std::error::Error + Sync + Send;
+
+    /// Returns the trusted header of the requested block, or of the latest block if `None`.
+    async fn trusted_header(&self, block: Option<BlockId>) -> Result<Header, Self::Error>;
+}
+
+/// Error returned when no trusted header is known for the requested block.
+#[derive(Error, Debug)]
+#[error("no trusted header for block {0:?}")]
+pub struct UntrustedBlockError(pub Option<BlockId>);
+
+/// A single header supplied by the caller, which is used for requests at that header's number or
+/// hash.
+///
+/// Requests for the latest block, including those without a block, are rejected: a fixed header
+/// goes stale, so its state must not be served as the latest one. Use a [`TrustedProvider`] to
+/// follow the chain.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl TrustedHeaders for Header {
+    type Error = UntrustedBlockError;
+
+    async fn trusted_header(&self, block: Option<BlockId>) -> Result<Header, Self::Error> {
+        let is_trusted = match block {
+            Some(BlockId::Number(BlockNumber::Number(number))) => number == self.number,
+            Some(BlockId::Hash(hash)) => hash == self.hash_slow(),
+            None | Some(BlockId::Number(_)) => false,
+        };
+        if !is_trusted {
+            return Err(UntrustedBlockError(block))
+        }
+        Ok(self.clone())
+    }
+}
+
+/// Uses the headers returned by a separate provider which is trusted, e.g. a local node or a light
+/// client.
+#[derive(Clone, Debug)]
+pub struct TrustedProvider<M>(pub M);
+
+/// Error thrown when fetching a header from a [`TrustedProvider`].
+#[derive(Error, Debug)]
+pub enum TrustedProviderError<M: Middleware> {
+    /// Thrown when the trusted middleware errors
+    #[error(transparent)]
+    MiddlewareError(M::Error),
+    /// Thrown when the trusted provider does not know the requested block
+    #[error("block {0:?} not found")]
+    BlockNotFound(BlockId),
+    /// Thrown when the hash of the returned block does not match its header
+    #[error("the hash of block {0:?} does not match its header")]
+    InvalidBlockHash(BlockId),
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<M: Middleware> TrustedHeaders for TrustedProvider<M> {
+    type Error = TrustedProviderError<M>;
+
+    async fn trusted_header(&self, block: Option<BlockId>) -> Result<Header, Self::Error> {
+        let block = block.unwrap_or_else(|| BlockNumber::Latest.into());
+        let full = self
+            .0
+            .get_block(block)
+            .await
+            .map_err(TrustedProviderError::MiddlewareError)?
+            .ok_or(TrustedProviderError::BlockNotFound(block))?;
+        if !full.verify_hash() {
+            return Err(TrustedProviderError::InvalidBlockHash(block))
+        }
+        Ok(full.header())
+    }
+}
+
+/// Middleware which answers account and storage queries with `eth_getProof` and verifies the
+/// proofs against the state root of a trusted block header, so that the state returned by an
+/// untrusted node can't be forged.
+///
+/// All state queries are pinned to the hash of the trusted header. ENS names are resolved through
+/// the inner middleware, and are therefore not verified.
+///
+/// ```no_run
+/// use ethers_providers::{Middleware, Provider, Http};
+/// use ethers_middleware::verifying::{TrustedProvider, VerifyingMiddleware};
+/// use std::convert::TryFrom;
+///
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// let untrusted = Provider::<Http>::try_from("https://rpc.example.com")?;
+/// let trusted = Provider::<Http>::try_from("http://localhost:8545")?;
+/// let provider = VerifyingMiddleware::new(untrusted, TrustedProvider(trusted));
+///
+/// let balance = provider.get_balance("vitalik.eth", None).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct VerifyingMiddleware<M, T> {
+    inner: M,
+    trusted: T,
+}
+
+impl<M, T> VerifyingMiddleware<M, T>
+where
+    M: Middleware,
+    T: TrustedHeaders,
+{
+    /// Creates a new client which verifies the state returned by `inner` against the headers of
+    /// `trusted`.
+    pub fn new(inner: M, trusted: T) -> Self {
+        Self { inner, trusted }
+    }
+
+    /// Returns the source of the trusted headers
+    pub fn trusted(&self) -> &T {
+        &self.trusted
+    }
+
+    /// Fetches the trusted header of the block, as well as the account and storage proofs of the
+    /// account at that block, and verifies the proofs against the header's state root.
+    async fn verified_proof(
+        &self,
+        address: NameOrAddress,
+        locations: Vec<H256>,
+        block: Option<BlockId>,
+    ) -> Result<(H256, EIP1186ProofResponse), VerifyingMiddlewareError<M, T>> {
+        let address = self.resolve(address).await?;
+        let header = self
+            .trusted
+            .trusted_header(block)
+            .await
+            .map_err(VerifyingMiddlewareError::TrustedHeaderError)?;
+        let block_hash = header.hash_slow();
+
+        let proof = self
+            .inner
+            .get_proof(address, locations.clone(), Some(block_hash.into()))
+            .await
+            .map_err(VerifyingMiddlewareError::MiddlewareError)?;
+
+        // the node may return valid proofs for a different account or slots
+        let keys = proof.storage_proof.iter().map(|p| p.key);
+        if proof.address != address || !keys.eq(locations) {
+            return Err(VerifyingMiddlewareError::UnexpectedProof)
+        }
+        proof.verify(header.state_root)?;
+
+        Ok((block_hash, proof))
+    }
+
+    async fn resolve(
+        &self,
+        address: NameOrAddress,
+    ) -> Result<Address, VerifyingMiddlewareError<M, T>> {
+        match address {
+            NameOrAddress::Address(address) => Ok(address),
+            NameOrAddress::Name(ens_name) => self
+                .inner
+                .resolve_name(&ens_name)
+                .await
+                .map_err(VerifyingMiddlewareError::MiddlewareError),
+        }
+    }
+}
+
+/// Error thrown when the client interacts with the verifying middleware.
+#[derive(Error, Debug)]
+pub enum VerifyingMiddlewareError<M: Middleware, T: TrustedHeaders> {
+    /// Thrown when an internal middleware errors
+    #[error(transparent)]
+    MiddlewareError(M::Error),
+    /// Thrown when the trusted header could not be retrieved
+    #[error("{0}")]
+    TrustedHeaderError(T::Error),
+    /// Thrown when a proof does not match the trusted state root
+    #[error(transparent)]
+    ProofError(#[from] ProofError),
+    /// Thrown when the node returns a proof for another account or other storage slots than the
+    /// requested ones
+    #[error("the proof does not match the requested account or storage slots")]
+    UnexpectedProof,
+    /// Thrown when the returned code does not match the proven code hash
+    #[error("the code does not match the proven code hash {0:?}")]
+    CodeMismatch(H256),
+}
+
+impl<M: Middleware, T: TrustedHeaders> MiddlewareError for VerifyingMiddlewareError<M, T> {
+    type Inner = M::Error;
+
+    fn from_err(src: M::Error) -> Self {
+        VerifyingMiddlewareError::MiddlewareError(src)
+    }
+
+    fn as_inner(&self) -> Option<&Self::Inner> {
+        match self {
+            VerifyingMiddlewareError::MiddlewareError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<M, T> Middleware for VerifyingMiddleware<M, T>
+where
+    M: Middleware,
+    T: TrustedHeaders,
+{
+    type Error = VerifyingMiddlewareError<M, T>;
+    type Provider = M::Provider;
+    type Inner = M;
+
+    fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    async fn get_balance<A: Into<NameOrAddress> + Send + Sync>(
+        &self,
+        from: A,
+        block: Option<BlockId>,
+    ) -> Result<U256, Self::Error> {
+        let (_, proof) = self.verified_proof(from.into(), vec![], block).await?;
+        Ok(proof.balance)
+    }
+
+    async fn get_transaction_count<A: Into<NameOrAddress> + Send + Sync>(
+        &self,
+        from: A,
+        block: Option<BlockId>,
+    ) -> Result<U256, Self::Error> {
+        let (_, proof) = self.verified_proof(from.into(), vec![], block).await?;
+        Ok(proof.nonce.as_u64().into())
+    }
+
+    async fn get_code<A: Into<NameOrAddress> + Send + Sync>(
+        &self,
+        at: A,
+        block: Option<BlockId>,
+    ) -> Result<Bytes, Self::Error> {
+        let (block_hash, proof) = self.verified_proof(at.into(), vec![], block).await?;
+        let code = self
+            .inner
+            .get_code(proof.address, Some(block_hash.into()))
+            .await
+            .map_err(VerifyingMiddlewareError::MiddlewareError)?;
+
+        // accounts which don't exist have no code hash
+        let code_hash = if proof.code_hash.is_zero() { KECCAK_EMPTY } else { proof.code_hash };
+        if H256(keccak256(&code)) != code_hash {
+            return Err(VerifyingMiddlewareError::CodeMismatch(code_hash))
+        }
+        Ok(code)
+    }
+
+    async fn get_storage_at<A: Into<NameOrAddress> + Send + Sync>(
+        &self,
+        from: A,
+        location: H256,
+        block: Option<BlockId>,
+    ) -> Result<H256, Self::Error> {
+        let (_, proof) = self.verified_proof(from.into(), vec![location], block).await?;
+        let mut value = H256::zero();
+        proof.storage_proof[0].value.to_big_endian(value.as_bytes_mut());
+        Ok(value)
+    }
+
+    /// Returns the EIP-1186 proof response, after verifying it against the trusted header
+    async fn get_proof<A: Into<NameOrAddress> + Send + Sync>(
+        &self,
+        from: A,
+        locations: Vec<H256>,
+        block: Option<BlockId>,
+    ) -> Result<EIP1186ProofResponse, Self::Error> {
+        let (_, proof) = self.verified_proof(from.into(), locations, block).await?;
+        Ok(proof)
+    }
+}
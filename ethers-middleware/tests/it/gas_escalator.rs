@@ -39,3 +39,39 @@ async fn gas_escalator() {
     assert_eq!(receipt.to, Some(Address::zero()));
     assert!(receipt.effective_gas_price.unwrap() > gas_price * 2, "{receipt:?}");
 }
+
+#[tokio::test]
+#[ignore]
+async fn gas_escalator_eip1559() {
+    let anvil = Anvil::new().block_time(2u64).spawn();
+    let chain_id = anvil.chain_id();
+    let provider = Provider::<Http>::try_from(anvil.endpoint()).unwrap();
+
+    let wallet: LocalWallet = anvil.keys().first().unwrap().clone().into();
+    let wallet = wallet.with_chain_id(chain_id);
+    let address = wallet.address();
+    let provider = provider.with_signer(wallet);
+
+    let escalator = GeometricGasPrice::new(5.0, 10u64, None::<u64>);
+    let fee_cap = U256::from(100_000_000_000u64);
+    let provider = GasEscalatorMiddleware::new(provider, escalator, Frequency::Duration(300))
+        .with_fee_cap(fee_cap);
+
+    let nonce = provider.get_transaction_count(address, None).await.unwrap();
+    // 1 gwei default base fee
+    let max_fee = U256::from(1_000_000_000_u64);
+    let tx = Eip1559TransactionRequest::new()
+        .to(Address::zero())
+        .value(1u64)
+        .max_fee_per_gas(max_fee)
+        .max_priority_fee_per_gas(1u64)
+        .nonce(nonce)
+        .chain_id(chain_id);
+
+    let pending = provider.send_transaction(tx, None).await.expect("could not send");
+    let receipt = pending.await.expect("reverted").expect("dropped");
+    assert_eq!(receipt.from, address);
+    let tx = provider.get_transaction(receipt.transaction_hash).await.unwrap().unwrap();
+    assert!(tx.max_fee_per_gas.unwrap() > max_fee * 2, "{tx:?}");
+    assert!(tx.max_fee_per_gas.unwrap() <= fee_cap, "{tx:?}");
+}
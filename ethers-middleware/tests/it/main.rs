@@ -15,6 +15,9 @@ mod gas_oracle;
 #[cfg(not(feature = "celo"))]
 mod signer;
 
+#[cfg(not(feature = "celo"))]
+mod multi_signer;
+
 #[cfg(not(feature = "celo"))]
 mod nonce_manager;
 
@@ -24,6 +27,12 @@ mod stack;
 #[cfg(not(feature = "celo"))]
 mod transformer;
 
+#[cfg(not(feature = "celo"))]
+mod tx_manager;
+
+#[cfg(not(feature = "celo"))]
+mod verifying;
+
 /// Spawns Anvil and instantiates an Http provider.
 pub fn spawn_anvil() -> (Provider<Http>, AnvilInstance) {
     let anvil = Anvil::new().block_time(1u64).spawn();
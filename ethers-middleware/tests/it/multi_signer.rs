@@ -0,0 +1,52 @@
+use ethers_core::{
+    types::{transaction::eip2718::TypedTransaction, *},
+    utils::{keccak256, rlp::Rlp},
+};
+use ethers_middleware::{multi_signer::MultiSignerMiddlewareError, MiddlewareBuilder};
+use ethers_providers::{Middleware, Provider};
+use ethers_signers::{LocalWallet, Signer};
+use std::sync::{Arc, Mutex};
+
+#[tokio::test]
+async fn multi_signer_routes_by_from_address() {
+    let (provider, mock) = Provider::mocked();
+    let alice_wallet = LocalWallet::new(&mut rand::thread_rng()).with_chain_id(1u64);
+    let bob_wallet = LocalWallet::new(&mut rand::thread_rng()).with_chain_id(1u64);
+    let (alice, bob) = (alice_wallet.address(), bob_wallet.address());
+    let carol = Address::random();
+    let client = provider.multi_signer().with_signer(alice_wallet).with_signer(bob_wallet);
+
+    // alice's nonce is 1 and bob's is 2
+    mock.on_call("eth_getTransactionCount", move |params| {
+        let from: Address = serde_json::from_value(params[0].clone()).unwrap();
+        Ok(U256::from(if from == alice { 1 } else { 2 }))
+    });
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let sent_by = sent.clone();
+    mock.on_call("eth_sendRawTransaction", move |params| {
+        let raw: Bytes = serde_json::from_value(params[0].clone()).unwrap();
+        let (tx, sig) = TypedTransaction::decode_signed(&Rlp::new(&raw)).unwrap();
+        sent_by.lock().unwrap().push((sig.recover(tx.sighash()).unwrap(), *tx.nonce().unwrap()));
+        Ok(H256::from(keccak256(&raw)))
+    });
+    mock.on("eth_sendTransaction", H256::zero()).unwrap();
+    mock.expect_calls("eth_sendTransaction", 1);
+
+    let tx = TransactionRequest::new().to(Address::random()).gas(21_000).gas_price(1);
+    // the default sender is the first signer
+    client.send_transaction(tx.clone(), None).await.unwrap();
+    client.send_transaction(tx.clone().from(bob), None).await.unwrap();
+    // addresses without a signer are delegated to the node
+    client.send_transaction(tx.from(carol), None).await.unwrap();
+    assert_eq!(*sent.lock().unwrap(), vec![(alice, 1.into()), (bob, 2.into())]);
+    mock.verify().unwrap();
+
+    let mut accounts = vec![alice, bob];
+    accounts.sort();
+    assert_eq!(client.get_accounts().await.unwrap(), accounts);
+
+    let signature = client.sign(b"hello".to_vec(), &bob).await.unwrap();
+    signature.verify("hello", bob).unwrap();
+    let err = client.sign(b"hello".to_vec(), &carol).await.unwrap_err();
+    assert!(matches!(err, MultiSignerMiddlewareError::UnknownSigner(address) if address == carol));
+}
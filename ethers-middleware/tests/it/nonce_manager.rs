@@ -1,7 +1,7 @@
 use crate::spawn_anvil;
 use ethers_core::types::*;
 use ethers_middleware::MiddlewareBuilder;
-use ethers_providers::Middleware;
+use ethers_providers::{JsonRpcError, Middleware, MiddlewareError};
 
 #[tokio::test]
 async fn nonce_manager() {
@@ -36,3 +36,114 @@ async fn nonce_manager() {
 
     assert_eq!(nonces, (nonce..nonce + num_tx as u64).collect::<Vec<_>>());
 }
+
+#[tokio::test]
+async fn multi_nonce_manager_tracks_senders() {
+    let (provider, mock) = ethers_providers::Provider::mocked();
+    let provider = provider.multi_nonce_manager();
+    let (alice, bob) = (Address::random(), Address::random());
+    let tx = |from| TransactionRequest::new().from(from).to(bob).gas(21_000).gas_price(1);
+
+    // responses are popped from the back: the pending and mined counts, then the tx hash
+    mock.push::<H256, _>(H256::random()).unwrap();
+    mock.push::<U256, _>(U256::from(4)).unwrap();
+    mock.push::<U256, _>(U256::from(3)).unwrap();
+    provider.send_transaction(tx(alice), None).await.unwrap();
+    mock.assert_request("eth_getTransactionCount", (alice, "latest")).unwrap();
+    mock.assert_request("eth_getTransactionCount", (alice, "pending")).unwrap();
+    assert_eq!(provider.pending(alice).await, [U256::from(4)].into());
+
+    // the nonce of a transaction which failed to be sent is reused
+    provider.send_transaction(tx(alice), None).await.unwrap_err();
+    assert_eq!(provider.pending(alice).await, [U256::from(4)].into());
+    assert_eq!(provider.next_nonce(alice).await, Some(5.into()));
+
+    // other senders are tracked separately
+    mock.push::<U256, _>(U256::from(0)).unwrap();
+    mock.push::<U256, _>(U256::from(0)).unwrap();
+    assert_eq!(provider.reserve(bob, None).await.unwrap(), 0.into());
+    assert_eq!(provider.next_nonce(alice).await, Some(5.into()));
+
+    // released nonces are reserved again first
+    for nonce in 1..4 {
+        assert_eq!(provider.reserve(bob, None).await.unwrap(), nonce.into());
+    }
+    provider.release(bob, 1.into()).await;
+    assert_eq!(provider.next_nonce(bob).await, Some(1.into()));
+    assert_eq!(provider.reserve(bob, None).await.unwrap(), 1.into());
+    provider.release(bob, 3.into()).await;
+    provider.release(bob, 2.into()).await;
+    assert_eq!(provider.next_nonce(bob).await, Some(2.into()));
+    assert_eq!(provider.pending(bob).await, [0, 1].into_iter().map(U256::from).collect());
+
+    let mut accounts = provider.accounts();
+    accounts.sort();
+    let mut expected = vec![alice, bob];
+    expected.sort();
+    assert_eq!(accounts, expected);
+}
+
+#[tokio::test]
+async fn multi_nonce_manager_does_not_resend_known_transactions() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    let (provider, mock) = ethers_providers::Provider::mocked();
+    let provider = provider.multi_nonce_manager();
+    let alice = Address::random();
+    let tx = TransactionRequest::new().from(alice).to(Address::random()).gas(21_000).gas_price(1);
+
+    // the node already has the transaction with nonce 4 in its pool
+    let pending_count = AtomicU64::new(4);
+    mock.on_call("eth_getTransactionCount", move |params| {
+        Ok(match params[1].as_str() {
+            Some("latest") => U256::from(4),
+            _ => U256::from(pending_count.fetch_add(1, Ordering::SeqCst)),
+        })
+    });
+    let err = JsonRpcError { code: -32000, message: "already known".to_string(), data: None };
+    mock.on_error("eth_sendTransaction", err);
+    mock.expect_calls("eth_sendTransaction", 1);
+
+    let err = provider.send_transaction(tx, None).await.unwrap_err();
+    assert_eq!(err.as_error_response().unwrap().message, "already known");
+    mock.verify().unwrap();
+
+    // the nonces are synchronized, without reusing the nonce of the known transaction
+    assert_eq!(provider.pending(alice).await, [U256::from(4)].into());
+    assert_eq!(provider.next_nonce(alice).await, Some(5.into()));
+}
+
+#[tokio::test]
+async fn nonce_manager_with_signer_mocked_per_method() {
+    use ethers_core::{
+        types::transaction::eip2718::TypedTransaction,
+        utils::{keccak256, rlp::Rlp},
+    };
+    use ethers_signers::{LocalWallet, Signer};
+
+    let (provider, mock) = ethers_providers::Provider::mocked();
+    let wallet: LocalWallet =
+        "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318".parse().unwrap();
+    let wallet = wallet.with_chain_id(1u64);
+    let address = wallet.address();
+    let client = provider.with_signer(wallet).nonce_manager(address);
+
+    mock.on("eth_getTransactionCount", U256::from(7)).unwrap();
+    let sent = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sent2 = sent.clone();
+    mock.on_call("eth_sendRawTransaction", move |params| {
+        let raw: Bytes = serde_json::from_value(params[0].clone()).unwrap();
+        let (tx, _) = TypedTransaction::decode_signed(&Rlp::new(&raw)).unwrap();
+        sent2.lock().unwrap().push(*tx.nonce().unwrap());
+        Ok(H256::from(keccak256(&raw)))
+    });
+    // the nonce manager only fetches the nonce once
+    mock.expect_calls("eth_getTransactionCount", 1);
+    mock.expect_calls("eth_sendRawTransaction", 2);
+
+    let tx = TransactionRequest::new().to(Address::random()).gas(21_000).gas_price(1);
+    client.send_transaction(tx.clone(), None).await.unwrap();
+    client.send_transaction(tx, None).await.unwrap();
+    mock.verify().unwrap();
+    assert_eq!(*sent.lock().unwrap(), vec![U256::from(7), U256::from(8)]);
+}
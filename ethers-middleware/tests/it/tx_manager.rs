@@ -0,0 +1,149 @@
+use ethers_core::types::{
+    transaction::{
+        eip2718::TypedTransaction,
+        eip2930::{AccessList, AccessListItem},
+    },
+    *,
+};
+use ethers_middleware::{
+    gas_escalator::GasEscalator,
+    tx_manager::{FileStore, TxManagerMiddleware, TxStatus},
+    SignerMiddleware,
+};
+use ethers_providers::{JsonRpcError, Middleware, MockProvider, Provider};
+use ethers_signers::{LocalWallet, Signer};
+
+fn manager(
+    store: FileStore,
+) -> (
+    TxManagerMiddleware<SignerMiddleware<Provider<MockProvider>, LocalWallet>, FileStore>,
+    MockProvider,
+) {
+    let (provider, mock) = Provider::mocked();
+    let wallet: LocalWallet =
+        "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318".parse().unwrap();
+    let wallet = wallet.with_chain_id(1u64);
+    let address = wallet.address();
+    (TxManagerMiddleware::new(SignerMiddleware::new(provider, wallet), address, store), mock)
+}
+
+#[tokio::test]
+async fn tx_manager_recovers_pending_transactions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("txs.jsonl");
+
+    let (client, mock) = manager(FileStore::open(&path).unwrap());
+    let tx = TransactionRequest::new().to(Address::random()).value(100).gas(21_000).gas_price(100);
+
+    // responses are popped from the back
+    mock.push::<H256, _>(H256::random()).unwrap();
+    mock.push::<U256, _>(U256::from(5)).unwrap();
+    let pending = client.send_transaction(tx, None).await.unwrap();
+
+    let records = client.pending().await.unwrap();
+    assert_eq!(records.len(), 1);
+    let record = &records[0];
+    assert_eq!(record.hash, H256::from(ethers_core::utils::keccak256(&record.raw)));
+    assert_eq!(record.nonce, 5.into());
+    assert_eq!(record.status, TxStatus::Pending);
+    mock.assert_request("eth_getTransactionCount", (client.address(), "pending")).unwrap();
+    mock.assert_request("eth_sendRawTransaction", [&record.raw]).unwrap();
+    drop(pending);
+    drop(client);
+
+    // after a restart, the transaction the node forgot about is broadcast again
+    let (client, mock) = manager(FileStore::open(&path).unwrap());
+    mock.push::<H256, _>(record.hash).unwrap();
+    mock.push::<Option<Transaction>, _>(None).unwrap();
+    mock.push::<Option<TransactionReceipt>, _>(None).unwrap();
+    mock.push::<U256, _>(U256::from(5)).unwrap();
+    let report = client.sync().await.unwrap();
+    assert!(report.confirmed.is_empty());
+    assert!(report.replaced.is_empty());
+    assert_eq!(report.rebroadcast.len(), 1);
+    assert_eq!(report.rebroadcast[0].raw, record.raw);
+    assert_eq!(report.rebroadcast[0].transaction().chain_id(), Some(U64::one()));
+    mock.assert_request("eth_getTransactionCount", (client.address(), "latest")).unwrap();
+    mock.assert_request("eth_getTransactionReceipt", [record.hash]).unwrap();
+    mock.assert_request("eth_getTransactionByHash", [record.hash]).unwrap();
+    mock.assert_request("eth_sendRawTransaction", [&record.raw]).unwrap();
+
+    // the stuck nonce is cancelled with a zero-value transaction to self with bumped fees
+    mock.push::<H256, _>(H256::random()).unwrap();
+    let cancel = client.cancel(5.into()).await.unwrap();
+    assert_eq!(cancel.nonce, 5.into());
+    assert_eq!(cancel.first_sent_at, record.first_sent_at);
+    assert_eq!(cancel.tx.to_addr(), Some(&client.address()));
+    assert_eq!(cancel.tx.value(), Some(&U256::zero()));
+    assert_eq!(cancel.tx.gas_price(), Some(110.into()));
+    let pending: Vec<_> = client.pending().await.unwrap().into_iter().map(|r| r.hash).collect();
+    assert_eq!(pending, vec![record.hash, cancel.hash]);
+}
+
+#[tokio::test]
+async fn tx_manager_cancels_typed_transactions() {
+    let dir = tempfile::tempdir().unwrap();
+    let (client, mock) = manager(FileStore::open(dir.path().join("txs.jsonl")).unwrap());
+    let access_list = AccessList(vec![AccessListItem {
+        address: Address::random(),
+        storage_keys: vec![H256::random()],
+    }]);
+    let tx = Eip1559TransactionRequest::new()
+        .to(Address::random())
+        .value(100)
+        .gas(50_000)
+        .max_fee_per_gas(100)
+        .max_priority_fee_per_gas(10)
+        .access_list(access_list);
+    let blob_tx = Eip4844TransactionRequest::new(tx.clone())
+        .max_fee_per_blob_gas(20)
+        .blob_versioned_hashes(vec![H256::random()]);
+
+    mock.push::<H256, _>(H256::random()).unwrap();
+    mock.push::<U256, _>(U256::from(5)).unwrap();
+    client.send_transaction(tx, None).await.unwrap();
+    mock.push::<H256, _>(H256::random()).unwrap();
+    client.send_transaction(blob_tx, None).await.unwrap();
+
+    // the fees are bumped and the access list is cleared
+    mock.push::<H256, _>(H256::random()).unwrap();
+    let cancel = client.cancel(5.into()).await.unwrap();
+    let TypedTransaction::Eip1559(inner) = &cancel.tx else { panic!("not an EIP-1559 tx") };
+    assert_eq!(inner.max_fee_per_gas, Some(110.into()));
+    assert_eq!(inner.max_priority_fee_per_gas, Some(11.into()));
+    assert!(inner.access_list.0.is_empty());
+
+    // blob transactions also bump the blob fee
+    mock.push::<H256, _>(H256::random()).unwrap();
+    let cancel = client.cancel(6.into()).await.unwrap();
+    let TypedTransaction::Eip4844(inner) = &cancel.tx else { panic!("not a blob tx") };
+    assert_eq!(inner.tx.max_fee_per_gas, Some(110.into()));
+    assert_eq!(inner.tx.max_priority_fee_per_gas, Some(11.into()));
+    assert_eq!(inner.max_fee_per_blob_gas, Some(22.into()));
+    assert!(inner.tx.access_list.0.is_empty());
+}
+
+#[derive(Debug)]
+struct DoubleGasPrice;
+
+impl GasEscalator for DoubleGasPrice {
+    fn get_gas_price(&self, initial_price: U256, _time_elapsed: u64) -> U256 {
+        initial_price * 2
+    }
+}
+
+#[tokio::test]
+async fn tx_manager_skips_escalations_of_used_nonces() {
+    let dir = tempfile::tempdir().unwrap();
+    let (client, mock) = manager(FileStore::open(dir.path().join("txs.jsonl")).unwrap());
+    let tx = TransactionRequest::new().to(Address::random()).value(100).gas(21_000).gas_price(100);
+    mock.push::<H256, _>(H256::random()).unwrap();
+    mock.push::<U256, _>(U256::from(5)).unwrap();
+    client.send_transaction(tx, None).await.unwrap();
+
+    // the node reports that the nonce was mined in the meantime
+    let err = JsonRpcError { code: -32000, message: "nonce too low".to_string(), data: None };
+    mock.on_error("eth_sendRawTransaction", err);
+    let replacements = client.escalate(&DoubleGasPrice, None).await.unwrap();
+    assert!(replacements.is_empty());
+}
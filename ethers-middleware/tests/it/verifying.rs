@@ -0,0 +1,83 @@
+use ethers_core::{
+    types::{Address, BlockId, BlockNumber, Bytes, EIP1186ProofResponse, Header, H256, U256},
+    utils::keccak256,
+};
+use ethers_middleware::verifying::{VerifyingMiddleware, VerifyingMiddlewareError};
+use ethers_providers::{Middleware, Provider};
+
+fn proof() -> (Header, EIP1186ProofResponse) {
+    let proof: EIP1186ProofResponse =
+        serde_json::from_str(include_str!("../../../ethers-core/testdata/proof.json")).unwrap();
+    let header = Header {
+        number: 100u64.into(),
+        // the first node of the account proof is the root of the state trie
+        state_root: H256(keccak256(&proof.account_proof[0])),
+        ..Default::default()
+    };
+    (header, proof)
+}
+
+/// The response to a request without storage slots
+fn account_proof(proof: &EIP1186ProofResponse) -> EIP1186ProofResponse {
+    EIP1186ProofResponse { storage_proof: vec![], ..proof.clone() }
+}
+
+#[tokio::test]
+async fn verifies_account_state() {
+    let (header, proof) = proof();
+    let (provider, mock) = Provider::mocked();
+    let provider = VerifyingMiddleware::new(provider, header.clone());
+    let block: BlockId = header.hash_slow().into();
+
+    mock.push(account_proof(&proof)).unwrap();
+    let balance = provider.get_balance(proof.address, Some(block)).await.unwrap();
+    assert_eq!(balance, proof.balance);
+    mock.assert_request("eth_getProof", (proof.address, Vec::<H256>::new(), block)).unwrap();
+
+    mock.push(account_proof(&proof)).unwrap();
+    let nonce = provider.get_transaction_count(proof.address, Some(block)).await.unwrap();
+    assert_eq!(nonce, U256::one());
+    mock.assert_request("eth_getProof", (proof.address, Vec::<H256>::new(), block)).unwrap();
+
+    let slot = proof.storage_proof[0].key;
+    mock.push(proof.clone()).unwrap();
+    let value = provider.get_storage_at(proof.address, slot, Some(100u64.into())).await.unwrap();
+    assert_eq!(value, H256::zero());
+    mock.assert_request("eth_getProof", (proof.address, vec![slot], block)).unwrap();
+
+    // the header is only trusted for its own block, and never as the latest block
+    let err = provider.get_balance(proof.address, Some(99u64.into())).await.unwrap_err();
+    assert!(matches!(err, VerifyingMiddlewareError::TrustedHeaderError(_)));
+    let err = provider.get_balance(proof.address, None).await.unwrap_err();
+    assert!(matches!(err, VerifyingMiddlewareError::TrustedHeaderError(_)));
+    let err = provider.get_balance(proof.address, Some(BlockNumber::Latest.into())).await;
+    assert!(matches!(err.unwrap_err(), VerifyingMiddlewareError::TrustedHeaderError(_)));
+}
+
+#[tokio::test]
+async fn rejects_forged_state() {
+    let (header, proof) = proof();
+    let (provider, mock) = Provider::mocked();
+    let block = Some(header.hash_slow().into());
+    let provider = VerifyingMiddleware::new(provider, header);
+
+    let mut forged = account_proof(&proof);
+    forged.balance = U256::exp10(18);
+    mock.push(forged).unwrap();
+    let err = provider.get_balance(proof.address, block).await.unwrap_err();
+    assert!(matches!(err, VerifyingMiddlewareError::ProofError(_)), "{err:?}");
+
+    // a valid proof, but for another account
+    mock.push(account_proof(&proof)).unwrap();
+    let err = provider.get_balance(Address::zero(), block).await.unwrap_err();
+    assert!(matches!(err, VerifyingMiddlewareError::UnexpectedProof), "{err:?}");
+
+    // code which does not hash to the proven code hash
+    mock.push::<Bytes, Bytes>(vec![0u8].into()).unwrap();
+    mock.push(account_proof(&proof)).unwrap();
+    let err = provider.get_code(proof.address, block).await.unwrap_err();
+    assert!(
+        matches!(err, VerifyingMiddlewareError::CodeMismatch(hash) if hash == proof.code_hash),
+        "{err:?}"
+    );
+}
@@ -0,0 +1,205 @@
+//! A middleware supporting the ERC-4337 bundler JSON RPC methods
+//!
+//! # Example
+//!
+//! ```no_run
+//! use ethers_providers::{BundlerMiddleware, Http, Provider};
+//! use ethers_core::types::{Address, UserOperation, ENTRY_POINT_V06_ADDRESS};
+//! use std::convert::TryFrom;
+//!
+//! # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+//! let provider = Provider::<Http>::try_from("http://localhost:4337")?;
+//! let bundler = BundlerMiddleware::new(provider);
+//!
+//! let entry_points = bundler.supported_entry_points().await?;
+//! assert!(entry_points.contains(&ENTRY_POINT_V06_ADDRESS));
+//!
+//! let sender: Address = "0x9406cc6185a346906296840746125a0e44976454".parse()?;
+//! let user_op = UserOperation::new(sender).call_data(vec![0xb6, 0x1d, 0x27, 0xf6]);
+//! let estimate = bundler.estimate_user_operation_gas(&user_op, ENTRY_POINT_V06_ADDRESS).await?;
+//!
+//! // sign the operation and submit it to the bundler
+//! let user_op = user_op.gas_estimate(&estimate);
+//! let user_op_hash = bundler.send_user_operation(&user_op, ENTRY_POINT_V06_ADDRESS).await?;
+//! let receipt = bundler.get_user_operation_receipt(user_op_hash).await?;
+//! # Ok(()) }
+//! ```
+
+use crate::{Middleware, MiddlewareError, ProviderError};
+use async_trait::async_trait;
+use ethers_core::types::{
+    Address, UserOperation, UserOperationGasEstimate, UserOperationReceipt, H256,
+};
+use thiserror::Error;
+
+use std::fmt::Debug;
+
+/// `BundlerMiddleware`
+#[derive(Clone, Debug)]
+pub struct BundlerMiddleware<M>(M);
+
+/// BundlerMiddleware Errors
+#[derive(Error, Debug)]
+pub enum BundlerMiddlewareError<M: Middleware> {
+    /// Internal Middleware error
+    #[error("{0}")]
+    MiddlewareError(M::Error),
+
+    /// Internal Provider error
+    #[error("{0}")]
+    ProviderError(ProviderError),
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<M: Middleware> Middleware for BundlerMiddleware<M> {
+    type Error = BundlerMiddlewareError<M>;
+    type Provider = M::Provider;
+    type Inner = M;
+
+    fn inner(&self) -> &M {
+        &self.0
+    }
+}
+
+impl<M: Middleware> MiddlewareError for BundlerMiddlewareError<M> {
+    type Inner = M::Error;
+
+    fn from_err(src: M::Error) -> BundlerMiddlewareError<M> {
+        BundlerMiddlewareError::MiddlewareError(src)
+    }
+
+    fn as_inner(&self) -> Option<&Self::Inner> {
+        match self {
+            BundlerMiddlewareError::MiddlewareError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<M> From<ProviderError> for BundlerMiddlewareError<M>
+where
+    M: Middleware,
+{
+    fn from(src: ProviderError) -> Self {
+        Self::ProviderError(src)
+    }
+}
+
+impl<M: Middleware> BundlerMiddleware<M> {
+    /// Instantiate a new `BundlerMiddleware`
+    pub fn new(inner: M) -> Self {
+        Self(inner)
+    }
+
+    /// Submits a signed user operation to the bundler's mempool, returning its hash.
+    ///
+    /// Ref: [Here](https://eips.ethereum.org/EIPS/eip-4337#eth_senduseroperation)
+    pub async fn send_user_operation(
+        &self,
+        user_op: &UserOperation,
+        entry_point: Address,
+    ) -> Result<H256, BundlerMiddlewareError<M>> {
+        self.provider()
+            .request("eth_sendUserOperation", (user_op, entry_point))
+            .await
+            .map_err(From::from)
+    }
+
+    /// Estimates the gas values of a user operation. The signature does not need to be valid, but
+    /// should have the expected length.
+    ///
+    /// Ref: [Here](https://eips.ethereum.org/EIPS/eip-4337#eth_estimateuseroperationgas)
+    pub async fn estimate_user_operation_gas(
+        &self,
+        user_op: &UserOperation,
+        entry_point: Address,
+    ) -> Result<UserOperationGasEstimate, BundlerMiddlewareError<M>> {
+        self.provider()
+            .request("eth_estimateUserOperationGas", (user_op, entry_point))
+            .await
+            .map_err(From::from)
+    }
+
+    /// Returns the receipt of the user operation with the given hash, or `None` if it has not been
+    /// included yet.
+    ///
+    /// Ref: [Here](https://eips.ethereum.org/EIPS/eip-4337#eth_getuseroperationreceipt)
+    pub async fn get_user_operation_receipt(
+        &self,
+        user_op_hash: H256,
+    ) -> Result<Option<UserOperationReceipt>, BundlerMiddlewareError<M>> {
+        self.provider()
+            .request("eth_getUserOperationReceipt", [user_op_hash])
+            .await
+            .map_err(From::from)
+    }
+
+    /// Returns the `EntryPoint` addresses supported by the bundler.
+    ///
+    /// Ref: [Here](https://eips.ethereum.org/EIPS/eip-4337#eth_supportedentrypoints)
+    pub async fn supported_entry_points(&self) -> Result<Vec<Address>, BundlerMiddlewareError<M>> {
+        self.provider().request("eth_supportedEntryPoints", ()).await.map_err(From::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockProvider, Provider};
+    use ethers_core::types::{ENTRY_POINT_V06_ADDRESS, U256};
+    use serde_json::{json, Value};
+
+    fn bundler() -> (BundlerMiddleware<Provider<MockProvider>>, MockProvider) {
+        let (provider, mock) = Provider::mocked();
+        (BundlerMiddleware::new(provider), mock)
+    }
+
+    #[tokio::test]
+    async fn sends_and_estimates_user_operations() {
+        let (bundler, mock) = bundler();
+        let user_op = UserOperation::new(Address::random()).nonce(1).signature(vec![0xff; 65]);
+        let user_op_hash = user_op.hash(ENTRY_POINT_V06_ADDRESS, 1);
+
+        let expected = user_op.clone();
+        mock.on_call("eth_estimateUserOperationGas", move |params: &Value| {
+            let op: UserOperation = serde_json::from_value(params[0].clone()).unwrap();
+            assert_eq!(op, expected);
+            assert_eq!(params[1], json!(ENTRY_POINT_V06_ADDRESS));
+            Ok(json!({
+                "preVerificationGas": "0xb708",
+                "verificationGasLimit": "0x13d05",
+                "callGasLimit": "0x2f44"
+            }))
+        });
+        mock.on_call("eth_sendUserOperation", move |params: &Value| {
+            let op: UserOperation = serde_json::from_value(params[0].clone()).unwrap();
+            assert_eq!(op.call_gas_limit, U256::from(0x2f44));
+            Ok(user_op_hash)
+        });
+
+        let estimate =
+            bundler.estimate_user_operation_gas(&user_op, ENTRY_POINT_V06_ADDRESS).await.unwrap();
+        assert_eq!(estimate.pre_verification_gas, 0xb708.into());
+        assert_eq!(estimate.verification_gas_limit, 0x13d05.into());
+
+        let user_op = user_op.gas_estimate(&estimate);
+        let hash = bundler.send_user_operation(&user_op, ENTRY_POINT_V06_ADDRESS).await.unwrap();
+        assert_eq!(hash, user_op_hash);
+    }
+
+    #[tokio::test]
+    async fn queries_entry_points_and_receipts() {
+        let (bundler, mock) = bundler();
+        mock.on::<Vec<Address>, _>("eth_supportedEntryPoints", vec![ENTRY_POINT_V06_ADDRESS])
+            .unwrap();
+        mock.on::<Value, _>("eth_getUserOperationReceipt", Value::Null).unwrap();
+
+        let entry_points = bundler.supported_entry_points().await.unwrap();
+        assert_eq!(entry_points, vec![ENTRY_POINT_V06_ADDRESS]);
+
+        let receipt = bundler.get_user_operation_receipt(H256::random()).await.unwrap();
+        assert!(receipt.is_none());
+        assert_eq!(mock.calls("eth_getUserOperationReceipt"), 1);
+    }
+}
@@ -7,6 +7,9 @@ pub use ens::*;
 
 pub mod erc;
 
+pub mod bundler;
+pub use bundler::{BundlerMiddleware, BundlerMiddlewareError};
+
 #[cfg(feature = "dev-rpc")]
 pub mod dev_rpc;
 #[cfg(feature = "dev-rpc")]
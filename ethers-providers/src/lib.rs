@@ -24,7 +24,9 @@ pub use errors::{MiddlewareError, ProviderError, RpcError};
 mod stream;
 pub use futures_util::StreamExt;
 pub use stream::{
-    tx_stream::TransactionStream, FilterWatcher, DEFAULT_LOCAL_POLL_INTERVAL, DEFAULT_POLL_INTERVAL,
+    reorg::{ReorgEvent, ReorgStream, DEFAULT_REORG_WINDOW},
+    tx_stream::TransactionStream,
+    FilterWatcher, DEFAULT_LOCAL_POLL_INTERVAL, DEFAULT_POLL_INTERVAL,
 };
 
 mod middleware;
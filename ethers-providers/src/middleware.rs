@@ -194,7 +194,7 @@ pub trait Middleware: Sync + Send + Debug {
             .map(|req| async move {
                 self.sign_transaction(&req, self.default_sender().unwrap_or_default())
                     .await
-                    .map(|sig| req.rlp_signed(&sig))
+                    .map(|sig| req.rlp_signed_network(&sig))
             })
             .collect();
 
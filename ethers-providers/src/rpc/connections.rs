@@ -6,7 +6,7 @@ use ethers_core::types::U256;
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::value::RawValue;
 
-use crate::{ProviderError, RpcError};
+use crate::{BatchCall, BatchResult, ProviderError, RpcError};
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
@@ -22,6 +22,31 @@ pub trait JsonRpcClient: Debug + Send + Sync {
     where
         T: Debug + Serialize + Send + Sync,
         R: DeserializeOwned + Send;
+
+    /// Sends a batch of requests, returning one result per call in the same order as `calls`.
+    ///
+    /// JSON-RPC errors returned for individual calls are reported per call, while any other error
+    /// fails the whole batch.
+    ///
+    /// The default implementation dispatches every call as a separate, concurrent request.
+    /// Transports which support JSON-RPC 2.0 batch payloads, like [`Http`](crate::Http), override
+    /// it to send all calls at once.
+    async fn batch_request(&self, calls: Vec<BatchCall>) -> Result<Vec<BatchResult>, Self::Error> {
+        let requests = calls.iter().map(|call| async move {
+            let res: Result<Box<RawValue>, Self::Error> = match &call.params {
+                Some(params) => self.request(&call.method, params).await,
+                None => self.request(&call.method, ()).await,
+            };
+            match res {
+                Ok(res) => Ok(Ok(res)),
+                Err(err) => match err.as_error_response() {
+                    Some(err) => Ok(Err(err.clone())),
+                    None => Err(err),
+                },
+            }
+        });
+        futures_util::future::join_all(requests).await.into_iter().collect()
+    }
 }
 
 /// A transport implementation supporting pub sub subscriptions.
@@ -7,8 +7,9 @@ use crate::{
     rpc::pubsub::{PubsubClient, SubscriptionStream},
     stream::{FilterWatcher, DEFAULT_LOCAL_POLL_INTERVAL, DEFAULT_POLL_INTERVAL},
     utils::maybe,
-    Http as HttpProvider, JsonRpcClient, JsonRpcClientWrapper, LogQuery, MiddlewareError,
-    MockProvider, NodeInfo, PeerInfo, PendingTransaction, QuorumProvider, RwClient,
+    BatchRequest, Http as HttpProvider, JsonRpcClient, JsonRpcClientWrapper, LogQuery,
+    MiddlewareError, MockProvider, NodeInfo, PeerInfo, PendingTransaction, QuorumProvider,
+    RwClient,
 };
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -24,7 +25,10 @@ use async_trait::async_trait;
 use ethers_core::{
     abi::{self, Detokenize, ParamType},
     types::{
-        transaction::{eip2718::TypedTransaction, eip2930::AccessListWithGasUsed},
+        transaction::{
+            eip2718::TypedTransaction, eip2930::AccessListWithGasUsed,
+            eip4844::Eip4844TransactionRequest,
+        },
         Address, Block, BlockId, BlockNumber, BlockTrace, Bytes, Chain, EIP1186ProofResponse,
         FeeHistory, Filter, FilterBlockOption, GethDebugTracingCallOptions,
         GethDebugTracingOptions, GethTrace, Log, NameOrAddress, Selector, Signature, Trace,
@@ -238,6 +242,34 @@ impl<P: JsonRpcClient> Provider<P> {
     pub fn call_raw<'a>(&'a self, tx: &'a TypedTransaction) -> CallBuilder<'a, P> {
         CallBuilder::new(self, tx)
     }
+
+    /// Returns a [`BatchRequest`] builder which groups several calls into JSON-RPC 2.0 batch
+    /// requests.
+    ///
+    /// Each added call returns a typed future resolving to the call's result once the batch has
+    /// been sent. Whether the calls are sent in a single payload depends on the transport, see
+    /// [`JsonRpcClient::batch_request`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use ethers_core::types::{Address, BlockNumber, U256, U64};
+    /// # use ethers_providers::{Provider, Http};
+    /// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+    /// let provider = Provider::<Http>::try_from("http://localhost:8545")?;
+    /// let address: Address = "0x6fC21092DA55B392b045eD78F4732bff3C580e2c".parse()?;
+    ///
+    /// let mut batch = provider.batch();
+    /// let block_number = batch.add_call::<_, U64>("eth_blockNumber", ())?;
+    /// let balance = batch.add_call::<_, U256>("eth_getBalance", (address, BlockNumber::Latest))?;
+    /// batch.send().await?;
+    ///
+    /// println!("balance at block {}: {}", block_number.await?, balance.await?);
+    /// # Ok(()) }
+    /// ```
+    pub fn batch(&self) -> BatchRequest<'_, P> {
+        BatchRequest::new(self)
+    }
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
@@ -279,6 +311,15 @@ impl<P: JsonRpcClient> Middleware for Provider<P> {
             }
         }
 
+        // blob transactions cannot create contracts
+        if let TypedTransaction::Eip4844(ref inner) = tx {
+            if inner.tx.to.is_none() {
+                return Err(ProviderError::CustomError(
+                    "blob transactions require a `to` address".into(),
+                ))
+            }
+        }
+
         // TODO: Join the name resolution and gas price future
 
         // set the ENS name
@@ -293,24 +334,47 @@ impl<P: JsonRpcClient> Middleware for Provider<P> {
                 let gas_price = maybe(tx.gas_price(), self.get_gas_price()).await?;
                 tx.set_gas_price(gas_price);
             }
-            TypedTransaction::Eip1559(ref mut inner) => {
-                if inner.max_fee_per_gas.is_none() || inner.max_priority_fee_per_gas.is_none() {
-                    let (max_fee_per_gas, max_priority_fee_per_gas) =
-                        self.estimate_eip1559_fees(None).await?;
-                    // we want to avoid overriding the user if either of these
-                    // are set. In order to do this, we refuse to override the
-                    // `max_fee_per_gas` if already set.
-                    // However, we must preserve the constraint that the tip
-                    // cannot be higher than max fee, so we override user
-                    // intent if that is so. We override by
-                    //   - first: if set, set to the min(current value, MFPG)
-                    //   - second, if still unset, use the RPC estimated amount
-                    let mfpg = inner.max_fee_per_gas.get_or_insert(max_fee_per_gas);
-                    inner.max_priority_fee_per_gas = inner
-                        .max_priority_fee_per_gas
-                        .map(|tip| std::cmp::min(tip, *mfpg))
-                        .or(Some(max_priority_fee_per_gas));
-                };
+            TypedTransaction::Eip1559(ref mut inner) |
+            TypedTransaction::Eip4844(Eip4844TransactionRequest { tx: ref mut inner, .. })
+                if inner.max_fee_per_gas.is_none() || inner.max_priority_fee_per_gas.is_none() =>
+            {
+                let (max_fee_per_gas, max_priority_fee_per_gas) =
+                    self.estimate_eip1559_fees(None).await?;
+                // we want to avoid overriding the user if either of these
+                // are set. In order to do this, we refuse to override the
+                // `max_fee_per_gas` if already set.
+                // However, we must preserve the constraint that the tip
+                // cannot be higher than max fee, so we override user
+                // intent if that is so. We override by
+                //   - first: if set, set to the min(current value, MFPG)
+                //   - second, if still unset, use the RPC estimated amount
+                let mfpg = inner.max_fee_per_gas.get_or_insert(max_fee_per_gas);
+                inner.max_priority_fee_per_gas = inner
+                    .max_priority_fee_per_gas
+                    .map(|tip| std::cmp::min(tip, *mfpg))
+                    .or(Some(max_priority_fee_per_gas));
+            }
+            // the fees are already set
+            TypedTransaction::Eip1559(_) | TypedTransaction::Eip4844(_) => {}
+            // deposit transactions are paid for on L1
+            #[cfg(feature = "optimism")]
+            TypedTransaction::DepositTransaction(_) => {}
+        }
+
+        // fill blob gas price
+        #[cfg(not(feature = "celo"))]
+        if let TypedTransaction::Eip4844(ref mut inner) = tx {
+            if inner.max_fee_per_blob_gas.is_none() {
+                let block = self
+                    .get_block(BlockNumber::Latest)
+                    .await?
+                    .ok_or_else(|| ProviderError::CustomError("Latest block not found".into()))?;
+                let blob_fee = block.next_block_blob_fee().ok_or_else(|| {
+                    ProviderError::CustomError("Latest block has no blob gas fields".into())
+                })?;
+                inner.max_fee_per_blob_gas = Some(blob_fee.saturating_mul(
+                    ethers_core::types::transaction::eip4844::BLOB_FEE_SURGE_MULTIPLIER.into(),
+                ));
             }
         }
 
@@ -1782,6 +1846,44 @@ mod tests {
         assert!(matches!(res, Err(ProviderError::JsonRpcClientError(_))));
     }
 
+    #[tokio::test]
+    #[cfg(not(feature = "celo"))]
+    async fn test_fill_transaction_4844() {
+        let (provider, mock) = Provider::mocked();
+        let from: Address = "0x0000000000000000000000000000000000000001".parse().unwrap();
+        let to: Address = "0x0000000000000000000000000000000000000002".parse().unwrap();
+        let inner = Eip1559TransactionRequest::new()
+            .from(from)
+            .gas(21000)
+            .max_fee_per_gas(25)
+            .max_priority_fee_per_gas(1);
+
+        // --- rejects contract creation without making requests
+        let mut tx = Eip4844TransactionRequest::new(inner.clone()).into();
+        let err = provider.fill_transaction(&mut tx, None).await.unwrap_err();
+        assert!(matches!(err, ProviderError::CustomError(_)));
+
+        // --- pads the next block's blob fee
+        let block = Block::<TxHash> {
+            excess_blob_gas: Some(U256::from(10_000_000u64)),
+            blob_gas_used: Some(U256::from(393_216u64)),
+            ..Default::default()
+        };
+        mock.push::<Block<TxHash>, _>(&block).unwrap();
+
+        let mut tx = Eip4844TransactionRequest::new(inner.clone().to(to)).into();
+        provider.fill_transaction(&mut tx, None).await.unwrap();
+        let TypedTransaction::Eip4844(filled) = tx else { panic!("not a blob transaction") };
+        assert_eq!(filled.max_fee_per_blob_gas, Some(block.next_block_blob_fee().unwrap() * 2));
+
+        // --- errors on a latest block without blob gas fields
+        mock.push(Block::<TxHash>::default()).unwrap();
+
+        let mut tx = Eip4844TransactionRequest::new(inner.to(to)).into();
+        let err = provider.fill_transaction(&mut tx, None).await.unwrap_err();
+        assert!(matches!(err, ProviderError::CustomError(_)));
+    }
+
     #[tokio::test]
     async fn test_fill_transaction_legacy() {
         let (mut provider, mock) = Provider::mocked();
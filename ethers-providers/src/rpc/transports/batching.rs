@@ -0,0 +1,626 @@
+//! A [JsonRpcClient] implementation that transparently coalesces requests into JSON-RPC batches
+
+use super::common::{BatchCall, BatchResult, JsonRpcError};
+use crate::{errors::ProviderError, JsonRpcClient, DEFAULT_MAX_BATCH_SIZE};
+use async_trait::async_trait;
+use futures_channel::oneshot;
+use futures_timer::Delay;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use thiserror::Error;
+use tracing::trace;
+
+/// Read-only methods which are deduplicated: issuing them twice at the same time is equivalent to
+/// issuing them once. Any other method, including unknown ones, is always sent as is.
+const DEDUPLICABLE_METHODS: &[&str] = &[
+    "eth_blockNumber",
+    "eth_chainId",
+    "net_version",
+    "web3_clientVersion",
+    "eth_gasPrice",
+    "eth_maxPriorityFeePerGas",
+    "eth_blobBaseFee",
+    "eth_feeHistory",
+    "eth_syncing",
+    "eth_accounts",
+    "eth_getBalance",
+    "eth_getCode",
+    "eth_getStorageAt",
+    "eth_getTransactionCount",
+    "eth_getProof",
+    "eth_call",
+    "eth_estimateGas",
+    "eth_createAccessList",
+    "eth_getBlockByNumber",
+    "eth_getBlockByHash",
+    "eth_getBlockReceipts",
+    "eth_getBlockTransactionCountByNumber",
+    "eth_getBlockTransactionCountByHash",
+    "eth_getUncleByBlockNumberAndIndex",
+    "eth_getUncleByBlockHashAndIndex",
+    "eth_getTransactionByHash",
+    "eth_getTransactionByBlockNumberAndIndex",
+    "eth_getTransactionByBlockHashAndIndex",
+    "eth_getTransactionReceipt",
+    "eth_getLogs",
+];
+
+/// [BatchingClient] presents as a wrapper around [JsonRpcClient] that buffers the requests issued
+/// within a short time window, or until a maximum number of requests has been buffered, and
+/// sends them to the inner client as a single batch via [JsonRpcClient::batch_request].
+///
+/// Identical requests (same method and params) which are pending or in flight at the same time
+/// are only sent once and share the response. This only applies to known read-only methods like
+/// `eth_call` or `eth_getBalance`, methods with side effects like `eth_sendRawTransaction` or
+/// `evm_mine` are always sent as often as they are issued.
+///
+/// Batches are flushed on a spawned task, so the client must be used within a tokio runtime
+/// (outside of wasm).
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn demo() -> Result<(), Box<dyn std::error::Error>> {
+/// use ethers_core::types::Address;
+/// use ethers_providers::{BatchingClientBuilder, Http, Middleware, Provider};
+/// use std::time::Duration;
+/// use url::Url;
+///
+/// let http = Http::new(Url::parse("http://localhost:8545")?);
+/// let client = BatchingClientBuilder::default()
+///     .window(Duration::from_millis(5))
+///     .max_batch_size(50)
+///     .build(http);
+/// let provider = Provider::new(client);
+///
+/// // both requests are sent in a single batch
+/// let (block, balance) = futures_util::try_join!(
+///     provider.get_block_number(),
+///     provider.get_balance(Address::zero(), None)
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BatchingClient<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for BatchingClient<T> {
+    fn clone(&self) -> Self {
+        Self { shared: self.shared.clone() }
+    }
+}
+
+/// The response of a request, shared among all deduplicated requests
+type SharedResult = Result<BatchResult, Arc<ProviderError>>;
+
+#[derive(Debug)]
+struct Shared<T> {
+    inner: T,
+    /// How long to wait for more requests after the first request of a batch
+    window: Duration,
+    /// How many requests to buffer at most before sending a batch
+    max_batch_size: usize,
+    /// Whether identical requests are deduplicated
+    deduplicate: bool,
+    state: Mutex<State>,
+    stats: Stats,
+}
+
+/// Identifies the listeners of a request
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum RequestKey {
+    /// A deduplicated request, identified by its method and serialized params
+    Call(String, Option<String>),
+    /// A request which is never deduplicated
+    Unique(u64),
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Incremented every time the pending batch is taken, to detect stale flush timers
+    generation: u64,
+    /// The batch which has not been sent yet
+    pending: Vec<(RequestKey, BatchCall)>,
+    /// The listeners of all pending and in-flight requests
+    waiters: HashMap<RequestKey, Vec<oneshot::Sender<SharedResult>>>,
+    next_unique_id: u64,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    batches: AtomicU64,
+    requests: AtomicU64,
+    deduplicated: AtomicU64,
+    largest_batch: AtomicU64,
+}
+
+/// A snapshot of the metrics collected by a [BatchingClient]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchingStats {
+    /// Number of batches sent to the inner client
+    pub batches: u64,
+    /// Number of requests sent to the inner client, over all batches
+    pub requests: u64,
+    /// Number of requests which were served by an identical pending or in-flight request
+    pub deduplicated: u64,
+    /// Size of the largest batch sent so far
+    pub largest_batch: u64,
+}
+
+impl BatchingStats {
+    /// Returns the average number of requests per batch
+    pub fn average_batch_size(&self) -> f64 {
+        if self.batches == 0 {
+            0.0
+        } else {
+            self.requests as f64 / self.batches as f64
+        }
+    }
+}
+
+impl<T> BatchingClient<T>
+where
+    T: JsonRpcClient + 'static,
+    T::Error: Sync + Send + 'static,
+{
+    /// Creates a new `BatchingClient` with the default batching window and size
+    pub fn new(inner: T) -> Self {
+        BatchingClientBuilder::default().build(inner)
+    }
+
+    /// Returns the wrapped client
+    pub fn inner(&self) -> &T {
+        &self.shared.inner
+    }
+
+    /// Returns a snapshot of the batching metrics collected so far
+    pub fn stats(&self) -> BatchingStats {
+        let stats = &self.shared.stats;
+        BatchingStats {
+            batches: stats.batches.load(Ordering::Relaxed),
+            requests: stats.requests.load(Ordering::Relaxed),
+            deduplicated: stats.deduplicated.load(Ordering::Relaxed),
+            largest_batch: stats.largest_batch.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Registers the call and returns the receiver of its response
+    fn enqueue(&self, call: BatchCall) -> oneshot::Receiver<SharedResult> {
+        let shared = &self.shared;
+        let (tx, rx) = oneshot::channel();
+        let mut state = shared.state.lock().unwrap();
+
+        let key = if shared.deduplicate && DEDUPLICABLE_METHODS.contains(&call.method.as_str()) {
+            let key = RequestKey::Call(
+                call.method.clone(),
+                call.params.as_ref().map(|params| params.get().to_string()),
+            );
+            if let Some(waiters) = state.waiters.get_mut(&key) {
+                trace!(method = %call.method, "Deduplicating request");
+                waiters.push(tx);
+                shared.stats.deduplicated.fetch_add(1, Ordering::Relaxed);
+                return rx
+            }
+            key
+        } else {
+            state.next_unique_id += 1;
+            RequestKey::Unique(state.next_unique_id)
+        };
+        state.waiters.insert(key.clone(), vec![tx]);
+        state.pending.push((key, call));
+
+        if state.pending.len() >= shared.max_batch_size {
+            let batch = Self::take_pending(&mut state);
+            drop(state);
+            spawn(self.shared.clone().flush(batch));
+        } else if state.pending.len() == 1 {
+            let generation = state.generation;
+            drop(state);
+            let shared = self.shared.clone();
+            spawn(async move {
+                Delay::new(shared.window).await;
+                let batch = {
+                    let mut state = shared.state.lock().unwrap();
+                    // the batch may have been flushed already because it was full
+                    if state.generation != generation {
+                        return
+                    }
+                    Self::take_pending(&mut state)
+                };
+                shared.flush(batch).await;
+            });
+        }
+
+        rx
+    }
+
+    fn take_pending(state: &mut State) -> Vec<(RequestKey, BatchCall)> {
+        state.generation += 1;
+        std::mem::take(&mut state.pending)
+    }
+}
+
+impl<T> Shared<T>
+where
+    T: JsonRpcClient + 'static,
+    T::Error: Sync + Send + 'static,
+{
+    /// Sends the batch and dispatches the responses to all listeners
+    async fn flush(self: Arc<Self>, batch: Vec<(RequestKey, BatchCall)>) {
+        let (keys, calls): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+        let size = calls.len() as u64;
+        trace!(size, "Sending batch");
+
+        self.stats.batches.fetch_add(1, Ordering::Relaxed);
+        self.stats.requests.fetch_add(size, Ordering::Relaxed);
+        self.stats.largest_batch.fetch_max(size, Ordering::Relaxed);
+
+        let mut results: Vec<SharedResult> = match self.inner.batch_request(calls).await {
+            Ok(results) => results.into_iter().map(Ok).collect(),
+            Err(err) => {
+                let err = Arc::new(err.into());
+                keys.iter().map(|_| Err(Arc::clone(&err))).collect()
+            }
+        };
+        if results.len() != keys.len() {
+            // the waiters left without a result must not hang
+            let err = Arc::new(ProviderError::CustomError(format!(
+                "expected {} batch responses, got {}",
+                keys.len(),
+                results.len()
+            )));
+            results.resize(keys.len(), Err(err));
+        }
+
+        let mut state = self.state.lock().unwrap();
+        for (key, result) in keys.into_iter().zip(results) {
+            for waiter in state.waiters.remove(&key).unwrap_or_default() {
+                // the request may have been dropped, which is fine
+                let _ = waiter.send(result.clone());
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn(fut: impl Future<Output = ()> + Send + 'static) {
+    tokio::spawn(fut);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn spawn(fut: impl Future<Output = ()> + 'static) {
+    wasm_bindgen_futures::spawn_local(fut);
+}
+
+/// Builder for a [`BatchingClient`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BatchingClientBuilder {
+    /// How long to wait for more requests after the first request of a batch
+    window: Duration,
+    /// How many requests to buffer at most before sending a batch
+    max_batch_size: usize,
+    /// Whether identical requests are deduplicated
+    deduplicate: bool,
+}
+
+// === impl BatchingClientBuilder ===
+
+impl BatchingClientBuilder {
+    /// Sets how long to wait for more requests after the first request of a batch was issued
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the maximum number of requests in a batch. A full batch is sent immediately.
+    ///
+    /// A value of `0` is treated as `1`.
+    pub fn max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size.max(1);
+        self
+    }
+
+    /// Sets whether identical pending or in-flight requests are sent only once
+    pub fn deduplicate(mut self, deduplicate: bool) -> Self {
+        self.deduplicate = deduplicate;
+        self
+    }
+
+    /// Creates the `BatchingClient` with the configured settings
+    pub fn build<T>(self, client: T) -> BatchingClient<T>
+    where
+        T: JsonRpcClient + 'static,
+        T::Error: Sync + Send + 'static,
+    {
+        let BatchingClientBuilder { window, max_batch_size, deduplicate } = self;
+        BatchingClient {
+            shared: Arc::new(Shared {
+                inner: client,
+                window,
+                max_batch_size,
+                deduplicate,
+                state: Default::default(),
+                stats: Default::default(),
+            }),
+        }
+    }
+}
+
+impl Default for BatchingClientBuilder {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_millis(10),
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            deduplicate: true,
+        }
+    }
+}
+
+/// Error thrown when using a [BatchingClient]
+#[derive(Error, Debug)]
+pub enum BatchingClientError {
+    /// The batch containing the request failed as a whole. The error is shared by all requests
+    /// of the batch.
+    #[error(transparent)]
+    Batch(Arc<ProviderError>),
+    /// The node returned an error response for the request
+    #[error(transparent)]
+    JsonRpcError(JsonRpcError),
+    /// (De)Serialization error
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+    /// The batch containing the request was dropped before a response was received
+    #[error("batch was dropped before a response was received")]
+    Dropped,
+}
+
+impl crate::RpcError for BatchingClientError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            BatchingClientError::Batch(err) => err.as_error_response(),
+            BatchingClientError::JsonRpcError(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            BatchingClientError::Batch(err) => crate::RpcError::as_serde_error(err.as_ref()),
+            BatchingClientError::SerdeJson(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<BatchingClientError> for ProviderError {
+    fn from(src: BatchingClientError) -> Self {
+        match src {
+            BatchingClientError::SerdeJson(err) => err.into(),
+            _ => ProviderError::JsonRpcClientError(Box::new(src)),
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T> JsonRpcClient for BatchingClient<T>
+where
+    T: JsonRpcClient + 'static,
+    T::Error: Sync + Send + 'static,
+{
+    type Error = BatchingClientError;
+
+    async fn request<A, R>(&self, method: &str, params: A) -> Result<R, Self::Error>
+    where
+        A: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let rx = self.enqueue(BatchCall::new(method, params)?);
+        let raw = rx
+            .await
+            .map_err(|_| BatchingClientError::Dropped)?
+            .map_err(BatchingClientError::Batch)?
+            .map_err(BatchingClientError::JsonRpcError)?;
+        Ok(serde_json::from_str(raw.get())?)
+    }
+
+    /// Sends an explicit batch as is with the inner client, bypassing the batching window
+    async fn batch_request(&self, calls: Vec<BatchCall>) -> Result<Vec<BatchResult>, Self::Error> {
+        let shared = &self.shared;
+        let size = calls.len() as u64;
+        shared.stats.batches.fetch_add(1, Ordering::Relaxed);
+        shared.stats.requests.fetch_add(size, Ordering::Relaxed);
+        shared.stats.largest_batch.fetch_max(size, Ordering::Relaxed);
+
+        shared
+            .inner
+            .batch_request(calls)
+            .await
+            .map_err(|err| BatchingClientError::Batch(Arc::new(err.into())))
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use crate::{Middleware, MockProvider, Provider, RpcError};
+    use ethers_core::types::{Address, U256, U64};
+
+    fn batching(
+        mock: &MockProvider,
+        builder: BatchingClientBuilder,
+    ) -> Provider<BatchingClient<MockProvider>> {
+        Provider::new(builder.build(mock.clone()))
+    }
+
+    #[tokio::test]
+    async fn coalesces_concurrent_requests() {
+        let mock = MockProvider::new();
+        let provider = batching(&mock, BatchingClientBuilder::default());
+        let address = Address::random();
+
+        // mock responses are popped from the back
+        mock.push(U256::from(100)).unwrap();
+        mock.push(U64::from(12)).unwrap();
+
+        let (block, balance) = futures_util::try_join!(
+            provider.get_block_number(),
+            provider.get_balance(address, None)
+        )
+        .unwrap();
+        assert_eq!(block, U64::from(12));
+        assert_eq!(balance, U256::from(100));
+
+        let stats = provider.as_ref().stats();
+        assert_eq!(
+            stats,
+            BatchingStats { batches: 1, requests: 2, deduplicated: 0, largest_batch: 2 }
+        );
+    }
+
+    #[tokio::test]
+    async fn deduplicates_identical_requests() {
+        let mock = MockProvider::new();
+        let provider = batching(&mock, BatchingClientBuilder::default());
+
+        mock.push(U64::from(12)).unwrap();
+
+        let (a, b, c) = futures_util::try_join!(
+            provider.get_block_number(),
+            provider.get_block_number(),
+            provider.get_block_number()
+        )
+        .unwrap();
+        assert_eq!((a, b, c), (U64::from(12), U64::from(12), U64::from(12)));
+
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        mock.assert_request("eth_blockNumber", ()).unwrap_err();
+
+        let stats = provider.as_ref().stats();
+        assert_eq!(stats.requests, 1);
+        assert_eq!(stats.deduplicated, 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_deduplicate_when_disabled() {
+        let mock = MockProvider::new();
+        let provider = batching(&mock, BatchingClientBuilder::default().deduplicate(false));
+
+        mock.push(U64::from(2)).unwrap();
+        mock.push(U64::from(1)).unwrap();
+
+        let (a, b) =
+            futures_util::try_join!(provider.get_block_number(), provider.get_block_number())
+                .unwrap();
+        assert_eq!((a, b), (U64::from(1), U64::from(2)));
+        assert_eq!(provider.as_ref().stats().deduplicated, 0);
+    }
+
+    #[tokio::test]
+    async fn does_not_deduplicate_side_effects() {
+        let mock = MockProvider::new();
+        let client = BatchingClientBuilder::default().build(mock.clone());
+
+        mock.push(true).unwrap();
+        mock.push(true).unwrap();
+
+        // mining twice must mine two blocks, and unknown methods are never merged
+        let (a, b) = futures_util::try_join!(
+            client.request::<_, bool>("evm_mine", ()),
+            client.request::<_, bool>("evm_mine", ())
+        )
+        .unwrap();
+        assert!(a && b);
+        mock.assert_request("evm_mine", ()).unwrap();
+        mock.assert_request("evm_mine", ()).unwrap();
+        assert_eq!(client.stats().deduplicated, 0);
+    }
+
+    #[tokio::test]
+    async fn flushes_full_batches() {
+        let mock = MockProvider::new();
+        let builder =
+            BatchingClientBuilder::default().window(Duration::from_secs(60)).max_batch_size(2);
+        let provider = batching(&mock, builder);
+
+        mock.push(U256::from(2)).unwrap();
+        mock.push(U256::from(1)).unwrap();
+
+        // with a window of 60 seconds this only completes because the batch is full
+        let (a, b) = futures_util::try_join!(
+            provider.get_balance(Address::zero(), None),
+            provider.get_balance(Address::repeat_byte(1), None)
+        )
+        .unwrap();
+        assert_eq!((a, b), (U256::from(1), U256::from(2)));
+        assert_eq!(provider.as_ref().stats().batches, 1);
+    }
+
+    #[tokio::test]
+    async fn shares_batch_errors() {
+        let mock = MockProvider::new();
+        let client = BatchingClientBuilder::default().deduplicate(false).build(mock);
+
+        // no responses were pushed, so the batch fails
+        let (a, b) = futures_util::join!(
+            client.request::<_, U64>("eth_blockNumber", ()),
+            client.request::<_, U64>("eth_chainId", ())
+        );
+        assert!(matches!(a.unwrap_err(), BatchingClientError::Batch(_)));
+        let err = b.unwrap_err();
+        assert!(matches!(err, BatchingClientError::Batch(_)));
+        assert!(!err.is_error_response());
+    }
+    /// Drops the last result of every batch
+    #[derive(Debug)]
+    struct TruncatingClient(MockProvider);
+
+    #[async_trait]
+    impl JsonRpcClient for TruncatingClient {
+        type Error = crate::MockError;
+
+        async fn request<A, R>(&self, method: &str, params: A) -> Result<R, Self::Error>
+        where
+            A: Debug + Serialize + Send + Sync,
+            R: DeserializeOwned + Send,
+        {
+            self.0.request(method, params).await
+        }
+
+        async fn batch_request(
+            &self,
+            calls: Vec<BatchCall>,
+        ) -> Result<Vec<BatchResult>, Self::Error> {
+            let mut results = self.0.batch_request(calls).await?;
+            results.pop();
+            Ok(results)
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_requests_missing_from_the_response() {
+        let mock = MockProvider::new();
+        mock.on::<U64, _>("eth_blockNumber", U64::from(12)).unwrap();
+        mock.on::<U64, _>("eth_chainId", U64::from(1)).unwrap();
+        let client = BatchingClientBuilder::default().build(TruncatingClient(mock));
+
+        let (a, b) = futures_util::join!(
+            client.request::<_, U64>("eth_blockNumber", ()),
+            client.request::<_, U64>("eth_chainId", ())
+        );
+        assert_eq!(a.unwrap(), U64::from(12));
+        assert!(matches!(b.unwrap_err(), BatchingClientError::Batch(_)));
+    }
+}
@@ -0,0 +1,683 @@
+//! A [JsonRpcClient] implementation that caches the responses of requests for immutable data
+
+use crate::{errors::ProviderError, BatchCall, BatchResult, JsonRpcClient, RpcError};
+use async_trait::async_trait;
+use ethers_core::{types::U64, utils::keccak256};
+use instant::{Duration, Instant};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{value::RawValue, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+use thiserror::Error;
+use tracing::trace;
+
+/// A storage backend for the responses cached by a [CachingClient].
+///
+/// Keys are derived from the method and params of the request, and values are the raw JSON
+/// results. Entries never expire: only immutable data is cached.
+pub trait ResponseCache: Debug + Send + Sync {
+    /// Returns the cached result of the request identified by `key`, if any
+    fn get(&self, key: &str) -> Option<Box<RawValue>>;
+
+    /// Caches the result of the request identified by `key`
+    fn set(&self, key: &str, value: &RawValue);
+}
+
+/// An in-memory cache holding a bounded number of responses, evicting the least recently used
+/// response once full.
+#[derive(Debug)]
+pub struct MemoryCache {
+    capacity: usize,
+    inner: Mutex<LruState>,
+}
+
+#[derive(Debug, Default)]
+struct LruState {
+    /// Incremented on every access
+    tick: u64,
+    /// The values with the tick of their last access
+    entries: HashMap<String, (Box<RawValue>, u64)>,
+    /// The keys by the tick of their last access
+    recency: BTreeMap<u64, String>,
+}
+
+impl LruState {
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some((_, last)) = self.entries.get_mut(key) {
+            self.recency.remove(last);
+            *last = tick;
+            self.recency.insert(tick, key.to_string());
+        }
+    }
+}
+
+impl MemoryCache {
+    /// Creates a cache holding at most `capacity` responses
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, inner: Default::default() }
+    }
+
+    /// Returns the number of cached responses
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Returns `true` if no response is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new(10_000)
+    }
+}
+
+impl ResponseCache for MemoryCache {
+    fn get(&self, key: &str) -> Option<Box<RawValue>> {
+        let mut state = self.inner.lock().unwrap();
+        state.touch(key);
+        state.entries.get(key).map(|(value, _)| value.clone())
+    }
+
+    fn set(&self, key: &str, value: &RawValue) {
+        if self.capacity == 0 {
+            return
+        }
+        let mut state = self.inner.lock().unwrap();
+        state.entries.insert(key.to_string(), (value.to_owned(), 0));
+        state.touch(key);
+        while state.entries.len() > self.capacity {
+            let Some((_, oldest)) = state.recency.pop_first_entry() else { break };
+            state.entries.remove(&oldest);
+        }
+    }
+}
+
+/// `BTreeMap::pop_first` is only stable since Rust 1.66
+trait PopFirstEntry<K, V> {
+    fn pop_first_entry(&mut self) -> Option<(K, V)>;
+}
+
+impl<K: Ord + Clone, V> PopFirstEntry<K, V> for BTreeMap<K, V> {
+    fn pop_first_entry(&mut self) -> Option<(K, V)> {
+        let key = self.keys().next()?.clone();
+        self.remove_entry(&key)
+    }
+}
+
+/// An on-disk cache storing each response in a JSON file named after the hash of its request,
+/// which survives restarts.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    root: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DiskCache {
+    /// Creates a cache storing the responses in the `root` directory, which is created if it does
+    /// not exist
+    pub fn new(root: impl Into<std::path::PathBuf>) -> std::io::Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    /// Returns the directory of the cache
+    pub fn root(&self) -> &std::path::Path {
+        &self.root
+    }
+
+    fn path(&self, key: &str) -> std::path::PathBuf {
+        self.root.join(format!("{}.json", hex::encode(keccak256(key))))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ResponseCache for DiskCache {
+    fn get(&self, key: &str) -> Option<Box<RawValue>> {
+        let data = std::fs::read_to_string(self.path(key)).ok()?;
+        serde_json::from_str(&data).ok()
+    }
+
+    fn set(&self, key: &str, value: &RawValue) {
+        // write to a temporary file first, so that a response is never read half written
+        let path = self.path(key);
+        let tmp = path.with_extension("tmp");
+        if std::fs::write(&tmp, value.get()).is_ok() {
+            let _ = std::fs::rename(tmp, path);
+        }
+    }
+}
+
+/// Methods returning immutable data identified by a block or transaction hash
+const HASH_METHODS: &[&str] = &[
+    "eth_getBlockByHash",
+    "eth_getBlockTransactionCountByHash",
+    "eth_getUncleCountByBlockHash",
+    "eth_getTransactionByBlockHashAndIndex",
+    "eth_getUncleByBlockHashAndIndex",
+];
+
+/// Methods returning a transaction or receipt, which is immutable once its block is final
+const TRANSACTION_METHODS: &[&str] = &["eth_getTransactionByHash", "eth_getTransactionReceipt"];
+
+/// Whether and when the result of a request can be cached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cacheability {
+    /// The result changes over time
+    Never,
+    /// The result is immutable
+    Always,
+    /// The result is immutable once the given block is final
+    AtBlock(U64),
+    /// The result is immutable once the block in its `blockNumber` field is final
+    AtResultBlock,
+}
+
+/// A block param of a request
+enum BlockParam {
+    Hash,
+    Number(U64),
+    Tag,
+}
+
+fn parse_block(param: &Value) -> BlockParam {
+    match param {
+        Value::String(block) if block.len() == 66 => BlockParam::Hash,
+        Value::String(_) => {
+            serde_json::from_value(param.clone()).map_or(BlockParam::Tag, BlockParam::Number)
+        }
+        // EIP-1898 block params
+        Value::Object(block) if block.contains_key("blockHash") => BlockParam::Hash,
+        Value::Object(block) => block.get("blockNumber").map_or(BlockParam::Tag, parse_block),
+        _ => BlockParam::Tag,
+    }
+}
+
+/// Returns whether and when the result of the request can be cached
+fn cacheability(method: &str, params: &Value) -> Cacheability {
+    if HASH_METHODS.contains(&method) {
+        return Cacheability::Always
+    }
+    if TRANSACTION_METHODS.contains(&method) {
+        return Cacheability::AtResultBlock
+    }
+    let Some(params) = params.as_array() else { return Cacheability::Never };
+
+    let block = match method {
+        "eth_getBlockByNumber" |
+        "eth_getBlockTransactionCountByNumber" |
+        "eth_getUncleCountByBlockNumber" |
+        "eth_getTransactionByBlockNumberAndIndex" |
+        "eth_getUncleByBlockNumberAndIndex" |
+        "eth_getBlockReceipts" |
+        "trace_block" => params.first(),
+        "eth_getBalance" |
+        "eth_getCode" |
+        "eth_getStorageAt" |
+        "eth_getTransactionCount" |
+        "eth_call" |
+        "eth_getProof" => params.last(),
+        "eth_getLogs" => {
+            let Some(filter) = params.first() else { return Cacheability::Never };
+            if filter.get("blockHash").is_some() {
+                return Cacheability::Always
+            }
+            // the range must be bounded, by default `toBlock` is `latest`
+            let from = filter.get("fromBlock").map(parse_block);
+            let to = filter.get("toBlock").map(parse_block);
+            return match (from, to) {
+                (Some(BlockParam::Number(_)), Some(BlockParam::Number(to))) => {
+                    Cacheability::AtBlock(to)
+                }
+                _ => Cacheability::Never,
+            }
+        }
+        _ => None,
+    };
+
+    match block.map(parse_block) {
+        Some(BlockParam::Hash) => Cacheability::Always,
+        Some(BlockParam::Number(number)) => Cacheability::AtBlock(number),
+        _ => Cacheability::Never,
+    }
+}
+
+/// [CachingClient] presents as a wrapper around [JsonRpcClient] that caches the responses of
+/// requests for immutable data in a [ResponseCache], and serves them from the cache afterwards.
+///
+/// The results of the following requests are cached:
+/// - requests at a block hash, e.g. `eth_getBlockByHash`, or `eth_call` with an EIP-1898 block hash
+///   param
+/// - requests at a block number, e.g. `eth_getBalance` or `eth_getBlockByNumber`, once the block is
+///   final
+/// - `eth_getTransactionByHash` and `eth_getTransactionReceipt`, once the block of the transaction
+///   is final
+/// - `eth_getLogs` at a block hash, or between block numbers once the last block is final
+///
+/// A block is considered final once it is [`finality_depth`](CachingClient::finality_depth)
+/// blocks below the chain head. `null` results, e.g. for unknown hashes, and requests at block
+/// tags like `latest` are never cached.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn demo() -> Result<(), Box<dyn std::error::Error>> {
+/// use ethers_core::types::{BlockNumber, H256};
+/// use ethers_providers::{CachingClient, DiskCache, Http, Middleware, Provider};
+/// use std::str::FromStr;
+///
+/// let http = Http::from_str("http://localhost:8545")?;
+/// let client = CachingClient::new(http, DiskCache::new("rpc-cache")?).finality_depth(12);
+/// let provider = Provider::new(client);
+///
+/// // served from the cache from now on
+/// let block = provider.get_block(BlockNumber::Number(1_000_000.into())).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CachingClient<T, C = MemoryCache> {
+    inner: T,
+    cache: C,
+    finality_depth: u64,
+    head_refresh_interval: Duration,
+    head: Mutex<Option<(U64, Instant)>>,
+    stats: Stats,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// A snapshot of the metrics collected by a [CachingClient]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CachingStats {
+    /// Number of cacheable requests served from the cache
+    pub hits: u64,
+    /// Number of cacheable requests sent to the inner client
+    pub misses: u64,
+}
+
+impl<T, C> CachingClient<T, C>
+where
+    T: JsonRpcClient,
+    C: ResponseCache,
+{
+    /// Creates a new `CachingClient`, considering blocks final 64 blocks below the chain head
+    pub fn new(inner: T, cache: C) -> Self {
+        Self {
+            inner,
+            cache,
+            finality_depth: 64,
+            head_refresh_interval: Duration::from_secs(1),
+            head: Default::default(),
+            stats: Default::default(),
+        }
+    }
+
+    /// Sets how many blocks below the chain head a block is considered final
+    pub fn finality_depth(mut self, finality_depth: u64) -> Self {
+        self.finality_depth = finality_depth;
+        self
+    }
+
+    /// Sets how long the chain head is reused before being queried again to determine the final
+    /// blocks. A stale chain head only delays the caching of recent blocks.
+    pub fn head_refresh_interval(mut self, interval: Duration) -> Self {
+        self.head_refresh_interval = interval;
+        self
+    }
+
+    /// Returns the wrapped client
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns the cache
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+
+    /// Returns a snapshot of the cache metrics collected so far
+    pub fn stats(&self) -> CachingStats {
+        CachingStats {
+            hits: self.stats.hits.load(Ordering::Relaxed),
+            misses: self.stats.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns whether the block is final
+    async fn is_final(&self, block: U64) -> Result<bool, T::Error> {
+        let cached = *self.head.lock().unwrap();
+        let head = match cached {
+            // the head only moves forward, a stale head is only more conservative
+            Some((head, _)) if block.saturating_add(self.finality_depth.into()) <= head => {
+                return Ok(true)
+            }
+            Some((head, fetched_at)) if fetched_at.elapsed() < self.head_refresh_interval => head,
+            _ => {
+                let head: U64 = self.inner.request("eth_blockNumber", ()).await?;
+                *self.head.lock().unwrap() = Some((head, Instant::now()));
+                head
+            }
+        };
+        // blocks near `U64::MAX` are never final
+        Ok(block.saturating_add(self.finality_depth.into()) <= head)
+    }
+
+    /// Returns whether the result of the request can be cached
+    async fn is_cacheable(
+        &self,
+        cacheability: Cacheability,
+        result: &RawValue,
+    ) -> Result<bool, T::Error> {
+        if result.get() == "null" {
+            return Ok(false)
+        }
+        match cacheability {
+            Cacheability::AtResultBlock => {
+                let block = serde_json::from_str::<Value>(result.get()).ok().and_then(|result| {
+                    serde_json::from_value(result.get("blockNumber")?.clone()).ok()
+                });
+                match block {
+                    Some(block) => self.is_final(block).await,
+                    // pending transactions
+                    None => Ok(false),
+                }
+            }
+            _ => Ok(true),
+        }
+    }
+}
+
+/// Error thrown when using a [CachingClient]
+#[derive(Error, Debug)]
+pub enum CachingClientError {
+    /// Thrown if the inner client errors
+    #[error(transparent)]
+    Inner(ProviderError),
+    /// (De)Serialization error
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+}
+
+impl RpcError for CachingClientError {
+    fn as_error_response(&self) -> Option<&super::JsonRpcError> {
+        match self {
+            CachingClientError::Inner(err) => err.as_error_response(),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            CachingClientError::Inner(err) => RpcError::as_serde_error(err),
+            CachingClientError::SerdeJson(err) => Some(err),
+        }
+    }
+}
+
+impl From<CachingClientError> for ProviderError {
+    fn from(src: CachingClientError) -> Self {
+        match src {
+            CachingClientError::SerdeJson(err) => err.into(),
+            _ => ProviderError::JsonRpcClientError(Box::new(src)),
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T, C> JsonRpcClient for CachingClient<T, C>
+where
+    T: JsonRpcClient,
+    C: ResponseCache,
+{
+    type Error = CachingClientError;
+
+    async fn request<A, R>(&self, method: &str, params: A) -> Result<R, Self::Error>
+    where
+        A: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let inner_err = |err: T::Error| CachingClientError::Inner(err.into());
+
+        // requests without params are never cached
+        if std::mem::size_of::<A>() == 0 {
+            return self.inner.request(method, params).await.map_err(inner_err)
+        }
+        let params = serde_json::to_value(params)?;
+
+        let cacheability = match cacheability(method, &params) {
+            Cacheability::AtBlock(block) if !self.is_final(block).await.map_err(inner_err)? => {
+                Cacheability::Never
+            }
+            cacheability => cacheability,
+        };
+        if cacheability == Cacheability::Never {
+            return self.inner.request(method, params).await.map_err(inner_err)
+        }
+
+        let key = format!("{method}:{params}");
+        if let Some(cached) = self.cache.get(&key) {
+            trace!(method, "Serving response from the cache");
+            self.stats.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(serde_json::from_str(cached.get())?)
+        }
+
+        self.stats.misses.fetch_add(1, Ordering::Relaxed);
+        let result: Box<RawValue> = self.inner.request(method, params).await.map_err(inner_err)?;
+        if self.is_cacheable(cacheability, &result).await.map_err(inner_err)? {
+            self.cache.set(&key, &result);
+        }
+        Ok(serde_json::from_str(result.get())?)
+    }
+
+    /// Serves the cached calls from the cache and sends the remaining calls as one batch with the
+    /// inner client
+    async fn batch_request(&self, calls: Vec<BatchCall>) -> Result<Vec<BatchResult>, Self::Error> {
+        let inner_err = |err: T::Error| CachingClientError::Inner(err.into());
+
+        let mut results: Vec<Option<BatchResult>> = Vec::with_capacity(calls.len());
+        let mut uncached = Vec::new();
+        // the index of every call sent to the inner client, with its cache key and cacheability
+        // if it missed the cache
+        let mut sent = Vec::new();
+        for (idx, call) in calls.into_iter().enumerate() {
+            results.push(None);
+            // calls without params are never cached
+            let Some(params) = call.params.as_deref() else {
+                sent.push((idx, None));
+                uncached.push(call);
+                continue
+            };
+            let params: Value = serde_json::from_str(params.get())?;
+
+            let cacheability = match cacheability(&call.method, &params) {
+                Cacheability::AtBlock(block)
+                    if !self.is_final(block).await.map_err(inner_err)? =>
+                {
+                    Cacheability::Never
+                }
+                cacheability => cacheability,
+            };
+            if cacheability == Cacheability::Never {
+                sent.push((idx, None));
+                uncached.push(call);
+                continue
+            }
+
+            let key = format!("{}:{params}", call.method);
+            if let Some(cached) = self.cache.get(&key) {
+                trace!(method = %call.method, "Serving response from the cache");
+                self.stats.hits.fetch_add(1, Ordering::Relaxed);
+                results[idx] = Some(Ok(cached));
+                continue
+            }
+
+            self.stats.misses.fetch_add(1, Ordering::Relaxed);
+            sent.push((idx, Some((key, cacheability))));
+            uncached.push(call);
+        }
+
+        if !uncached.is_empty() {
+            let responses = self.inner.batch_request(uncached).await.map_err(inner_err)?;
+            if responses.len() != sent.len() {
+                return Err(CachingClientError::Inner(ProviderError::CustomError(format!(
+                    "expected {} batch responses, got {}",
+                    sent.len(),
+                    responses.len()
+                ))))
+            }
+            for ((idx, key), response) in sent.into_iter().zip(responses) {
+                if let (Some((key, cacheability)), Ok(result)) = (key, &response) {
+                    if self.is_cacheable(cacheability, result).await.map_err(inner_err)? {
+                        self.cache.set(&key, result);
+                    }
+                }
+                results[idx] = Some(response);
+            }
+        }
+
+        Ok(results.into_iter().flatten().collect())
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use crate::{Middleware, MockProvider, Provider};
+    use ethers_core::types::{Address, BlockId, BlockNumber, TransactionReceipt, H256, U256};
+    use serde_json::json;
+
+    #[test]
+    fn detects_cacheable_requests() {
+        let hash = H256::random();
+        let cases = [
+            ("eth_getBlockByHash", json!([hash, false]), Cacheability::Always),
+            ("eth_getTransactionReceipt", json!([hash]), Cacheability::AtResultBlock),
+            ("eth_getBalance", json!([Address::zero(), "latest"]), Cacheability::Never),
+            ("eth_getCode", json!([Address::zero(), "0x10"]), Cacheability::AtBlock(16.into())),
+            ("eth_call", json!([{}, BlockId::Hash(hash)]), Cacheability::Always),
+            ("eth_call", json!([{}, { "blockNumber": "0x2" }]), Cacheability::AtBlock(2.into())),
+            ("eth_getBlockByNumber", json!(["0x5", false]), Cacheability::AtBlock(5.into())),
+            ("eth_getBlockByNumber", json!(["pending", false]), Cacheability::Never),
+            (
+                "eth_getLogs",
+                json!([{ "fromBlock": "0x1", "toBlock": "0x3" }]),
+                Cacheability::AtBlock(3.into()),
+            ),
+            ("eth_getLogs", json!([{ "fromBlock": "0x1" }]), Cacheability::Never),
+            ("eth_getLogs", json!([{ "blockHash": hash }]), Cacheability::Always),
+            ("eth_sendRawTransaction", json!(["0x00"]), Cacheability::Never),
+        ];
+        for (method, params, expected) in cases {
+            assert_eq!(cacheability(method, &params), expected, "{method} {params}");
+        }
+    }
+
+    #[test]
+    fn memory_cache_evicts_least_recently_used() {
+        let cache = MemoryCache::new(2);
+        let value = |v: u64| serde_json::value::to_raw_value(&v).unwrap();
+        cache.set("a", &value(1));
+        cache.set("b", &value(2));
+        assert!(cache.get("a").is_some());
+        cache.set("c", &value(3));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").unwrap().get(), "1");
+        assert_eq!(cache.get("c").unwrap().get(), "3");
+    }
+
+    #[tokio::test]
+    async fn caches_final_blocks_only() {
+        let mock = MockProvider::new();
+        let client = CachingClient::new(mock.clone(), MemoryCache::default()).finality_depth(10);
+        let provider = Provider::new(client);
+        let address = Address::random();
+
+        // block 90 is final at head 100, but block 95 is not
+        mock.push(U256::from(1)).unwrap();
+        mock.push(U64::from(100)).unwrap();
+        let at_final = Some(BlockNumber::Number(90.into()).into());
+        assert_eq!(provider.get_balance(address, at_final).await.unwrap(), 1.into());
+        assert_eq!(provider.get_balance(address, at_final).await.unwrap(), 1.into());
+
+        mock.push(U256::from(2)).unwrap();
+        mock.push(U256::from(3)).unwrap();
+        let at_recent = Some(BlockNumber::Number(95.into()).into());
+        assert_eq!(provider.get_balance(address, at_recent).await.unwrap(), 3.into());
+        assert_eq!(provider.get_balance(address, at_recent).await.unwrap(), 2.into());
+
+        assert_eq!(provider.as_ref().stats(), CachingStats { hits: 1, misses: 1 });
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        mock.assert_request("eth_getBalance", (address, "0x5a")).unwrap();
+        mock.assert_request("eth_getBalance", (address, "0x5f")).unwrap();
+        mock.assert_request("eth_getBalance", (address, "0x5f")).unwrap();
+        mock.assert_request("eth_getBalance", ()).unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn blocks_near_the_max_are_never_final() {
+        let mock = MockProvider::new();
+        let client = CachingClient::new(mock.clone(), MemoryCache::default()).finality_depth(10);
+
+        mock.push(U64::from(100)).unwrap();
+        assert!(!client.is_final(U64::MAX).await.unwrap());
+        // with the cached head
+        assert!(!client.is_final(U64::MAX - 5).await.unwrap());
+        assert!(client.is_final(U64::from(90)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn caches_receipts_of_final_blocks() {
+        let mock = MockProvider::new();
+        let dir = tempfile::tempdir().unwrap();
+        let client = CachingClient::new(mock.clone(), DiskCache::new(dir.path()).unwrap())
+            .finality_depth(10)
+            .head_refresh_interval(Duration::ZERO);
+        let provider = Provider::new(client);
+
+        let receipt = |block: u64| TransactionReceipt {
+            block_number: Some(block.into()),
+            ..Default::default()
+        };
+        let (final_hash, recent_hash) = (H256::random(), H256::random());
+
+        mock.push(U64::from(100)).unwrap();
+        mock.push(receipt(90)).unwrap();
+        let cached = provider.get_transaction_receipt(final_hash).await.unwrap();
+        assert_eq!(provider.get_transaction_receipt(final_hash).await.unwrap(), cached);
+
+        for _ in 0..2 {
+            mock.push(U64::from(100)).unwrap();
+            mock.push(receipt(95)).unwrap();
+        }
+        provider.get_transaction_receipt(recent_hash).await.unwrap();
+        provider.get_transaction_receipt(recent_hash).await.unwrap();
+
+        // unknown transactions are not cached
+        mock.push(Option::<TransactionReceipt>::None).unwrap();
+        mock.push(Option::<TransactionReceipt>::None).unwrap();
+        assert!(provider.get_transaction_receipt(H256::zero()).await.unwrap().is_none());
+        assert!(provider.get_transaction_receipt(H256::zero()).await.unwrap().is_none());
+
+        assert_eq!(provider.as_ref().stats(), CachingStats { hits: 1, misses: 5 });
+    }
+}
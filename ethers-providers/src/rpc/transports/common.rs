@@ -10,11 +10,11 @@ use serde::{
     Deserialize, Serialize,
 };
 use serde_json::{value::RawValue, Value};
-use std::fmt;
+use std::{collections::HashMap, fmt};
 use thiserror::Error;
 
 /// A JSON-RPC 2.0 error
-#[derive(Deserialize, Debug, Clone, Error)]
+#[derive(Serialize, Deserialize, Debug, Clone, Error)]
 pub struct JsonRpcError {
     /// The error code
     pub code: i64,
@@ -73,6 +73,16 @@ impl fmt::Display for JsonRpcError {
     }
 }
 
+impl crate::RpcError for JsonRpcError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        Some(self)
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        None
+    }
+}
+
 fn is_zst<T>(_t: &T) -> bool {
     std::mem::size_of::<T>() == 0
 }
@@ -94,6 +104,90 @@ impl<'a, T> Request<'a, T> {
     }
 }
 
+/// A single call of a JSON-RPC batch, with its params already serialized.
+///
+/// `params` is `None` for calls without parameters, in which case the `params` field is omitted
+/// from the request, mirroring [`Request`]'s handling of zero-sized params.
+#[derive(Debug, Clone)]
+pub struct BatchCall {
+    /// The JSON-RPC method
+    pub method: String,
+    /// The serialized params, if any
+    pub params: Option<Box<RawValue>>,
+}
+
+impl BatchCall {
+    /// Creates a new batch call, serializing the provided params
+    pub fn new<T: Serialize>(
+        method: impl Into<String>,
+        params: T,
+    ) -> Result<Self, serde_json::Error> {
+        let params = if is_zst(&params) {
+            None
+        } else {
+            Some(RawValue::from_string(serde_json::to_string(&params)?)?)
+        };
+        Ok(Self { method: method.into(), params })
+    }
+
+    /// Returns the request object for this call with the given id
+    pub(crate) fn to_request(&self, id: u64) -> BatchCallRequest<'_> {
+        BatchCallRequest {
+            id,
+            jsonrpc: "2.0",
+            method: &self.method,
+            params: self.params.as_deref(),
+        }
+    }
+}
+
+/// A JSON-RPC request object of a batch
+#[derive(Serialize, Debug)]
+pub(crate) struct BatchCallRequest<'a> {
+    id: u64,
+    jsonrpc: &'a str,
+    method: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    params: Option<&'a RawValue>,
+}
+
+/// The result of a single call of a JSON-RPC batch
+pub type BatchResult = Result<Box<RawValue>, JsonRpcError>;
+
+/// Matches the responses of a batch whose requests were assigned the consecutive ids
+/// `first_id..first_id + len` back to their requests.
+///
+/// The JSON-RPC spec allows the server to return the responses of a batch in any order, so they
+/// are matched by id. Fails if a response is missing, unexpected or not a success/error object.
+pub(crate) fn collect_batch_responses(
+    first_id: u64,
+    len: usize,
+    responses: Vec<Response<'_>>,
+) -> Result<Vec<BatchResult>, serde_json::Error> {
+    let mut results: HashMap<u64, BatchResult> = HashMap::with_capacity(len);
+    for response in responses {
+        let (id, result) = match response {
+            Response::Success { id, result } => (id, Ok(result.to_owned())),
+            Response::Error { id, error } => (id, Err(error)),
+            Response::Notification { .. } => {
+                return Err(de::Error::custom("unexpected notification in batch response"))
+            }
+        };
+        if id < first_id || id - first_id >= len as u64 {
+            return Err(de::Error::custom(format!("unexpected id {id} in batch response")))
+        }
+        results.insert(id, result);
+    }
+
+    (first_id..first_id + len as u64)
+        .map(|id| {
+            results
+                .remove(&id)
+                .ok_or_else(|| de::Error::custom(format!("missing response for batch id {id}")))
+        })
+        .collect()
+}
+
 /// A JSON-RPC response
 #[derive(Debug)]
 pub enum Response<'a> {
@@ -315,6 +409,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn ser_batch_call() {
+        let call = BatchCall::new("eth_chainId", ()).unwrap();
+        assert!(call.params.is_none());
+        assert_eq!(
+            &serde_json::to_string(&call.to_request(1)).unwrap(),
+            r#"{"id":1,"jsonrpc":"2.0","method":"eth_chainId"}"#
+        );
+
+        let call = BatchCall::new("eth_getBalance", ("0x00", "latest")).unwrap();
+        assert_eq!(
+            &serde_json::to_string(&[call.to_request(2), call.to_request(3)]).unwrap(),
+            r#"[{"id":2,"jsonrpc":"2.0","method":"eth_getBalance","params":["0x00","latest"]},{"id":3,"jsonrpc":"2.0","method":"eth_getBalance","params":["0x00","latest"]}]"#
+        );
+    }
+
+    #[test]
+    fn collects_batch_responses() {
+        // responses may arrive out of order and contain per-call errors
+        let body = r#"[
+            {"jsonrpc":"2.0","error":{"code":-32000,"message":"header not found"},"id":6},
+            {"jsonrpc":"2.0","result":"0x2","id":7},
+            {"jsonrpc":"2.0","result":"0x1","id":5}
+        ]"#;
+        let responses: Vec<Response<'_>> = serde_json::from_str(body).unwrap();
+        let results = collect_batch_responses(5, 3, responses).unwrap();
+
+        assert_eq!(results[0].as_ref().unwrap().get(), r#""0x1""#);
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(err.code, -32000);
+        assert_eq!(err.message, "header not found");
+        assert_eq!(results[2].as_ref().unwrap().get(), r#""0x2""#);
+
+        // missing response
+        let responses: Vec<Response<'_>> =
+            serde_json::from_str(r#"[{"jsonrpc":"2.0","result":"0x1","id":5}]"#).unwrap();
+        collect_batch_responses(5, 2, responses).unwrap_err();
+
+        // unknown id
+        let responses: Vec<Response<'_>> =
+            serde_json::from_str(r#"[{"jsonrpc":"2.0","result":"0x1","id":9}]"#).unwrap();
+        collect_batch_responses(5, 1, responses).unwrap_err();
+    }
+
     #[test]
     fn ser_request() {
         let request: Request<()> = Request::new(0, "eth_chainId", ());
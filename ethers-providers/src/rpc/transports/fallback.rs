@@ -0,0 +1,553 @@
+//! A [JsonRpcClient] implementation that routes requests to the best of several endpoints, and
+//! fails over to the next one when an endpoint is unavailable.
+
+use crate::{errors::ProviderError, BatchCall, BatchResult, JsonRpcClient, RpcError};
+use async_trait::async_trait;
+use ethers_core::types::U64;
+use futures_timer::Delay;
+use futures_util::future::join_all;
+use instant::{Duration, Instant};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{
+    fmt::Debug,
+    future::Future,
+    sync::{Arc, Mutex, Weak},
+};
+use thiserror::Error;
+use tracing::{debug, trace};
+
+/// Weight of the latest observation in the moving averages of the latency and error rate
+const EWMA_ALPHA: f64 = 0.3;
+
+/// Penalty added to the latency of an endpoint which fails all its requests, in milliseconds
+const ERROR_PENALTY_MS: f64 = 1_000.0;
+
+/// [FallbackClient] presents as a wrapper around several [JsonRpcClient]s of the same endpoint
+/// type, and sends each request to the best available endpoint, failing over to the next best
+/// endpoint when a request fails.
+///
+/// Endpoints are ranked by their observed latency and error rate, averaged over the recent
+/// requests, and endpoints with the same score are ranked in the order they were added. An
+/// endpoint is ejected for a cooldown period after several consecutive failures, and is only used
+/// again once the cooldown expired, or when no other endpoint is available.
+///
+/// Health checks query `eth_blockNumber` on all endpoints, and eject the endpoints which fail or
+/// lag too many blocks behind the best endpoint. They are run with
+/// [`FallbackClient::check_health`], or periodically in the background if a
+/// [health check interval](FallbackClientBuilder::health_check_interval) is configured.
+///
+/// Error responses returned by a node, e.g. a reverted `eth_call`, are returned as is without
+/// trying the other endpoints.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn demo() -> Result<(), Box<dyn std::error::Error>> {
+/// use ethers_providers::{FallbackClientBuilder, Http, Middleware, Provider};
+/// use std::{str::FromStr, time::Duration};
+///
+/// let client = FallbackClientBuilder::default()
+///     .cooldown(Duration::from_secs(60))
+///     .max_block_lag(3)
+///     .health_check_interval(Duration::from_secs(15))
+///     .build([Http::from_str("http://localhost:8545")?, Http::from_str("http://localhost:8546")?]);
+/// let provider = Provider::new(client.clone());
+///
+/// let block = provider.get_block_number().await?;
+/// for stats in client.stats() {
+///     println!("{:?} {:?}", stats.latency, stats.block_number);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FallbackClient<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for FallbackClient<T> {
+    fn clone(&self) -> Self {
+        Self { shared: self.shared.clone() }
+    }
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    endpoints: Vec<Endpoint<T>>,
+    /// How long an unhealthy endpoint is ejected for
+    cooldown: Duration,
+    /// How many consecutive failed requests eject an endpoint
+    max_consecutive_failures: u32,
+    /// How many blocks an endpoint may lag behind the best endpoint before being ejected
+    max_block_lag: u64,
+}
+
+#[derive(Debug)]
+struct Endpoint<T> {
+    client: T,
+    state: Mutex<EndpointState>,
+}
+
+#[derive(Debug, Default)]
+struct EndpointState {
+    latency: Option<f64>,
+    error_rate: f64,
+    requests: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    block_number: Option<U64>,
+    ejected_until: Option<Instant>,
+}
+
+impl EndpointState {
+    fn record_success(&mut self, latency: Duration) {
+        let latency = latency.as_secs_f64() * 1_000.0;
+        self.latency = Some(match self.latency {
+            Some(average) => average + EWMA_ALPHA * (latency - average),
+            None => latency,
+        });
+        self.error_rate -= EWMA_ALPHA * self.error_rate;
+        self.requests += 1;
+        self.consecutive_failures = 0;
+    }
+
+    fn record_failure(&mut self) {
+        self.error_rate += EWMA_ALPHA * (1.0 - self.error_rate);
+        self.requests += 1;
+        self.failures += 1;
+        self.consecutive_failures += 1;
+    }
+
+    fn is_ejected(&self, now: Instant) -> bool {
+        self.ejected_until.map_or(false, |until| until > now)
+    }
+
+    fn score(&self) -> f64 {
+        self.latency.unwrap_or_default() + self.error_rate * ERROR_PENALTY_MS
+    }
+}
+
+/// A snapshot of the metrics collected by a [FallbackClient] for one of its endpoints
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EndpointStats {
+    /// Average latency of the recent successful requests, `None` if no request succeeded yet
+    pub latency: Option<Duration>,
+    /// Share of the recent requests which failed, between 0 and 1
+    pub error_rate: f64,
+    /// Number of requests sent to the endpoint, including health checks
+    pub requests: u64,
+    /// Number of requests which failed
+    pub failures: u64,
+    /// Block number returned by the last successful health check
+    pub block_number: Option<U64>,
+    /// Whether the endpoint is currently ejected
+    pub ejected: bool,
+}
+
+impl<T> FallbackClient<T>
+where
+    T: JsonRpcClient + 'static,
+    T::Error: Sync + Send + 'static,
+{
+    /// Creates a new `FallbackClient` with the default settings, without background health checks
+    pub fn new(endpoints: impl IntoIterator<Item = T>) -> Self {
+        FallbackClientBuilder::default().build(endpoints)
+    }
+
+    /// Returns the endpoints, in the order they were added
+    pub fn endpoints(&self) -> impl Iterator<Item = &T> + '_ {
+        self.shared.endpoints.iter().map(|endpoint| &endpoint.client)
+    }
+
+    /// Returns a snapshot of the metrics of each endpoint, in the order they were added
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        let now = Instant::now();
+        self.shared
+            .endpoints
+            .iter()
+            .map(|endpoint| {
+                let state = endpoint.state.lock().unwrap();
+                EndpointStats {
+                    latency: state.latency.map(|latency| Duration::from_secs_f64(latency / 1e3)),
+                    error_rate: state.error_rate,
+                    requests: state.requests,
+                    failures: state.failures,
+                    block_number: state.block_number,
+                    ejected: state.is_ejected(now),
+                }
+            })
+            .collect()
+    }
+
+    /// Queries the block number of all endpoints, and ejects the endpoints which fail or lag
+    /// behind the best endpoint. Ejected endpoints which are healthy again are restored.
+    pub async fn check_health(&self) {
+        let shared = &self.shared;
+        let results = join_all(shared.endpoints.iter().map(|endpoint| async move {
+            let start = Instant::now();
+            let res: Result<U64, _> = endpoint.client.request("eth_blockNumber", ()).await;
+            (res, start.elapsed())
+        }))
+        .await;
+
+        let best = results.iter().filter_map(|(res, _)| res.as_ref().ok()).max().copied();
+        let now = Instant::now();
+        for (index, (endpoint, (res, latency))) in shared.endpoints.iter().zip(results).enumerate()
+        {
+            let mut state = endpoint.state.lock().unwrap();
+            match res {
+                Ok(block_number) => {
+                    state.record_success(latency);
+                    state.block_number = Some(block_number);
+                    let lag = best.unwrap_or_default().saturating_sub(block_number);
+                    if lag > shared.max_block_lag.into() {
+                        debug!(index, %lag, "Ejecting lagging endpoint");
+                        state.ejected_until = Some(now + shared.cooldown);
+                    } else {
+                        state.ejected_until = None;
+                    }
+                }
+                Err(err) => {
+                    let err: ProviderError = err.into();
+                    debug!(index, %err, "Ejecting unhealthy endpoint");
+                    state.record_failure();
+                    state.ejected_until = Some(now + shared.cooldown);
+                }
+            }
+        }
+    }
+
+    /// Returns the indices of the endpoints in the order they should be tried: the available
+    /// endpoints by score, then the ejected endpoints by the end of their cooldown
+    fn ranked(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let mut available = Vec::new();
+        let mut ejected = Vec::new();
+        for (index, endpoint) in self.shared.endpoints.iter().enumerate() {
+            let state = endpoint.state.lock().unwrap();
+            match state.ejected_until {
+                Some(until) if until > now => ejected.push((until, index)),
+                _ => available.push((state.score(), index)),
+            }
+        }
+        available.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        ejected.sort();
+        available
+            .into_iter()
+            .map(|(_, index)| index)
+            .chain(ejected.into_iter().map(|(_, index)| index))
+            .collect()
+    }
+
+    /// Sends a request with the endpoints in the order of [`Self::ranked`] until one of them
+    /// responds, recording the outcome of every attempt
+    async fn try_endpoints<'a, F, Fut, R>(
+        &'a self,
+        method: &str,
+        mut send: F,
+    ) -> Result<R, FallbackClientError>
+    where
+        F: FnMut(&'a T) -> Fut,
+        Fut: Future<Output = Result<R, T::Error>>,
+    {
+        let ranked = self.ranked();
+        if ranked.is_empty() {
+            return Err(FallbackClientError::NoEndpoints)
+        }
+
+        let mut errors = Vec::new();
+        for index in ranked {
+            let endpoint = &self.shared.endpoints[index];
+            let start = Instant::now();
+            let res = send(&endpoint.client).await;
+            let latency = start.elapsed();
+
+            let mut state = endpoint.state.lock().unwrap();
+            match res {
+                Ok(res) => {
+                    state.record_success(latency);
+                    return Ok(res)
+                }
+                // the endpoint is fine, the request itself failed
+                Err(err) if err.is_error_response() => {
+                    state.record_success(latency);
+                    return Err(FallbackClientError::Endpoint(err.into()))
+                }
+                Err(err) => {
+                    state.record_failure();
+                    if state.consecutive_failures >= self.shared.max_consecutive_failures {
+                        debug!(index, "Ejecting failing endpoint");
+                        state.ejected_until = Some(Instant::now() + self.shared.cooldown);
+                    }
+                    let err: ProviderError = err.into();
+                    trace!(index, method, %err, "Request failed, trying the next endpoint");
+                    errors.push(err);
+                }
+            }
+        }
+
+        Err(FallbackClientError::AllEndpointsFailed(errors))
+    }
+
+    fn spawn_health_checks(&self, interval: Duration) {
+        let shared = Arc::downgrade(&self.shared);
+        spawn(async move {
+            loop {
+                Delay::new(interval).await;
+                // stop once all the clones of the client were dropped
+                let Some(shared) = Weak::upgrade(&shared) else { return };
+                FallbackClient { shared }.check_health().await;
+            }
+        });
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn(fut: impl Future<Output = ()> + Send + 'static) {
+    tokio::spawn(fut);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn spawn(fut: impl Future<Output = ()> + 'static) {
+    wasm_bindgen_futures::spawn_local(fut);
+}
+
+/// Builder for a [`FallbackClient`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FallbackClientBuilder {
+    /// How long an unhealthy endpoint is ejected for
+    cooldown: Duration,
+    /// How many consecutive failed requests eject an endpoint
+    max_consecutive_failures: u32,
+    /// How many blocks an endpoint may lag behind the best endpoint before being ejected
+    max_block_lag: u64,
+    /// How often to run the health checks in the background, if at all
+    health_check_interval: Option<Duration>,
+}
+
+// === impl FallbackClientBuilder ===
+
+impl FallbackClientBuilder {
+    /// Sets how long an unhealthy endpoint is ejected for
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Sets how many consecutive failed requests eject an endpoint
+    ///
+    /// A value of `0` is treated as `1`.
+    pub fn max_consecutive_failures(mut self, max_consecutive_failures: u32) -> Self {
+        self.max_consecutive_failures = max_consecutive_failures.max(1);
+        self
+    }
+
+    /// Sets how many blocks an endpoint may lag behind the best endpoint during a health check
+    /// before being ejected
+    pub fn max_block_lag(mut self, max_block_lag: u64) -> Self {
+        self.max_block_lag = max_block_lag;
+        self
+    }
+
+    /// Runs the health checks in the background at the given interval.
+    ///
+    /// The checks are run on a spawned task, so the client must be built within a tokio runtime
+    /// (outside of wasm). The task stops once the client and all its clones are dropped.
+    pub fn health_check_interval(mut self, interval: Duration) -> Self {
+        self.health_check_interval = Some(interval);
+        self
+    }
+
+    /// Creates the `FallbackClient` with the configured settings. Endpoints with the same score
+    /// are tried in the given order.
+    pub fn build<T>(self, endpoints: impl IntoIterator<Item = T>) -> FallbackClient<T>
+    where
+        T: JsonRpcClient + 'static,
+        T::Error: Sync + Send + 'static,
+    {
+        let FallbackClientBuilder {
+            cooldown,
+            max_consecutive_failures,
+            max_block_lag,
+            health_check_interval,
+        } = self;
+        let endpoints = endpoints
+            .into_iter()
+            .map(|client| Endpoint { client, state: Default::default() })
+            .collect();
+        let client = FallbackClient {
+            shared: Arc::new(Shared {
+                endpoints,
+                cooldown,
+                max_consecutive_failures,
+                max_block_lag,
+            }),
+        };
+        if let Some(interval) = health_check_interval {
+            client.spawn_health_checks(interval);
+        }
+        client
+    }
+}
+
+impl Default for FallbackClientBuilder {
+    fn default() -> Self {
+        Self {
+            cooldown: Duration::from_secs(30),
+            max_consecutive_failures: 3,
+            max_block_lag: 5,
+            health_check_interval: None,
+        }
+    }
+}
+
+/// Error thrown when using a [FallbackClient]
+#[derive(Error, Debug)]
+pub enum FallbackClientError {
+    /// The client has no endpoints
+    #[error("no endpoints configured")]
+    NoEndpoints,
+    /// An endpoint returned an error response, which was not retried with the other endpoints
+    #[error(transparent)]
+    Endpoint(ProviderError),
+    /// The request failed on all endpoints. The errors are in the order the endpoints were tried.
+    #[error("request failed on all endpoints: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    AllEndpointsFailed(Vec<ProviderError>),
+    /// (De)Serialization error
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+}
+
+impl RpcError for FallbackClientError {
+    fn as_error_response(&self) -> Option<&super::JsonRpcError> {
+        match self {
+            FallbackClientError::Endpoint(err) => err.as_error_response(),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            FallbackClientError::Endpoint(err) => RpcError::as_serde_error(err),
+            FallbackClientError::SerdeJson(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<FallbackClientError> for ProviderError {
+    fn from(src: FallbackClientError) -> Self {
+        match src {
+            FallbackClientError::SerdeJson(err) => err.into(),
+            _ => ProviderError::JsonRpcClientError(Box::new(src)),
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T> JsonRpcClient for FallbackClient<T>
+where
+    T: JsonRpcClient + 'static,
+    T::Error: Sync + Send + 'static,
+{
+    type Error = FallbackClientError;
+
+    async fn request<A, R>(&self, method: &str, params: A) -> Result<R, Self::Error>
+    where
+        A: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        // the params are serialized once to be sent to several endpoints, while preserving the
+        // special handling of zero-sized params by the endpoints
+        let params: Option<Value> =
+            if std::mem::size_of::<A>() == 0 { None } else { Some(serde_json::to_value(params)?) };
+
+        let params = &params;
+        self.try_endpoints(method, |client| async move {
+            match params {
+                Some(params) => client.request(method, params).await,
+                None => client.request(method, ()).await,
+            }
+        })
+        .await
+    }
+
+    /// Sends the batch with the endpoints in the order of [`Self::ranked`] until one of them
+    /// responds
+    async fn batch_request(&self, calls: Vec<BatchCall>) -> Result<Vec<BatchResult>, Self::Error> {
+        self.try_endpoints("batch", |client| client.batch_request(calls.clone())).await
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use crate::{Middleware, MockProvider, Provider};
+
+    fn fallback(mocks: &[MockProvider]) -> FallbackClient<MockProvider> {
+        FallbackClientBuilder::default()
+            .max_consecutive_failures(1)
+            .cooldown(Duration::from_secs(60))
+            .build(mocks.iter().cloned())
+    }
+
+    #[tokio::test]
+    async fn fails_over_and_ejects() {
+        let mocks = [MockProvider::new(), MockProvider::new()];
+        let client = fallback(&mocks);
+        let provider = Provider::new(client.clone());
+
+        // the first endpoint has no response and fails
+        mocks[1].push(U64::from(1)).unwrap();
+        assert_eq!(provider.get_block_number().await.unwrap(), 1.into());
+        mocks[0].assert_request("eth_blockNumber", ()).unwrap();
+        mocks[1].assert_request("eth_blockNumber", ()).unwrap();
+
+        let stats = client.stats();
+        assert!(stats[0].ejected);
+        assert_eq!((stats[0].requests, stats[0].failures), (1, 1));
+        assert!(!stats[1].ejected);
+        assert_eq!((stats[1].requests, stats[1].failures), (1, 0));
+
+        // the ejected endpoint is not used while the other one is available
+        mocks[1].push(U64::from(2)).unwrap();
+        assert_eq!(provider.get_block_number().await.unwrap(), 2.into());
+        mocks[0].assert_request("eth_blockNumber", ()).unwrap_err();
+
+        // all endpoints failed
+        let err = provider.get_block_number().await.unwrap_err();
+        assert!(err.to_string().contains("request failed on all endpoints"), "{err}");
+        mocks[1].assert_request("eth_blockNumber", ()).unwrap();
+        mocks[1].assert_request("eth_blockNumber", ()).unwrap();
+        mocks[0].assert_request("eth_blockNumber", ()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn health_check_ejects_lagging_endpoints() {
+        let mocks = [MockProvider::new(), MockProvider::new(), MockProvider::new()];
+        let client = fallback(&mocks);
+
+        mocks[0].push(U64::from(100)).unwrap();
+        mocks[1].push(U64::from(110)).unwrap();
+        client.check_health().await;
+
+        let stats = client.stats();
+        assert_eq!(stats[0].block_number, Some(100.into()));
+        assert!(stats[0].ejected);
+        assert!(!stats[1].ejected);
+        // the third endpoint did not respond
+        assert!(stats[2].ejected);
+        assert_eq!(stats[2].failures, 1);
+        assert_eq!(client.ranked(), [1, 0, 2]);
+
+        // the endpoint is restored once it caught up
+        mocks[0].push(U64::from(111)).unwrap();
+        mocks[1].push(U64::from(111)).unwrap();
+        client.check_health().await;
+        let stats = client.stats();
+        assert!(!stats[0].ejected && !stats[1].ejected && stats[2].ejected);
+    }
+}
@@ -1,6 +1,8 @@
 // Code adapted from: https://github.com/althea-net/guac_rs/tree/master/web3/src/jsonrpc
 
-use super::common::{Authorization, JsonRpcError, Request, Response};
+use super::common::{
+    collect_batch_responses, Authorization, BatchCall, BatchResult, JsonRpcError, Request, Response,
+};
 use crate::{errors::ProviderError, JsonRpcClient};
 use async_trait::async_trait;
 use reqwest::{header::HeaderValue, Client, Error as ReqwestError};
@@ -119,6 +121,27 @@ impl JsonRpcClient for Provider {
 
         Ok(res)
     }
+
+    /// Sends all calls in a single POST request as a JSON-RPC 2.0 batch array
+    async fn batch_request(&self, calls: Vec<BatchCall>) -> Result<Vec<BatchResult>, ClientError> {
+        if calls.is_empty() {
+            return Ok(Vec::new())
+        }
+
+        let first_id = self.id.fetch_add(calls.len() as u64, Ordering::SeqCst);
+        let payload: Vec<_> =
+            calls.iter().zip(first_id..).map(|(call, id)| call.to_request(id)).collect();
+
+        let res = self.client.post(self.url.as_ref()).json(&payload).send().await?;
+        let body = res.bytes().await?;
+
+        serde_json::from_slice(&body)
+            .and_then(|responses| collect_batch_responses(first_id, calls.len(), responses))
+            .map_err(|err| ClientError::SerdeJson {
+                err,
+                text: String::from_utf8_lossy(&body).to_string(),
+            })
+    }
 }
 
 impl Provider {
@@ -0,0 +1,409 @@
+//! A [JsonRpcClient] implementation that reports metrics and tracing spans for every request
+
+use crate::{errors::ProviderError, BatchCall, BatchResult, JsonRpcClient, PubsubClient, RpcError};
+use async_trait::async_trait;
+use ethers_core::types::U256;
+use instant::{Duration, Instant};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::value::RawValue;
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+use thiserror::Error;
+use tracing::{debug, debug_span, Instrument};
+
+/// The outcome of a request sent by an [InstrumentedClient]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestEvent<'a> {
+    /// The id of the request, unique for the client
+    pub id: u64,
+    /// The JSON-RPC method
+    pub method: &'a str,
+    /// The time it took to receive the response
+    pub duration: Duration,
+    /// The size of the serialized params, in bytes
+    pub request_size: usize,
+    /// The size of the serialized result, in bytes, or `None` if the request failed
+    pub response_size: Option<usize>,
+    /// Whether the request failed
+    pub is_error: bool,
+    /// The code of the JSON-RPC error response, if the node returned one
+    pub error_code: Option<i64>,
+}
+
+/// Receives the metrics of the requests sent by an [InstrumentedClient], e.g. to export them to
+/// a monitoring system.
+pub trait RpcMetrics: Debug + Send + Sync {
+    /// Called before a request is sent
+    fn on_request(&self, _id: u64, _method: &str) {}
+
+    /// Called once a request completed, successfully or not
+    fn on_response(&self, event: &RequestEvent<'_>);
+}
+
+/// [RpcMetrics] emitting a `tracing` event at the `DEBUG` level for every request.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingMetrics;
+
+impl RpcMetrics for TracingMetrics {
+    fn on_response(&self, event: &RequestEvent<'_>) {
+        debug!(
+            id = event.id,
+            method = event.method,
+            duration_ms = event.duration.as_secs_f64() * 1e3,
+            request_size = event.request_size,
+            response_size = event.response_size,
+            is_error = event.is_error,
+            error_code = event.error_code,
+            "JSON-RPC request completed"
+        );
+    }
+}
+
+/// Upper bounds of the latency histogram buckets of [MethodStats], in milliseconds. The last
+/// bucket counts the slower requests.
+pub const LATENCY_BUCKETS_MS: [u64; 12] =
+    [1, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+
+/// The metrics of the requests of a method, collected by [MethodMetrics]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MethodStats {
+    /// Number of completed requests
+    pub requests: u64,
+    /// Number of failed requests
+    pub errors: u64,
+    /// Number of failed requests by JSON-RPC error code
+    pub error_codes: BTreeMap<i64, u64>,
+    /// Total size of the params of all requests, in bytes
+    pub request_bytes: u64,
+    /// Total size of the results of all successful requests, in bytes
+    pub response_bytes: u64,
+    /// Total time spent by all requests
+    pub total_duration: Duration,
+    /// Number of requests by latency, with one more bucket than [LATENCY_BUCKETS_MS] for the
+    /// slower requests
+    pub latency_histogram: [u64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+impl MethodStats {
+    /// Returns the average time spent by a request
+    pub fn average_duration(&self) -> Duration {
+        if self.requests == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(self.total_duration.as_secs_f64() / self.requests as f64)
+        }
+    }
+}
+
+/// [RpcMetrics] aggregating per-method counters and latency histograms in memory, which can be
+/// read with [MethodMetrics::snapshot].
+#[derive(Debug, Default)]
+pub struct MethodMetrics {
+    methods: Mutex<BTreeMap<String, MethodStats>>,
+}
+
+impl MethodMetrics {
+    /// Returns the metrics collected so far, by method
+    pub fn snapshot(&self) -> BTreeMap<String, MethodStats> {
+        self.methods.lock().unwrap().clone()
+    }
+}
+
+impl RpcMetrics for MethodMetrics {
+    fn on_response(&self, event: &RequestEvent<'_>) {
+        let mut methods = self.methods.lock().unwrap();
+        let stats = match methods.get_mut(event.method) {
+            Some(stats) => stats,
+            None => methods.entry(event.method.to_string()).or_default(),
+        };
+
+        stats.requests += 1;
+        if event.is_error {
+            stats.errors += 1;
+        }
+        if let Some(code) = event.error_code {
+            *stats.error_codes.entry(code).or_default() += 1;
+        }
+        stats.request_bytes += event.request_size as u64;
+        stats.response_bytes += event.response_size.unwrap_or_default() as u64;
+        stats.total_duration += event.duration;
+
+        let millis = event.duration.as_millis();
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| millis <= *bound as u128)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        stats.latency_histogram[bucket] += 1;
+    }
+}
+
+/// [InstrumentedClient] presents as a wrapper around [JsonRpcClient] that reports the method,
+/// latency, payload sizes and outcome of every request to a [RpcMetrics] implementation, and
+/// runs each request in a `rpc_request` tracing span carrying its `id` and `method`.
+///
+/// It composes with any transport, e.g. `Http`, `Ws`, `RetryClient` or `QuorumProvider`, and
+/// passes subscriptions through for pubsub transports.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn demo() -> Result<(), Box<dyn std::error::Error>> {
+/// use ethers_providers::{Http, InstrumentedClient, MethodMetrics, Middleware, Provider};
+/// use std::str::FromStr;
+///
+/// let http = Http::from_str("http://localhost:8545")?;
+/// let client = InstrumentedClient::with_metrics(http, MethodMetrics::default());
+/// let provider = Provider::new(client);
+///
+/// provider.get_block_number().await?;
+/// for (method, stats) in provider.as_ref().metrics().snapshot() {
+///     println!("{method}: {} requests, {:?} on average", stats.requests, stats.average_duration());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InstrumentedClient<T, M = TracingMetrics> {
+    inner: T,
+    metrics: M,
+    next_id: AtomicU64,
+}
+
+impl<T: JsonRpcClient> InstrumentedClient<T> {
+    /// Creates a new `InstrumentedClient` reporting the requests as `tracing` events
+    pub fn new(inner: T) -> Self {
+        Self::with_metrics(inner, TracingMetrics)
+    }
+}
+
+impl<T, M> InstrumentedClient<T, M>
+where
+    T: JsonRpcClient,
+    M: RpcMetrics,
+{
+    /// Creates a new `InstrumentedClient` reporting the requests to `metrics`
+    pub fn with_metrics(inner: T, metrics: M) -> Self {
+        Self { inner, metrics, next_id: AtomicU64::new(1) }
+    }
+
+    /// Returns the wrapped client
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns the metrics receiver
+    pub fn metrics(&self) -> &M {
+        &self.metrics
+    }
+}
+
+/// Error thrown when using an [InstrumentedClient]
+#[derive(Error, Debug)]
+pub enum InstrumentedClientError {
+    /// Thrown if the inner client errors
+    #[error(transparent)]
+    Inner(ProviderError),
+    /// (De)Serialization error
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+}
+
+impl RpcError for InstrumentedClientError {
+    fn as_error_response(&self) -> Option<&super::JsonRpcError> {
+        match self {
+            InstrumentedClientError::Inner(err) => err.as_error_response(),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            InstrumentedClientError::Inner(err) => RpcError::as_serde_error(err),
+            InstrumentedClientError::SerdeJson(err) => Some(err),
+        }
+    }
+}
+
+impl From<InstrumentedClientError> for ProviderError {
+    fn from(src: InstrumentedClientError) -> Self {
+        match src {
+            InstrumentedClientError::SerdeJson(err) => err.into(),
+            _ => ProviderError::JsonRpcClientError(Box::new(src)),
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T, M> JsonRpcClient for InstrumentedClient<T, M>
+where
+    T: JsonRpcClient,
+    M: RpcMetrics,
+{
+    type Error = InstrumentedClientError;
+
+    async fn request<A, R>(&self, method: &str, params: A) -> Result<R, Self::Error>
+    where
+        A: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let span = debug_span!("rpc_request", id, method);
+
+        async move {
+            // zero-sized params are passed through, as they are omitted from the request
+            let params = if std::mem::size_of::<A>() == 0 {
+                None
+            } else {
+                Some(serde_json::value::to_raw_value(&params)?)
+            };
+            let request_size = params.as_ref().map_or(0, |params| params.get().len());
+
+            self.metrics.on_request(id, method);
+            let start = Instant::now();
+            let res: Result<Box<RawValue>, T::Error> = match &params {
+                Some(params) => self.inner.request(method, params).await,
+                None => self.inner.request(method, ()).await,
+            };
+
+            let mut event = RequestEvent {
+                id,
+                method,
+                duration: start.elapsed(),
+                request_size,
+                response_size: None,
+                is_error: res.is_err(),
+                error_code: None,
+            };
+            match res {
+                Ok(result) => {
+                    event.response_size = Some(result.get().len());
+                    self.metrics.on_response(&event);
+                    Ok(serde_json::from_str(result.get())?)
+                }
+                Err(err) => {
+                    event.error_code = err.as_error_response().map(|err| err.code);
+                    self.metrics.on_response(&event);
+                    Err(InstrumentedClientError::Inner(err.into()))
+                }
+            }
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Sends the batch with the inner client, reporting every call as a request which took as
+    /// long as the whole batch
+    async fn batch_request(&self, calls: Vec<BatchCall>) -> Result<Vec<BatchResult>, Self::Error> {
+        let first_id = self.next_id.fetch_add(calls.len() as u64, Ordering::Relaxed);
+        let span = debug_span!("rpc_batch", first_id, size = calls.len());
+
+        async move {
+            let events: Vec<_> = calls
+                .iter()
+                .zip(first_id..)
+                .map(|(call, id)| {
+                    self.metrics.on_request(id, &call.method);
+                    (id, call.method.clone(), call.params.as_ref().map_or(0, |p| p.get().len()))
+                })
+                .collect();
+
+            let start = Instant::now();
+            let res = self.inner.batch_request(calls).await;
+            let duration = start.elapsed();
+
+            let results = match res {
+                Ok(results) => results,
+                Err(err) => {
+                    let error_code = err.as_error_response().map(|err| err.code);
+                    for (id, method, request_size) in &events {
+                        self.metrics.on_response(&RequestEvent {
+                            id: *id,
+                            method,
+                            duration,
+                            request_size: *request_size,
+                            response_size: None,
+                            is_error: true,
+                            error_code,
+                        });
+                    }
+                    return Err(InstrumentedClientError::Inner(err.into()))
+                }
+            };
+            for ((id, method, request_size), result) in events.iter().zip(&results) {
+                self.metrics.on_response(&RequestEvent {
+                    id: *id,
+                    method,
+                    duration,
+                    request_size: *request_size,
+                    response_size: result.as_ref().ok().map(|result| result.get().len()),
+                    is_error: result.is_err(),
+                    error_code: result.as_ref().err().map(|err| err.code),
+                });
+            }
+            Ok(results)
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+impl<T, M> PubsubClient for InstrumentedClient<T, M>
+where
+    T: PubsubClient,
+    M: RpcMetrics,
+{
+    type NotificationStream = T::NotificationStream;
+
+    fn subscribe<I: Into<U256>>(&self, id: I) -> Result<Self::NotificationStream, Self::Error> {
+        self.inner.subscribe(id).map_err(|err| InstrumentedClientError::Inner(err.into()))
+    }
+
+    fn unsubscribe<I: Into<U256>>(&self, id: I) -> Result<(), Self::Error> {
+        self.inner.unsubscribe(id).map_err(|err| InstrumentedClientError::Inner(err.into()))
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use crate::{Middleware, MockProvider, Provider};
+    use ethers_core::types::{Address, U64};
+
+    #[tokio::test]
+    async fn records_per_method_metrics() {
+        let mock = MockProvider::new();
+        let provider =
+            Provider::new(InstrumentedClient::with_metrics(mock.clone(), MethodMetrics::default()));
+
+        mock.push(U64::from(1)).unwrap();
+        mock.push(U64::from(2)).unwrap();
+        provider.get_block_number().await.unwrap();
+        provider.get_block_number().await.unwrap();
+        // no response, the request fails
+        provider.get_balance(Address::zero(), None).await.unwrap_err();
+
+        let snapshot = provider.as_ref().metrics().snapshot();
+        let block_number = &snapshot["eth_blockNumber"];
+        assert_eq!((block_number.requests, block_number.errors), (2, 0));
+        assert_eq!(block_number.request_bytes, 0);
+        assert_eq!(block_number.response_bytes, 2 * r#""0x1""#.len() as u64);
+        assert_eq!(block_number.latency_histogram.iter().sum::<u64>(), 2);
+
+        let balance = &snapshot["eth_getBalance"];
+        assert_eq!((balance.requests, balance.errors, balance.response_bytes), (1, 1, 0));
+        assert!(balance.request_bytes > 0);
+        assert!(balance.error_codes.is_empty());
+
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        mock.assert_request("eth_getBalance", (Address::zero(), "latest")).unwrap();
+    }
+}
@@ -1,10 +1,14 @@
-use crate::{JsonRpcClient, ProviderError};
+use super::common::JsonRpcError;
+use crate::{JsonRpcClient, ProviderError, PubsubClient};
 use async_trait::async_trait;
+use ethers_core::types::U256;
+use futures_channel::mpsc;
 use serde::{de::DeserializeOwned, Serialize};
-use serde_json::Value;
+use serde_json::{value::RawValue, Value};
 use std::{
     borrow::Borrow,
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
+    fmt,
     sync::{Arc, Mutex},
 };
 use thiserror::Error;
@@ -18,11 +22,72 @@ enum MockParams {
     Zst,
 }
 
+type Responder = Arc<dyn Fn(&Value) -> Result<Value, MockError> + Send + Sync>;
+
+/// The handler registered for a method, and the calls to that method
+#[derive(Default)]
+struct MethodHandler {
+    // `None` if the responses of the method are popped from the queue
+    responder: Option<Responder>,
+    calls: usize,
+    expected_calls: Option<usize>,
+}
+
+impl fmt::Debug for MethodHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MethodHandler")
+            .field("calls", &self.calls)
+            .field("expected_calls", &self.expected_calls)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The channel of a subscription, created by whichever of `notify` or `subscribe` comes first
+#[derive(Debug)]
+struct MockSubscription {
+    sender: mpsc::UnboundedSender<Box<RawValue>>,
+    receiver: Option<mpsc::UnboundedReceiver<Box<RawValue>>>,
+}
+
+impl MockSubscription {
+    fn new() -> Self {
+        let (sender, receiver) = mpsc::unbounded();
+        Self { sender, receiver: Some(receiver) }
+    }
+}
+
 #[derive(Clone, Debug)]
 /// Mock transport used in test environments.
+///
+/// Responses are either pushed to a queue with [MockProvider::push], and served in reverse
+/// order regardless of the method, or registered per method with [MockProvider::on],
+/// [MockProvider::on_call] or [MockProvider::on_error], in which case they are served whenever
+/// that method is requested, independently of the order of the requests. Methods with a
+/// registered handler never consume the queue.
+///
+/// Subscriptions are supported as well: once `eth_subscribe` returned an id, the notifications
+/// sent with [MockProvider::notify] are streamed to the subscription with that id.
+///
+/// ```
+/// # async fn demo() -> Result<(), Box<dyn std::error::Error>> {
+/// use ethers_core::types::U64;
+/// use ethers_providers::{Middleware, Provider};
+///
+/// let (provider, mock) = Provider::mocked();
+/// mock.on("eth_blockNumber", U64::from(12))?;
+/// mock.expect_calls("eth_blockNumber", 2);
+///
+/// assert_eq!(provider.get_block_number().await?, U64::from(12));
+/// assert_eq!(provider.get_block_number().await?, U64::from(12));
+/// mock.verify()?;
+/// # Ok(())
+/// # }
+/// ```
 pub struct MockProvider {
     requests: Arc<Mutex<VecDeque<(String, MockParams)>>>,
     responses: Arc<Mutex<VecDeque<Value>>>,
+    handlers: Arc<Mutex<HashMap<String, MethodHandler>>>,
+    subscriptions: Arc<Mutex<HashMap<U256, MockSubscription>>>,
 }
 
 impl Default for MockProvider {
@@ -48,7 +113,22 @@ impl JsonRpcClient for MockProvider {
         } else {
             MockParams::Value(serde_json::to_value(params)?)
         };
+        let handler_params = match &params {
+            MockParams::Value(value) => value.clone(),
+            MockParams::Zst => Value::Null,
+        };
         self.requests.lock().unwrap().push_back((method.to_owned(), params));
+
+        let responder = {
+            let mut handlers = self.handlers.lock().unwrap();
+            let handler = handlers.entry(method.to_string()).or_default();
+            handler.calls += 1;
+            handler.responder.clone()
+        };
+        if let Some(responder) = responder {
+            return Ok(serde_json::from_value(responder(&handler_params)?)?)
+        }
+
         let mut data = self.responses.lock().unwrap();
         let element = data.pop_back().ok_or(MockError::EmptyResponses)?;
         let res: R = serde_json::from_value(element)?;
@@ -83,6 +163,8 @@ impl MockProvider {
         Self {
             requests: Arc::new(Mutex::new(VecDeque::new())),
             responses: Arc::new(Mutex::new(VecDeque::new())),
+            handlers: Arc::new(Mutex::new(HashMap::new())),
+            subscriptions: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -92,6 +174,119 @@ impl MockProvider {
         self.responses.lock().unwrap().push_back(value);
         Ok(())
     }
+
+    /// Responds to every request to `method` with `data`, replacing any previous handler
+    pub fn on<T: Serialize + Send + Sync, K: Borrow<T>>(
+        &self,
+        method: &str,
+        data: K,
+    ) -> Result<(), MockError> {
+        let value = serde_json::to_value(data.borrow())?;
+        self.set_responder(method, Arc::new(move |_| Ok(value.clone())));
+        Ok(())
+    }
+
+    /// Responds to every request to `method` with the result of `f`, called with the params of
+    /// the request (`null` if there are none), replacing any previous handler.
+    ///
+    /// Returning an `Err` makes the request fail with that JSON-RPC error response.
+    pub fn on_call<F, R>(&self, method: &str, f: F)
+    where
+        F: Fn(&Value) -> Result<R, JsonRpcError> + Send + Sync + 'static,
+        R: Serialize,
+    {
+        self.set_responder(
+            method,
+            Arc::new(move |params| match f(params) {
+                Ok(res) => Ok(serde_json::to_value(res)?),
+                Err(err) => Err(MockError::JsonRpcError(err)),
+            }),
+        );
+    }
+
+    /// Fails every request to `method` with the JSON-RPC error response `err`, e.g. a revert,
+    /// replacing any previous handler
+    pub fn on_error(&self, method: &str, err: JsonRpcError) {
+        self.set_responder(method, Arc::new(move |_| Err(MockError::JsonRpcError(err.clone()))));
+    }
+
+    fn set_responder(&self, method: &str, responder: Responder) {
+        let mut handlers = self.handlers.lock().unwrap();
+        handlers.entry(method.to_string()).or_default().responder = Some(responder);
+    }
+
+    /// Expects `method` to be requested exactly `times` times, which is checked by
+    /// [MockProvider::verify].
+    ///
+    /// The requests are served by the handler of `method` if one is registered, and from the
+    /// queue otherwise.
+    pub fn expect_calls(&self, method: &str, times: usize) {
+        let mut handlers = self.handlers.lock().unwrap();
+        handlers.entry(method.to_string()).or_default().expected_calls = Some(times);
+    }
+
+    /// Returns how many times `method` was requested
+    pub fn calls(&self, method: &str) -> usize {
+        self.handlers.lock().unwrap().get(method).map_or(0, |handler| handler.calls)
+    }
+
+    /// Checks that every method was requested as many times as set with
+    /// [MockProvider::expect_calls]
+    pub fn verify(&self) -> Result<(), MockError> {
+        let handlers = self.handlers.lock().unwrap();
+        let unmet = handlers
+            .iter()
+            .filter(|(_, handler)| handler.expected_calls.map_or(false, |n| n != handler.calls))
+            // report the same method on every run
+            .min_by(|(a, _), (b, _)| a.cmp(b));
+        match unmet {
+            Some((method, handler)) => Err(MockError::UnmetExpectation {
+                method: method.clone(),
+                expected: handler.expected_calls.unwrap_or_default(),
+                actual: handler.calls,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Emits `notification` on the subscription with the given id. Notifications sent before
+    /// the subscription is created are buffered.
+    pub fn notify<T: Serialize + Send + Sync, K: Borrow<T>>(
+        &self,
+        id: impl Into<U256>,
+        notification: K,
+    ) -> Result<(), MockError> {
+        let notification = serde_json::value::to_raw_value(notification.borrow())?;
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let subscription = subscriptions.entry(id.into()).or_insert_with(MockSubscription::new);
+        // the subscription may have been dropped by the client, which is fine
+        let _ = subscription.sender.unbounded_send(notification);
+        Ok(())
+    }
+}
+
+impl PubsubClient for MockProvider {
+    type NotificationStream = mpsc::UnboundedReceiver<Box<RawValue>>;
+
+    fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, MockError> {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let subscription = subscriptions.entry(id.into()).or_insert_with(MockSubscription::new);
+        let receiver = match subscription.receiver.take() {
+            Some(receiver) => receiver,
+            None => {
+                // subscribing again to the same id starts a new stream
+                *subscription = MockSubscription::new();
+                subscription.receiver.take().unwrap()
+            }
+        };
+        Ok(receiver)
+    }
+
+    fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), MockError> {
+        // dropping the sender terminates the stream
+        self.subscriptions.lock().unwrap().remove(&id.into());
+        Ok(())
+    }
 }
 
 #[derive(Error, Debug)]
@@ -108,11 +303,29 @@ pub enum MockError {
     /// Empty responses array
     #[error("empty responses array, please push some responses")]
     EmptyResponses,
+
+    /// The JSON-RPC error response registered with `on_error` or `on_call`
+    #[error(transparent)]
+    JsonRpcError(JsonRpcError),
+
+    /// A method was not requested as many times as expected
+    #[error("expected {method} to be called {expected} times, but it was called {actual} times")]
+    UnmetExpectation {
+        /// The method
+        method: String,
+        /// The expected number of calls
+        expected: usize,
+        /// The actual number of calls
+        actual: usize,
+    },
 }
 
 impl crate::RpcError for MockError {
     fn as_error_response(&self) -> Option<&super::JsonRpcError> {
-        None
+        match self {
+            MockError::JsonRpcError(err) => Some(err),
+            _ => None,
+        }
     }
 
     fn as_serde_error(&self) -> Option<&serde_json::Error> {
@@ -133,8 +346,9 @@ impl From<MockError> for ProviderError {
 #[cfg(not(target_arch = "wasm32"))]
 mod tests {
     use super::*;
-    use crate::Middleware;
-    use ethers_core::types::U64;
+    use crate::{Middleware, RpcError};
+    use ethers_core::types::{Address, H256, U64};
+    use futures_util::StreamExt;
 
     #[tokio::test]
     async fn pushes_request_and_response() {
@@ -175,4 +389,66 @@ mod tests {
         let block = provider.get_block_number().await.unwrap();
         assert_eq!(block.as_u64(), 12);
     }
+
+    #[tokio::test]
+    async fn serves_method_handlers_in_any_order() {
+        let (provider, mock) = crate::Provider::mocked();
+        mock.on("eth_blockNumber", U64::from(12)).unwrap();
+        mock.on_call("eth_getBalance", |params| {
+            let address: Address = serde_json::from_value(params[0].clone()).unwrap();
+            Ok(U256::from(address.to_low_u64_be()))
+        });
+        mock.on_error(
+            "eth_call",
+            JsonRpcError { code: 3, message: "execution reverted".to_string(), data: None },
+        );
+        mock.expect_calls("eth_blockNumber", 2);
+        mock.expect_calls("eth_getBalance", 1);
+        // queued responses are still served for the other methods
+        mock.push(U64::from(1)).unwrap();
+
+        let balance = provider.get_balance(Address::from_low_u64_be(7), None).await.unwrap();
+        assert_eq!(balance, 7.into());
+        assert_eq!(provider.get_block_number().await.unwrap(), 12.into());
+        assert_eq!(provider.get_chainid().await.unwrap(), 1.into());
+        let err = provider.call(&Default::default(), None).await.unwrap_err();
+        assert!(err.as_error_response().unwrap().is_revert());
+
+        let err = mock.verify().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected eth_blockNumber to be called 2 times, but it was called 1 times"
+        );
+        assert_eq!(provider.get_block_number().await.unwrap(), 12.into());
+        mock.verify().unwrap();
+        assert_eq!(mock.calls("eth_call"), 1);
+    }
+
+    #[tokio::test]
+    async fn expects_calls_served_by_the_queue() {
+        let (provider, mock) = crate::Provider::mocked();
+        mock.expect_calls("eth_blockNumber", 1);
+        mock.push(U64::from(12)).unwrap();
+
+        assert_eq!(provider.get_block_number().await.unwrap(), 12.into());
+        mock.verify().unwrap();
+        assert_eq!(mock.calls("eth_blockNumber"), 1);
+    }
+
+    #[tokio::test]
+    async fn streams_subscription_notifications() {
+        let (provider, mock) = crate::Provider::mocked();
+        mock.on("eth_subscribe", U256::from(1)).unwrap();
+        let hash = H256::repeat_byte(1);
+        // buffered until the subscription is created
+        mock.notify::<H256, _>(1, hash).unwrap();
+
+        let mut stream = provider.subscribe_pending_txs().await.unwrap();
+        mock.notify::<H256, _>(1, H256::repeat_byte(2)).unwrap();
+        assert_eq!(stream.next().await, Some(hash));
+        assert_eq!(stream.next().await, Some(H256::repeat_byte(2)));
+
+        mock.unsubscribe(1).unwrap();
+        assert_eq!(stream.next().await, None);
+    }
 }
@@ -1,5 +1,5 @@
 pub(crate) mod common;
-pub use common::{Authorization, JsonRpcError};
+pub use common::{Authorization, BatchCall, BatchResult, JsonRpcError};
 
 mod http;
 pub use self::http::{ClientError as HttpClientError, Provider as Http};
@@ -10,7 +10,10 @@ mod ipc;
 pub use ipc::{Ipc, IpcError};
 
 mod quorum;
-pub use quorum::{JsonRpcClientWrapper, Quorum, QuorumError, QuorumProvider, WeightedProvider};
+pub use quorum::{
+    BlockPinning, JsonRpcClientWrapper, Quorum, QuorumError, QuorumProvider, QuorumProviderStats,
+    WeightedProvider,
+};
 
 mod rw;
 pub use rw::{RwClient, RwClientError};
@@ -18,10 +21,40 @@ pub use rw::{RwClient, RwClientError};
 mod retry;
 pub use retry::*;
 
+mod batching;
+pub use batching::{BatchingClient, BatchingClientBuilder, BatchingClientError, BatchingStats};
+
+mod instrumented;
+pub use instrumented::{
+    InstrumentedClient, InstrumentedClientError, MethodMetrics, MethodStats, RequestEvent,
+    RpcMetrics, TracingMetrics, LATENCY_BUCKETS_MS,
+};
+
+mod replay;
+pub use replay::{
+    FixtureEntry, RecordedResponse, RecordingClientError, ReplayClient, ReplayClientError,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use replay::{RecordingClient, RecordingStream};
+
+mod rate_limit;
+pub use rate_limit::{ComputeUnits, RateLimitStats, RateLimitedClient, RateLimitedClientBuilder};
+
+mod caching;
+#[cfg(not(target_arch = "wasm32"))]
+pub use caching::DiskCache;
+pub use caching::{CachingClient, CachingClientError, CachingStats, MemoryCache, ResponseCache};
+
+mod fallback;
+pub use fallback::{EndpointStats, FallbackClient, FallbackClientBuilder, FallbackClientError};
+
 #[cfg(all(feature = "ws", not(feature = "legacy-ws")))]
 mod ws;
 #[cfg(all(feature = "ws", not(feature = "legacy-ws")))]
-pub use ws::{ConnectionDetails, WsClient as Ws, WsClientError};
+pub use ws::{
+    Backfill, BackfillStream, ConnectionDetails, ReconnectEvent, SubscriptionEvent, WsClient as Ws,
+    WsClientError,
+};
 
 /// archival websocket
 #[cfg(feature = "legacy-ws")]
@@ -1,4 +1,4 @@
-use crate::{errors::ProviderError, JsonRpcClient, PubsubClient};
+use crate::{errors::ProviderError, BatchCall, BatchResult, JsonRpcClient, PubsubClient, RpcError};
 use async_trait::async_trait;
 use ethers_core::types::{U256, U64};
 use futures_core::Stream;
@@ -9,9 +9,14 @@ use std::{
     fmt::Debug,
     future::Future,
     pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
 };
 use thiserror::Error;
+use tracing::debug;
 
 /// A provider that bundles multiple providers and only returns a value to the
 /// caller once the quorum has been reached.
@@ -70,12 +75,46 @@ use thiserror::Error;
 /// # Ok(())
 /// # }
 /// ```
+///
+/// # Example
+///
+/// Create a `QuorumProvider` which tolerates providers being a few blocks apart: `latest` and
+/// `pending` tags are pinned to the block reached by a quorum of the providers, and block numbers
+/// returned by `eth_blockNumber` agree if they are at most 2 blocks apart.
+///
+/// ```
+/// use ethers_providers::{BlockPinning, Http, QuorumProvider, Quorum, WeightedProvider};
+/// use std::str::FromStr;
+///
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// let provider = QuorumProvider::builder()
+///     .add_providers([
+///         WeightedProvider::new(Http::from_str("http://localhost:8545")?),
+///         WeightedProvider::new(Http::from_str("http://localhost:8546")?),
+///         WeightedProvider::new(Http::from_str("http://localhost:8547")?),
+///     ])
+///     .quorum(Quorum::Majority)
+///     .block_pinning(BlockPinning::Quorum)
+///     .max_block_lag(2)
+///     .build();
+///
+/// // detect misbehaving providers
+/// for (idx, stats) in provider.provider_stats().into_iter().enumerate() {
+///     println!("provider {idx} disagreed {} times", stats.disagreed);
+/// }
+/// # Ok(())
+/// # }
+/// ```
 #[derive(Debug, Clone)]
 pub struct QuorumProvider<T = Box<dyn JsonRpcClientWrapper>> {
     /// What kind of quorum is required
     quorum: Quorum,
     /// The weight at which quorum is reached
     quorum_weight: u64,
+    /// How `latest` and `pending` block tags are pinned to a block number
+    block_pinning: BlockPinning,
+    /// How many blocks apart block numbers may be and still agree
+    max_block_lag: Option<u64>,
     /// All the internal providers this providers runs
     providers: Vec<WeightedProvider<T>>,
 }
@@ -121,17 +160,30 @@ impl<T> QuorumProvider<T> {
         self.providers.push(provider);
         self.quorum_weight = self.quorum.weight(&self.providers)
     }
+
+    /// Returns how often each provider agreed or disagreed with the quorum, in the order of
+    /// [`QuorumProvider::providers`]. Providers which often disagree are likely misbehaving.
+    pub fn provider_stats(&self) -> Vec<QuorumProviderStats> {
+        self.providers.iter().map(|provider| provider.stats.snapshot()).collect()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct QuorumProviderBuilder<T> {
     quorum: Quorum,
+    block_pinning: BlockPinning,
+    max_block_lag: Option<u64>,
     providers: Vec<WeightedProvider<T>>,
 }
 
 impl<T> Default for QuorumProviderBuilder<T> {
     fn default() -> Self {
-        Self { quorum: Default::default(), providers: Vec::new() }
+        Self {
+            quorum: Default::default(),
+            block_pinning: Default::default(),
+            max_block_lag: None,
+            providers: Vec::new(),
+        }
     }
 }
 
@@ -156,12 +208,107 @@ impl<T> QuorumProviderBuilder<T> {
         self
     }
 
+    /// Set how `latest` and `pending` block tags in the request params are pinned to a block
+    /// number, so that all providers answer at the same block
+    pub fn block_pinning(mut self, block_pinning: BlockPinning) -> Self {
+        self.block_pinning = block_pinning;
+        self
+    }
+
+    /// Set how many blocks apart providers may be and still agree.
+    ///
+    /// `eth_blockNumber` returns the highest block number reached by a quorum of the providers,
+    /// where providers agree with a block number if they are at most `max_block_lag` blocks ahead
+    /// of it. With [`BlockPinning::Quorum`], block numbers further ahead than `max_block_lag` from
+    /// the pinned block are ignored.
+    pub fn max_block_lag(mut self, max_block_lag: u64) -> Self {
+        self.max_block_lag = Some(max_block_lag);
+        self
+    }
+
     pub fn build(self) -> QuorumProvider<T> {
         let quorum_weight = self.quorum.weight(&self.providers);
-        QuorumProvider { quorum: self.quorum, quorum_weight, providers: self.providers }
+        QuorumProvider {
+            quorum: self.quorum,
+            quorum_weight,
+            block_pinning: self.block_pinning,
+            max_block_lag: self.max_block_lag,
+            providers: self.providers,
+        }
     }
 }
 
+/// Determines how `latest` and `pending` block tags in the params of requests at a block, like
+/// `eth_call` or `eth_getBalance`, are replaced by a block number before the request is sent to
+/// the providers. Providers only agree on such requests if they answer at the same block.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum BlockPinning {
+    /// `latest` is replaced with the lowest block number of all providers, for the calls which
+    /// take the block as their last param (`eth_call`, `eth_getCode`, `eth_getStorageAt`, ...).
+    /// The tag is kept if any provider fails to return its block number.
+    #[default]
+    Minimum,
+    /// `latest` and `pending` are replaced with the highest block number reached by a quorum of
+    /// the providers, for all the requests at a block, including `eth_getBalance`,
+    /// `eth_getTransactionCount` and `eth_getBlockByNumber`. The tag is kept if the providers
+    /// don't reach a quorum on a block number.
+    ///
+    /// `pending` is kept for `eth_getTransactionCount` and `eth_estimateGas`, since the pending
+    /// state includes the transactions of the mempool, e.g. to compute the next nonce.
+    Quorum,
+}
+
+/// Returns the index of the block param of a request at a block, used by
+/// [`BlockPinning::Quorum`]
+fn block_param_index(method: &str, params: &[Value]) -> Option<usize> {
+    match method {
+        "eth_getBlockByNumber" |
+        "eth_getBlockTransactionCountByNumber" |
+        "eth_getUncleCountByBlockNumber" |
+        "eth_getTransactionByBlockNumberAndIndex" |
+        "eth_getUncleByBlockNumberAndIndex" |
+        "eth_getBlockReceipts" |
+        "trace_block" => Some(0),
+        "eth_getBalance" |
+        "eth_getTransactionCount" |
+        "eth_getCode" |
+        "eth_call" |
+        "eth_estimateGas" |
+        "eth_createAccessList" |
+        "trace_call" |
+        "eth_getProof" |
+        "eth_getStorageAt" => params.len().checked_sub(1),
+        _ => None,
+    }
+}
+
+/// Returns the highest block number `n` such that the providers whose block number is between `n`
+/// and `n + max_block_lag` reach `quorum_weight`, given the block number and weight of each
+/// provider.
+fn agreed_block_number(
+    numbers: &[(U64, u64)],
+    quorum_weight: u64,
+    max_block_lag: Option<u64>,
+) -> Option<U64> {
+    numbers
+        .iter()
+        .map(|(candidate, _)| *candidate)
+        .filter(|candidate| {
+            let weight: u64 = numbers
+                .iter()
+                .filter(|(number, _)| agrees_with_block(*number, *candidate, max_block_lag))
+                .map(|(_, weight)| weight)
+                .sum();
+            weight >= quorum_weight
+        })
+        .max()
+}
+
+/// Returns whether a provider at block `number` agrees that block `agreed` was reached
+fn agrees_with_block(number: U64, agreed: U64, max_block_lag: Option<u64>) -> bool {
+    number >= agreed && max_block_lag.map_or(true, |lag| number - agreed <= U64::from(lag))
+}
+
 impl<T: JsonRpcClientWrapper> QuorumProvider<T> {
     /// Returns the block height that _all_ providers have surpassed.
     ///
@@ -182,6 +329,19 @@ impl<T: JsonRpcClientWrapper> QuorumProvider<T> {
             .ok_or_else(|| ProviderError::CustomError("No Providers".to_string()))
     }
 
+    /// Returns the highest block number reached by a quorum of the providers
+    async fn get_quorum_block_number(&self) -> Option<U64> {
+        let numbers = join_all(self.providers.iter().map(|provider| async move {
+            let block = provider.inner.request("eth_blockNumber", QuorumParams::Zst).await.ok()?;
+            Some((serde_json::from_value::<U64>(block).ok()?, provider.weight))
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        agreed_block_number(&numbers, self.quorum_weight, self.max_block_lag)
+    }
+
     /// Normalizes the request payload depending on the call
     async fn normalize_request(&self, method: &str, q_params: &mut QuorumParams) {
         let params = if let QuorumParams::Value(v) = q_params {
@@ -190,6 +350,28 @@ impl<T: JsonRpcClientWrapper> QuorumProvider<T> {
             // at this time no normalization is required for calls with zero parameters.
             return
         };
+        if self.block_pinning == BlockPinning::Quorum {
+            let block = params.as_array_mut().and_then(|params| {
+                let idx = block_param_index(method, params)?;
+                params.get_mut(idx)
+            });
+            if let Some(block) = block {
+                let pinned = match block.as_str() {
+                    Some("latest") => true,
+                    Some("pending") => {
+                        !matches!(method, "eth_getTransactionCount" | "eth_estimateGas")
+                    }
+                    _ => false,
+                };
+                if pinned {
+                    match self.get_quorum_block_number().await {
+                        Some(number) => *block = serde_json::to_value(number).unwrap_or_default(),
+                        None => debug!(method, "No quorum reached on the block number"),
+                    }
+                }
+            }
+            return
+        }
         match method {
             "eth_call" |
             "eth_createAccessList" |
@@ -274,8 +456,11 @@ type PendingRequest<'a> =
 /// reached a quorum.
 struct QuorumRequest<'a, T> {
     inner: &'a QuorumProvider<T>,
-    /// The different answers with their cumulative weight
-    responses: Vec<(Value, u64)>,
+    /// How many blocks apart block numbers may be and still agree, `None` if responses must be
+    /// equal
+    block_lag: Option<u64>,
+    /// The different answers with the index of their provider
+    responses: Vec<(Value, usize)>,
     /// All the errors the provider yielded
     errors: Vec<ProviderError>,
     // Requests currently pending
@@ -283,8 +468,71 @@ struct QuorumRequest<'a, T> {
 }
 
 impl<'a, T> QuorumRequest<'a, T> {
-    fn new(inner: &'a QuorumProvider<T>, requests: Vec<PendingRequest<'a>>) -> Self {
-        Self { responses: Vec::new(), errors: Vec::new(), inner, requests }
+    fn new(
+        inner: &'a QuorumProvider<T>,
+        block_lag: Option<u64>,
+        requests: Vec<PendingRequest<'a>>,
+    ) -> Self {
+        Self { responses: Vec::new(), errors: Vec::new(), inner, block_lag, requests }
+    }
+
+    fn weight(&self, idx: usize) -> u64 {
+        self.inner.providers[idx].weight
+    }
+
+    /// Returns the value the providers reached a quorum on, if any
+    fn quorum_value(&self) -> Option<Value> {
+        if let Some(lag) = self.block_lag {
+            let numbers = self
+                .responses
+                .iter()
+                .map(|(val, idx)| {
+                    Some((serde_json::from_value(val.clone()).ok()?, self.weight(*idx)))
+                })
+                .collect::<Option<Vec<(U64, u64)>>>();
+            if let Some(numbers) = numbers {
+                let number = agreed_block_number(&numbers, self.inner.quorum_weight, Some(lag))?;
+                return serde_json::to_value(number).ok()
+            }
+        }
+
+        // only the last response may have completed a quorum
+        let (last, _) = self.responses.last()?;
+        let weight: u64 = self
+            .responses
+            .iter()
+            .filter(|(val, _)| val == last)
+            .map(|(_, idx)| self.weight(*idx))
+            .sum();
+        (weight >= self.inner.quorum_weight).then(|| last.clone())
+    }
+
+    /// Returns whether the response agrees with the value of the quorum
+    fn agrees(&self, val: &Value, quorum_value: &Value) -> bool {
+        if val == quorum_value {
+            return true
+        }
+        match (
+            self.block_lag,
+            serde_json::from_value(val.clone()),
+            serde_json::from_value(quorum_value.clone()),
+        ) {
+            (Some(lag), Ok(number), Ok(agreed)) => agrees_with_block(number, agreed, Some(lag)),
+            _ => false,
+        }
+    }
+
+    /// Records which providers agreed with the quorum value
+    fn record_agreement(&self, quorum_value: &Value) {
+        for (val, idx) in &self.responses {
+            let stats = &self.inner.providers[*idx].stats;
+            if self.agrees(val, quorum_value) {
+                stats.agreed.fetch_add(1, Ordering::Relaxed);
+            } else {
+                debug!(provider = idx, response = %val, quorum = %quorum_value, "Provider disagreed with the quorum");
+                stats.disagreed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
     }
 }
 
@@ -297,24 +545,16 @@ impl<'a, T> Future for QuorumRequest<'a, T> {
             let mut request = this.requests.swap_remove(n);
             match request.poll_unpin(cx) {
                 Poll::Ready((Ok(val), idx)) => {
-                    let response_weight = this.inner.providers[idx].weight;
-                    if let Some((_, weight)) = this.responses.iter_mut().find(|(v, _)| &val == v) {
-                        // add the weight to equal response value
-                        *weight += response_weight;
-                        if *weight >= this.inner.quorum_weight {
-                            // reached quorum with multiple responses
-                            return Poll::Ready(Ok(val))
-                        } else {
-                            this.responses.push((val, response_weight));
-                        }
-                    } else if response_weight >= this.inner.quorum_weight {
-                        // reached quorum with single response
+                    this.responses.push((val, idx));
+                    if let Some(val) = this.quorum_value() {
+                        this.record_agreement(&val);
                         return Poll::Ready(Ok(val))
-                    } else {
-                        this.responses.push((val, response_weight));
                     }
                 }
-                Poll::Ready((Err(err), _)) => this.errors.push(err),
+                Poll::Ready((Err(err), idx)) => {
+                    this.inner.providers[idx].stats.failed.fetch_add(1, Ordering::Relaxed);
+                    this.errors.push(err)
+                }
                 _ => {
                     this.requests.push(request);
                 }
@@ -323,8 +563,16 @@ impl<'a, T> Future for QuorumRequest<'a, T> {
 
         if this.requests.is_empty() {
             // No more requests and no quorum reached
-            this.responses.sort_by(|a, b| b.1.cmp(&a.1));
-            let values = std::mem::take(&mut this.responses).into_iter().map(|r| r.0).collect();
+            let mut responses: Vec<(Value, u64)> = Vec::new();
+            for (val, idx) in std::mem::take(&mut this.responses) {
+                let response_weight = this.weight(idx);
+                match responses.iter_mut().find(|(v, _)| &val == v) {
+                    Some((_, weight)) => *weight += response_weight,
+                    None => responses.push((val, response_weight)),
+                }
+            }
+            responses.sort_by(|a, b| b.1.cmp(&a.1));
+            let values = responses.into_iter().map(|r| r.0).collect();
             let errors = std::mem::take(&mut this.errors);
             Poll::Ready(Err(QuorumError::NoQuorumReached { values, errors }))
         } else {
@@ -333,11 +581,43 @@ impl<'a, T> Future for QuorumRequest<'a, T> {
     }
 }
 
+#[derive(Debug, Default)]
+struct ProviderCounters {
+    agreed: AtomicU64,
+    disagreed: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl ProviderCounters {
+    fn snapshot(&self) -> QuorumProviderStats {
+        QuorumProviderStats {
+            agreed: self.agreed.load(Ordering::Relaxed),
+            disagreed: self.disagreed.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// How often a provider of a [`QuorumProvider`] agreed with the quorum.
+///
+/// Only the responses received until the quorum was reached are compared with the quorum, the
+/// remaining requests are dropped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuorumProviderStats {
+    /// Number of responses which agreed with the quorum
+    pub agreed: u64,
+    /// Number of responses which disagreed with the quorum
+    pub disagreed: u64,
+    /// Number of requests which failed
+    pub failed: u64,
+}
+
 /// The configuration of a provider for the `QuorumProvider`
 #[derive(Debug, Clone)]
 pub struct WeightedProvider<T> {
     inner: T,
     weight: u64,
+    stats: Arc<ProviderCounters>,
 }
 
 impl<T> WeightedProvider<T> {
@@ -349,7 +629,7 @@ impl<T> WeightedProvider<T> {
     /// Instantiate a `WeightedProvider` with a set weight
     pub fn with_weight(inner: T, weight: u64) -> Self {
         assert!(weight > 0);
-        Self { inner, weight }
+        Self { inner, weight, stats: Default::default() }
     }
 }
 
@@ -389,6 +669,10 @@ impl From<QuorumError> for ProviderError {
 pub trait JsonRpcClientWrapper: Send + Sync + Debug {
     /// Make a request, as [`crate::JsonRpcClient`]
     async fn request(&self, method: &str, params: QuorumParams) -> Result<Value, ProviderError>;
+
+    /// Send a batch of requests, as [`crate::JsonRpcClient`]
+    async fn batch_request(&self, calls: Vec<BatchCall>)
+        -> Result<Vec<BatchResult>, ProviderError>;
 }
 type NotificationStream =
     Box<dyn futures_core::Stream<Item = Box<RawValue>> + Send + Unpin + 'static>;
@@ -413,6 +697,13 @@ impl<C: JsonRpcClient> JsonRpcClientWrapper for C {
 
         Ok(fut.await.map_err(C::Error::into)?)
     }
+
+    async fn batch_request(
+        &self,
+        calls: Vec<BatchCall>,
+    ) -> Result<Vec<BatchResult>, ProviderError> {
+        JsonRpcClient::batch_request(self, calls).await.map_err(C::Error::into)
+    }
 }
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
@@ -420,6 +711,13 @@ impl JsonRpcClientWrapper for Box<dyn JsonRpcClientWrapper> {
     async fn request(&self, method: &str, params: QuorumParams) -> Result<Value, ProviderError> {
         self.as_ref().request(method, params).await
     }
+
+    async fn batch_request(
+        &self,
+        calls: Vec<BatchCall>,
+    ) -> Result<Vec<BatchResult>, ProviderError> {
+        self.as_ref().batch_request(calls).await
+    }
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
@@ -428,6 +726,13 @@ impl JsonRpcClientWrapper for Box<dyn PubsubClientWrapper> {
     async fn request(&self, method: &str, params: QuorumParams) -> Result<Value, ProviderError> {
         self.as_ref().request(method, params).await
     }
+
+    async fn batch_request(
+        &self,
+        calls: Vec<BatchCall>,
+    ) -> Result<Vec<BatchResult>, ProviderError> {
+        self.as_ref().batch_request(calls).await
+    }
 }
 
 impl<C: PubsubClient> PubsubClientWrapper for C
@@ -485,9 +790,69 @@ where
             })
             .collect::<Vec<_>>();
 
-        let value = QuorumRequest::new(self, requests).await?;
+        let block_lag = if method == "eth_blockNumber" { self.max_block_lag } else { None };
+        let value = QuorumRequest::new(self, block_lag, requests).await?;
         Ok(serde_json::from_value(value)?)
     }
+
+    /// Sends the whole batch to every provider, and requires a quorum on the result of each call.
+    /// A call whose results don't reach a quorum fails with the JSON-RPC error returned by a
+    /// provider, if any, and fails the whole batch otherwise.
+    async fn batch_request(&self, calls: Vec<BatchCall>) -> Result<Vec<BatchResult>, Self::Error> {
+        let mut normalized = Vec::with_capacity(calls.len());
+        for call in calls {
+            let mut params = match &call.params {
+                Some(params) => QuorumParams::Value(serde_json::from_str(params.get())?),
+                None => QuorumParams::Zst,
+            };
+            self.normalize_request(&call.method, &mut params).await;
+            normalized.push(match params {
+                QuorumParams::Value(params) => BatchCall::new(call.method, params)?,
+                QuorumParams::Zst => call,
+            });
+        }
+
+        let batches = join_all(
+            self.providers.iter().map(|provider| provider.inner.batch_request(normalized.clone())),
+        )
+        .await;
+
+        let mut results = Vec::with_capacity(normalized.len());
+        for (n, call) in normalized.iter().enumerate() {
+            let requests = batches
+                .iter()
+                .enumerate()
+                .map(|(idx, batch)| {
+                    let res = match batch {
+                        Ok(batch) => match batch.get(n) {
+                            Some(Ok(res)) => serde_json::from_str(res.get()).map_err(Into::into),
+                            Some(Err(err)) => {
+                                Err(ProviderError::JsonRpcClientError(Box::new(err.clone())))
+                            }
+                            None => Err(ProviderError::CustomError(
+                                "Missing result in batch response".to_string(),
+                            )),
+                        },
+                        Err(err) => Err(ProviderError::CustomError(err.to_string())),
+                    };
+                    Box::pin(futures_util::future::ready((res, idx))) as PendingRequest
+                })
+                .collect::<Vec<_>>();
+
+            let block_lag =
+                if call.method == "eth_blockNumber" { self.max_block_lag } else { None };
+            match QuorumRequest::new(self, block_lag, requests).await {
+                Ok(value) => results.push(Ok(serde_json::value::to_raw_value(&value)?)),
+                Err(QuorumError::NoQuorumReached { values, errors }) => {
+                    match errors.iter().find_map(|err| err.as_error_response()) {
+                        Some(err) => results.push(Err(err.clone())),
+                        None => return Err(QuorumError::NoQuorumReached { values, errors }.into()),
+                    }
+                }
+            }
+        }
+        Ok(results)
+    }
 }
 
 // A stream that returns a value and the weight of its provider
@@ -602,9 +967,9 @@ pub enum QuorumParams {
 #[cfg(test)]
 #[cfg(not(target_arch = "wasm32"))]
 mod tests {
-    use super::{Quorum, QuorumProvider, WeightedProvider};
-    use crate::{Middleware, MockProvider, Provider};
-    use ethers_core::types::U64;
+    use super::*;
+    use crate::{Middleware, MockError, MockProvider, Provider};
+    use ethers_core::types::{Address, BlockNumber};
 
     async fn test_quorum(q: Quorum) {
         let num = 5u64;
@@ -672,4 +1037,94 @@ mod tests {
     async fn all_quorum() {
         test_quorum(Quorum::All).await
     }
+
+    fn mocked(n: usize) -> (Vec<MockProvider>, QuorumProviderBuilder<MockProvider>) {
+        let mocks: Vec<_> = (0..n).map(|_| MockProvider::new()).collect();
+        let builder = QuorumProvider::builder()
+            .add_providers(mocks.iter().cloned().map(WeightedProvider::new))
+            .quorum(Quorum::Majority);
+        (mocks, builder)
+    }
+
+    #[tokio::test]
+    async fn pins_latest_to_quorum_block() {
+        let (mocks, builder) = mocked(3);
+        let provider = Provider::quorum(builder.block_pinning(BlockPinning::Quorum).build());
+        let address = Address::random();
+
+        // a majority of the providers reached block 11
+        for (mock, head) in mocks.iter().zip([10u64, 11, 12]) {
+            mock.push(U256::from(100)).unwrap();
+            mock.push(U64::from(head)).unwrap();
+        }
+        let balance = provider.get_balance(address, None).await.unwrap();
+        assert_eq!(balance, 100.into());
+        // the requests which were sent once the quorum was reached are dropped
+        let mut requested = 0;
+        for mock in &mocks {
+            mock.assert_request("eth_blockNumber", ()).unwrap();
+            requested += mock.assert_request("eth_getBalance", (address, "0xb")).is_ok() as usize;
+        }
+        assert!(requested >= 2);
+    }
+
+    #[tokio::test]
+    async fn keeps_pending_transaction_count() {
+        let (mocks, builder) = mocked(3);
+        let provider = Provider::quorum(builder.block_pinning(BlockPinning::Quorum).build());
+        let address = Address::random();
+
+        for mock in &mocks {
+            mock.push(U256::from(7)).unwrap();
+        }
+        let block = Some(BlockNumber::Pending.into());
+        let count = provider.get_transaction_count(address, block).await.unwrap();
+        assert_eq!(count, 7.into());
+        for mock in &mocks {
+            // the request may have been dropped once the quorum was reached
+            if let Err(err) = mock.assert_request("eth_getTransactionCount", (address, "pending")) {
+                assert!(matches!(err, MockError::EmptyRequests), "{err}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn block_numbers_within_lag_agree() {
+        let (mocks, builder) = mocked(3);
+        let quorum = builder.max_block_lag(1).build();
+        let provider = Provider::quorum(quorum.clone());
+
+        for (mock, head) in mocks.iter().zip([10u64, 11, 20]) {
+            mock.push(U64::from(head)).unwrap();
+        }
+        assert_eq!(provider.get_block_number().await.unwrap(), 10.into());
+
+        let stats = quorum.provider_stats();
+        assert_eq!(stats[0], QuorumProviderStats { agreed: 1, disagreed: 0, failed: 0 });
+        assert_eq!(stats[1], QuorumProviderStats { agreed: 1, disagreed: 0, failed: 0 });
+        assert_eq!(stats[2], QuorumProviderStats { agreed: 0, disagreed: 1, failed: 0 });
+    }
+
+    #[tokio::test]
+    async fn reports_disagreeing_providers() {
+        let (mocks, builder) = mocked(3);
+        let quorum = builder.build();
+        let provider = Provider::quorum(quorum.clone());
+
+        // the last provider fails, the other ones disagree
+        mocks[0].push(U64::from(10)).unwrap();
+        mocks[1].push(U64::from(11)).unwrap();
+        provider.get_block_number().await.unwrap_err();
+        let stats = quorum.provider_stats();
+        assert_eq!(stats[2], QuorumProviderStats { agreed: 0, disagreed: 0, failed: 1 });
+
+        mocks[0].push(U64::from(10)).unwrap();
+        mocks[1].push(U64::from(10)).unwrap();
+        mocks[2].push(U64::from(11)).unwrap();
+        assert_eq!(provider.get_block_number().await.unwrap(), 10.into());
+        let stats = quorum.provider_stats();
+        assert_eq!(stats[0], QuorumProviderStats { agreed: 1, disagreed: 0, failed: 0 });
+        assert_eq!(stats[1], QuorumProviderStats { agreed: 1, disagreed: 0, failed: 0 });
+        assert_eq!(stats[2], QuorumProviderStats { agreed: 0, disagreed: 1, failed: 1 });
+    }
 }
@@ -0,0 +1,385 @@
+//! A [JsonRpcClient] implementation that proactively limits the rate of requests with a token
+//! bucket, weighted by the compute units of each method.
+
+use crate::{BatchCall, BatchResult, JsonRpcClient};
+use async_trait::async_trait;
+use futures_timer::Delay;
+use instant::{Duration, Instant};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::HashMap, fmt::Debug, sync::Mutex};
+use tracing::trace;
+
+/// The cost of each JSON-RPC method, in compute units.
+///
+/// Node providers like Alchemy or Infura bill each method at a different price and limit the
+/// number of compute units which can be spent per second. Methods missing from the table cost
+/// the default cost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComputeUnits {
+    default_cost: u64,
+    costs: HashMap<String, u64>,
+}
+
+impl ComputeUnits {
+    /// Creates a table where every method costs `default_cost`
+    pub fn new(default_cost: u64) -> Self {
+        Self { default_cost, costs: HashMap::new() }
+    }
+
+    /// Sets the cost of a method
+    pub fn with_cost(mut self, method: impl Into<String>, cost: u64) -> Self {
+        self.costs.insert(method.into(), cost);
+        self
+    }
+
+    /// Returns the cost of a method
+    pub fn cost(&self, method: &str) -> u64 {
+        self.costs.get(method).copied().unwrap_or(self.default_cost)
+    }
+
+    /// The compute units of the methods as priced by Alchemy, see
+    /// <https://docs.alchemy.com/reference/compute-units>. Other methods cost as much as
+    /// `eth_call`.
+    pub fn alchemy() -> Self {
+        [
+            ("eth_accounts", 10),
+            ("eth_blockNumber", 10),
+            ("eth_call", 26),
+            ("eth_chainId", 0),
+            ("eth_createAccessList", 10),
+            ("eth_estimateGas", 87),
+            ("eth_feeHistory", 10),
+            ("eth_gasPrice", 19),
+            ("eth_getBalance", 19),
+            ("eth_getBlockByHash", 21),
+            ("eth_getBlockByNumber", 16),
+            ("eth_getBlockReceipts", 500),
+            ("eth_getBlockTransactionCountByHash", 20),
+            ("eth_getBlockTransactionCountByNumber", 20),
+            ("eth_getCode", 26),
+            ("eth_getFilterChanges", 20),
+            ("eth_getFilterLogs", 75),
+            ("eth_getLogs", 75),
+            ("eth_getProof", 21),
+            ("eth_getStorageAt", 17),
+            ("eth_getTransactionByBlockHashAndIndex", 15),
+            ("eth_getTransactionByBlockNumberAndIndex", 15),
+            ("eth_getTransactionByHash", 17),
+            ("eth_getTransactionCount", 26),
+            ("eth_getTransactionReceipt", 15),
+            ("eth_getUncleByBlockHashAndIndex", 15),
+            ("eth_getUncleByBlockNumberAndIndex", 15),
+            ("eth_maxPriorityFeePerGas", 10),
+            ("eth_newBlockFilter", 20),
+            ("eth_newFilter", 20),
+            ("eth_newPendingTransactionFilter", 20),
+            ("eth_protocolVersion", 0),
+            ("eth_sendRawTransaction", 250),
+            ("eth_subscribe", 10),
+            ("eth_syncing", 0),
+            ("eth_uninstallFilter", 10),
+            ("eth_unsubscribe", 10),
+            ("net_listening", 0),
+            ("net_version", 0),
+            ("web3_clientVersion", 0),
+            ("web3_sha3", 10),
+            ("debug_traceCall", 309),
+            ("debug_traceTransaction", 309),
+            ("trace_block", 24),
+            ("trace_call", 75),
+            ("trace_filter", 75),
+            ("trace_get", 17),
+            ("trace_replayBlockTransactions", 2983),
+            ("trace_replayTransaction", 2983),
+            ("trace_transaction", 26),
+        ]
+        .into_iter()
+        .fold(Self::new(26), |table, (method, cost)| table.with_cost(method, cost))
+    }
+}
+
+impl Default for ComputeUnits {
+    fn default() -> Self {
+        Self::alchemy()
+    }
+}
+
+/// [RateLimitedClient] presents as a wrapper around [JsonRpcClient] that delays requests so that
+/// the compute units spent per second stay within a budget, instead of relying on the endpoint
+/// rejecting requests with `429 Too Many Requests` and retrying them like the
+/// [RetryClient](crate::RetryClient).
+///
+/// The budget is a token bucket holding up to `burst` compute units, refilled at
+/// `compute_units_per_second`. Each request takes the cost of its method from the bucket, and
+/// waits until the bucket would have held enough units if it was empty. Requests are served in
+/// the order they were issued, so that no concurrent task is starved by cheaper requests.
+/// Methods which cost no units are never delayed.
+///
+/// Requests which are dropped while waiting keep their units spent.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn demo() -> Result<(), Box<dyn std::error::Error>> {
+/// use ethers_providers::{ComputeUnits, Http, Middleware, Provider, RateLimitedClientBuilder};
+/// use std::str::FromStr;
+///
+/// let http = Http::from_str("http://localhost:8545")?;
+/// let client = RateLimitedClientBuilder::default()
+///     .compute_units_per_second(330)
+///     .burst(660)
+///     .costs(ComputeUnits::alchemy().with_cost("eth_getLogs", 100))
+///     .build(http);
+/// let provider = Provider::new(client);
+///
+/// let block = provider.get_block_number().await?;
+/// println!("{:?}", provider.as_ref().stats());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RateLimitedClient<T> {
+    inner: T,
+    costs: ComputeUnits,
+    compute_units_per_second: f64,
+    burst: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    /// The units in the bucket, negative if requests are waiting for the bucket to refill
+    units: f64,
+    /// When `units` was last updated
+    updated: Instant,
+    stats: RateLimitStats,
+}
+
+/// A snapshot of the metrics collected by a [RateLimitedClient]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimitStats {
+    /// Number of requests sent
+    pub requests: u64,
+    /// Number of requests which were delayed
+    pub delayed: u64,
+    /// Compute units spent by all requests
+    pub compute_units: u64,
+    /// Time spent waiting by all requests
+    pub total_delay: Duration,
+    /// Longest time a request waited
+    pub max_delay: Duration,
+}
+
+impl RateLimitStats {
+    /// Returns the average time a request waited
+    pub fn average_delay(&self) -> Duration {
+        if self.requests == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(self.total_delay.as_secs_f64() / self.requests as f64)
+        }
+    }
+}
+
+impl<T> RateLimitedClient<T>
+where
+    T: JsonRpcClient,
+{
+    /// Creates a new `RateLimitedClient` with the default budget and costs
+    pub fn new(inner: T) -> Self {
+        RateLimitedClientBuilder::default().build(inner)
+    }
+
+    /// Returns the wrapped client
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns the cost of the methods
+    pub fn costs(&self) -> &ComputeUnits {
+        &self.costs
+    }
+
+    /// Returns a snapshot of the metrics collected so far
+    pub fn stats(&self) -> RateLimitStats {
+        self.state.lock().unwrap().stats
+    }
+
+    /// Takes `cost` units from the bucket and returns how long to wait until they are available
+    fn reserve(&self, cost: u64) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let refilled =
+            now.duration_since(state.updated).as_secs_f64() * self.compute_units_per_second;
+        state.units = (state.units + refilled).min(self.burst) - cost as f64;
+        state.updated = now;
+
+        // free requests don't wait for the requests issued before them
+        let delay = if cost > 0 && state.units < 0.0 {
+            Duration::from_secs_f64(-state.units / self.compute_units_per_second)
+        } else {
+            Duration::ZERO
+        };
+
+        let stats = &mut state.stats;
+        stats.requests += 1;
+        stats.compute_units += cost;
+        if !delay.is_zero() {
+            stats.delayed += 1;
+            stats.total_delay += delay;
+            stats.max_delay = stats.max_delay.max(delay);
+        }
+        delay
+    }
+}
+
+/// Builder for a [`RateLimitedClient`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitedClientBuilder {
+    compute_units_per_second: u64,
+    burst: Option<u64>,
+    costs: ComputeUnits,
+}
+
+// === impl RateLimitedClientBuilder ===
+
+impl RateLimitedClientBuilder {
+    /// Sets the number of compute units which can be spent per second.
+    ///
+    /// A value of `0` is treated as `1`.
+    pub fn compute_units_per_second(mut self, compute_units_per_second: u64) -> Self {
+        self.compute_units_per_second = compute_units_per_second.max(1);
+        self
+    }
+
+    /// Sets the number of compute units which can be spent at once after a period of inactivity.
+    /// Defaults to the compute units of one second.
+    pub fn burst(mut self, burst: u64) -> Self {
+        self.burst = Some(burst);
+        self
+    }
+
+    /// Sets the cost of the methods
+    pub fn costs(mut self, costs: ComputeUnits) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Creates the `RateLimitedClient` with the configured settings. The bucket starts full.
+    pub fn build<T>(self, client: T) -> RateLimitedClient<T>
+    where
+        T: JsonRpcClient,
+    {
+        let RateLimitedClientBuilder { compute_units_per_second, burst, costs } = self;
+        let burst = burst.unwrap_or(compute_units_per_second) as f64;
+        RateLimitedClient {
+            inner: client,
+            costs,
+            compute_units_per_second: compute_units_per_second as f64,
+            burst,
+            state: Mutex::new(BucketState {
+                units: burst,
+                updated: Instant::now(),
+                stats: Default::default(),
+            }),
+        }
+    }
+}
+
+impl Default for RateLimitedClientBuilder {
+    fn default() -> Self {
+        Self {
+            // alchemy max cpus <https://github.com/alchemyplatform/alchemy-docs/blob/master/documentation/compute-units.md#rate-limits-cups>
+            compute_units_per_second: 330,
+            burst: None,
+            costs: Default::default(),
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T> JsonRpcClient for RateLimitedClient<T>
+where
+    T: JsonRpcClient,
+{
+    type Error = T::Error;
+
+    async fn request<A, R>(&self, method: &str, params: A) -> Result<R, Self::Error>
+    where
+        A: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let delay = self.reserve(self.costs.cost(method));
+        if !delay.is_zero() {
+            trace!(method, ?delay, "Delaying request to stay within the compute units budget");
+            Delay::new(delay).await;
+        }
+        self.inner.request(method, params).await
+    }
+
+    /// Reserves the compute units of all calls at once, then sends the batch with the inner client
+    async fn batch_request(&self, calls: Vec<BatchCall>) -> Result<Vec<BatchResult>, Self::Error> {
+        let cost = calls.iter().map(|call| self.costs.cost(&call.method)).sum();
+        let delay = self.reserve(cost);
+        if !delay.is_zero() {
+            trace!(
+                size = calls.len(),
+                ?delay,
+                "Delaying batch to stay within the compute units budget"
+            );
+            Delay::new(delay).await;
+        }
+        self.inner.batch_request(calls).await
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use crate::{Middleware, MockProvider, Provider};
+    use ethers_core::types::U64;
+
+    #[tokio::test]
+    async fn delays_requests_over_budget() {
+        let mock = MockProvider::new();
+        let client = RateLimitedClientBuilder::default()
+            .compute_units_per_second(100)
+            .burst(20)
+            .costs(ComputeUnits::new(10).with_cost("eth_chainId", 0))
+            .build(mock.clone());
+        let provider = Provider::new(client);
+
+        for _ in 0..4 {
+            mock.push(U64::from(1)).unwrap();
+        }
+        let start = Instant::now();
+        futures_util::future::try_join_all((0..4).map(|_| provider.get_block_number()))
+            .await
+            .unwrap();
+        // the burst covers the first two requests, the next ones wait 100ms each
+        assert!(start.elapsed() >= Duration::from_millis(190), "{:?}", start.elapsed());
+
+        let stats = provider.as_ref().stats();
+        assert_eq!((stats.requests, stats.delayed, stats.compute_units), (4, 2, 40));
+        assert!(stats.max_delay >= Duration::from_millis(190));
+        assert!(stats.max_delay < Duration::from_millis(250));
+
+        // free methods are never delayed, even when the budget is exhausted
+        assert!(provider.as_ref().reserve(100) > Duration::ZERO);
+        mock.push(U64::from(1)).unwrap();
+        let start = Instant::now();
+        provider.get_chainid().await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(100), "{:?}", start.elapsed());
+        assert_eq!(provider.as_ref().stats().delayed, 3);
+    }
+
+    #[test]
+    fn alchemy_costs() {
+        let costs = ComputeUnits::alchemy();
+        assert_eq!(costs.cost("eth_getLogs"), 75);
+        assert_eq!(costs.cost("eth_unknownMethod"), 26);
+        assert_eq!(costs.with_cost("eth_getLogs", 100).cost("eth_getLogs"), 100);
+    }
+}
@@ -0,0 +1,531 @@
+//! [JsonRpcClient] implementations that record the traffic of a client to a fixture file and
+//! replay it, so tests written against a live node can run offline
+
+use super::common::JsonRpcError;
+use crate::{errors::ProviderError, BatchCall, BatchResult, JsonRpcClient, PubsubClient, RpcError};
+use async_trait::async_trait;
+use ethers_core::types::U256;
+use futures_util::stream::{self, Stream};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{value::RawValue, Value};
+use std::{collections::HashMap, fmt::Debug, sync::Mutex};
+use thiserror::Error;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// An entry of a fixture, written by a [RecordingClient] and served by a [ReplayClient].
+///
+/// Fixtures are stored as JSON lines, one entry per line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FixtureEntry {
+    /// A request and the response of the node
+    Request {
+        /// The JSON-RPC method
+        method: String,
+        /// The params of the request, `null` if there were none
+        #[serde(default)]
+        params: Value,
+        /// The response of the node
+        response: RecordedResponse,
+    },
+    /// A notification received on a subscription
+    Notification {
+        /// The id of the subscription, as returned by `eth_subscribe`
+        subscription: U256,
+        /// The notification
+        notification: Value,
+    },
+}
+
+/// The response to a recorded request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordedResponse {
+    /// The request succeeded
+    Result(Value),
+    /// The node returned a JSON-RPC error
+    Error(JsonRpcError),
+}
+
+/// Parses the JSON lines of a fixture
+fn parse_fixture(s: &str) -> Result<Vec<FixtureEntry>, serde_json::Error> {
+    s.lines().filter(|line| !line.trim().is_empty()).map(serde_json::from_str).collect()
+}
+
+/// Error thrown when using a [RecordingClient]
+#[derive(Error, Debug)]
+pub enum RecordingClientError {
+    /// Thrown if the inner client errors
+    #[error(transparent)]
+    Inner(ProviderError),
+    /// Thrown if the fixture could not be written
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// (De)Serialization error
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+}
+
+impl RpcError for RecordingClientError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            RecordingClientError::Inner(err) => err.as_error_response(),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            RecordingClientError::Inner(err) => RpcError::as_serde_error(err),
+            RecordingClientError::SerdeJson(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<RecordingClientError> for ProviderError {
+    fn from(src: RecordingClientError) -> Self {
+        match src {
+            RecordingClientError::SerdeJson(err) => err.into(),
+            _ => ProviderError::JsonRpcClientError(Box::new(src)),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type FixtureWriter = Arc<Mutex<BufWriter<File>>>;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_entry(writer: &FixtureWriter, entry: &FixtureEntry) -> Result<(), RecordingClientError> {
+    let mut writer = writer.lock().unwrap();
+    serde_json::to_writer(&mut *writer, entry)?;
+    writer.write_all(b"\n")?;
+    // flush every entry, so that the fixture is complete even if the process is killed
+    writer.flush()?;
+    Ok(())
+}
+
+/// [RecordingClient] presents as a wrapper around [JsonRpcClient] that appends every request,
+/// its response and, for pubsub clients, every subscription notification to a fixture file,
+/// which can be served back by a [ReplayClient].
+///
+/// Requests failing with a JSON-RPC error response are recorded too, other errors (e.g. a
+/// connection failure) are not.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn demo() -> Result<(), Box<dyn std::error::Error>> {
+/// use ethers_providers::{Http, Middleware, Provider, RecordingClient, ReplayClient};
+/// use std::str::FromStr;
+///
+/// // record the traffic of a test against a live node once...
+/// let http = Http::from_str("http://localhost:8545")?;
+/// let provider = Provider::new(RecordingClient::new(http, "fixtures/block_number.jsonl")?);
+/// let block = provider.get_block_number().await?;
+///
+/// // ...and replay it without a node
+/// let provider = Provider::new(ReplayClient::open("fixtures/block_number.jsonl")?);
+/// assert_eq!(provider.get_block_number().await?, block);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct RecordingClient<T> {
+    inner: T,
+    writer: FixtureWriter,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: JsonRpcClient> RecordingClient<T> {
+    /// Creates a new `RecordingClient` writing to the fixture at `path`, which is truncated if
+    /// it exists
+    pub fn new(inner: T, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self { inner, writer: Arc::new(Mutex::new(BufWriter::new(file))) })
+    }
+
+    /// Returns the wrapped client
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl<T: JsonRpcClient> JsonRpcClient for RecordingClient<T> {
+    type Error = RecordingClientError;
+
+    async fn request<A, R>(&self, method: &str, params: A) -> Result<R, Self::Error>
+    where
+        A: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let recorded_params = serde_json::to_value(&params)?;
+        let (response, res) = match self.inner.request::<_, Value>(method, params).await {
+            Ok(value) => (RecordedResponse::Result(value.clone()), Ok(value)),
+            Err(err) => match err.as_error_response() {
+                Some(err_resp) => (RecordedResponse::Error(err_resp.clone()), Err(err)),
+                None => return Err(RecordingClientError::Inner(err.into())),
+            },
+        };
+
+        let entry =
+            FixtureEntry::Request { method: method.to_string(), params: recorded_params, response };
+        write_entry(&self.writer, &entry)?;
+
+        let value = res.map_err(|err| RecordingClientError::Inner(err.into()))?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Sends the batch with the inner client and records every call as a separate request
+    async fn batch_request(&self, calls: Vec<BatchCall>) -> Result<Vec<BatchResult>, Self::Error> {
+        let recorded_calls = calls
+            .iter()
+            .map(|call| {
+                let params = match &call.params {
+                    Some(params) => serde_json::from_str(params.get())?,
+                    None => Value::Null,
+                };
+                Ok((call.method.clone(), params))
+            })
+            .collect::<Result<Vec<_>, serde_json::Error>>()?;
+
+        let results = self
+            .inner
+            .batch_request(calls)
+            .await
+            .map_err(|err| RecordingClientError::Inner(err.into()))?;
+
+        for ((method, params), result) in recorded_calls.into_iter().zip(&results) {
+            let response = match result {
+                Ok(value) => RecordedResponse::Result(serde_json::from_str(value.get())?),
+                Err(err) => RecordedResponse::Error(err.clone()),
+            };
+            write_entry(&self.writer, &FixtureEntry::Request { method, params, response })?;
+        }
+        Ok(results)
+    }
+}
+
+/// The notifications of a subscription of a [RecordingClient], recorded as they are polled
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct RecordingStream<S> {
+    inner: S,
+    subscription: U256,
+    writer: FixtureWriter,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<S> Stream for RecordingStream<S>
+where
+    S: Stream<Item = Box<RawValue>> + Unpin,
+{
+    type Item = Box<RawValue>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = futures_util::ready!(Pin::new(&mut self.inner).poll_next(cx));
+        if let Some(notification) = &item {
+            let subscription = self.subscription;
+            let res = serde_json::from_str(notification.get()).map_err(Into::into).and_then(
+                |notification| {
+                    let entry = FixtureEntry::Notification { subscription, notification };
+                    write_entry(&self.writer, &entry)
+                },
+            );
+            if let Err(err) = res {
+                tracing::warn!(?err, ?subscription, "failed to record notification");
+            }
+        }
+        Poll::Ready(item)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: PubsubClient> PubsubClient for RecordingClient<T> {
+    type NotificationStream = RecordingStream<T::NotificationStream>;
+
+    fn subscribe<I: Into<U256>>(&self, id: I) -> Result<Self::NotificationStream, Self::Error> {
+        let subscription = id.into();
+        let inner = self
+            .inner
+            .subscribe(subscription)
+            .map_err(|err| RecordingClientError::Inner(err.into()))?;
+        Ok(RecordingStream { inner, subscription, writer: self.writer.clone() })
+    }
+
+    fn unsubscribe<I: Into<U256>>(&self, id: I) -> Result<(), Self::Error> {
+        self.inner.unsubscribe(id).map_err(|err| RecordingClientError::Inner(err.into()))
+    }
+}
+
+/// Error thrown when using a [ReplayClient]
+#[derive(Error, Debug)]
+pub enum ReplayClientError {
+    /// Thrown if no recorded request matches the request
+    #[error("no recorded response for {method} with params {params}")]
+    NoMatchingRequest {
+        /// The method of the request
+        method: String,
+        /// The params of the request
+        params: Value,
+    },
+    /// Thrown in strict mode if the request is not the next recorded one
+    #[error("unexpected request {method} with params {params}, expected {expected}")]
+    UnexpectedRequest {
+        /// The method of the request
+        method: String,
+        /// The params of the request
+        params: Value,
+        /// The method of the next recorded request
+        expected: String,
+    },
+    /// The recorded JSON-RPC error response
+    #[error(transparent)]
+    JsonRpcError(JsonRpcError),
+    /// Thrown if the fixture could not be read
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// (De)Serialization error
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+}
+
+impl RpcError for ReplayClientError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            ReplayClientError::JsonRpcError(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            ReplayClientError::SerdeJson(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ReplayClientError> for ProviderError {
+    fn from(src: ReplayClientError) -> Self {
+        match src {
+            ReplayClientError::SerdeJson(err) => err.into(),
+            _ => ProviderError::JsonRpcClientError(Box::new(src)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RecordedRequest {
+    method: String,
+    params: Value,
+    response: RecordedResponse,
+    served: bool,
+}
+
+/// [ReplayClient] is a [JsonRpcClient] serving the responses recorded by a [RecordingClient].
+///
+/// Requests are matched by method and params, and every recorded response is served once, in
+/// the order it was recorded, so that repeated requests (e.g. polling the block number) get
+/// successive responses. With [ReplayClient::strict_ordering], requests must also be sent in
+/// the order they were recorded.
+///
+/// The notifications of a subscription are all available as soon as it is created.
+#[derive(Debug)]
+pub struct ReplayClient {
+    requests: Mutex<Vec<RecordedRequest>>,
+    notifications: Mutex<HashMap<U256, Vec<Value>>>,
+    strict: bool,
+}
+
+impl ReplayClient {
+    /// Creates a new `ReplayClient` serving the given fixture entries
+    pub fn new(entries: impl IntoIterator<Item = FixtureEntry>) -> Self {
+        let mut requests = Vec::new();
+        let mut notifications: HashMap<U256, Vec<Value>> = HashMap::new();
+        for entry in entries {
+            match entry {
+                FixtureEntry::Request { method, params, response } => {
+                    requests.push(RecordedRequest { method, params, response, served: false })
+                }
+                FixtureEntry::Notification { subscription, notification } => {
+                    notifications.entry(subscription).or_default().push(notification)
+                }
+            }
+        }
+        Self {
+            requests: Mutex::new(requests),
+            notifications: Mutex::new(notifications),
+            strict: false,
+        }
+    }
+
+    /// Creates a new `ReplayClient` serving the fixture at `path`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ReplayClientError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Creates a new `ReplayClient` serving the fixture in `s`, as JSON lines
+    pub fn parse(s: &str) -> Result<Self, ReplayClientError> {
+        Ok(Self::new(parse_fixture(s)?))
+    }
+
+    /// Requires the requests to be sent in the order they were recorded.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn strict_ordering(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Returns the number of recorded requests that were not served yet
+    pub fn remaining(&self) -> usize {
+        self.requests.lock().unwrap().iter().filter(|req| !req.served).count()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl JsonRpcClient for ReplayClient {
+    type Error = ReplayClientError;
+
+    async fn request<A, R>(&self, method: &str, params: A) -> Result<R, Self::Error>
+    where
+        A: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(&params)?;
+        let response = {
+            let mut requests = self.requests.lock().unwrap();
+            let no_match = || ReplayClientError::NoMatchingRequest {
+                method: method.to_string(),
+                params: params.clone(),
+            };
+            let mut unserved = requests.iter_mut().filter(|req| !req.served);
+            let request = if self.strict {
+                let request = unserved.next().ok_or_else(no_match)?;
+                if request.method != method || request.params != params {
+                    return Err(ReplayClientError::UnexpectedRequest {
+                        method: method.to_string(),
+                        params,
+                        expected: request.method.clone(),
+                    })
+                }
+                request
+            } else {
+                unserved
+                    .find(|req| req.method == method && req.params == params)
+                    .ok_or_else(no_match)?
+            };
+            request.served = true;
+            request.response.clone()
+        };
+
+        match response {
+            RecordedResponse::Result(value) => Ok(serde_json::from_value(value)?),
+            RecordedResponse::Error(err) => Err(ReplayClientError::JsonRpcError(err)),
+        }
+    }
+}
+
+impl PubsubClient for ReplayClient {
+    type NotificationStream = stream::Iter<std::vec::IntoIter<Box<RawValue>>>;
+
+    fn subscribe<I: Into<U256>>(&self, id: I) -> Result<Self::NotificationStream, Self::Error> {
+        let notifications =
+            self.notifications.lock().unwrap().remove(&id.into()).unwrap_or_default();
+        let notifications = notifications
+            .iter()
+            .map(serde_json::value::to_raw_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(stream::iter(notifications))
+    }
+
+    fn unsubscribe<I: Into<U256>>(&self, _id: I) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use crate::{Middleware, MockProvider, Provider};
+    use ethers_core::types::{Address, H256, U64};
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn records_and_replays_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixture.jsonl");
+
+        let mock = MockProvider::new();
+        let provider = Provider::new(RecordingClient::new(mock.clone(), &path).unwrap());
+        mock.push(U256::from(100)).unwrap();
+        mock.push(U64::from(2)).unwrap();
+        mock.push(U64::from(1)).unwrap();
+        assert_eq!(provider.get_block_number().await.unwrap(), 1.into());
+        assert_eq!(provider.get_block_number().await.unwrap(), 2.into());
+        assert_eq!(provider.get_balance(Address::zero(), None).await.unwrap(), 100.into());
+        drop(provider);
+
+        // requests are matched by method and params, and repeated requests get successive
+        // responses
+        let provider = Provider::new(ReplayClient::open(&path).unwrap());
+        assert_eq!(provider.get_balance(Address::zero(), None).await.unwrap(), 100.into());
+        assert_eq!(provider.get_block_number().await.unwrap(), 1.into());
+        assert_eq!(provider.get_block_number().await.unwrap(), 2.into());
+        assert_eq!(provider.as_ref().remaining(), 0);
+        provider.get_block_number().await.unwrap_err();
+        provider.get_balance(Address::random(), None).await.unwrap_err();
+
+        // in strict mode, the requests must be sent in the recorded order
+        let provider = Provider::new(ReplayClient::open(&path).unwrap().strict_ordering(true));
+        let err = provider.get_balance(Address::zero(), None).await.unwrap_err();
+        assert!(err.to_string().contains("expected eth_blockNumber"), "{err}");
+        assert_eq!(provider.get_block_number().await.unwrap(), 1.into());
+    }
+
+    #[tokio::test]
+    async fn replays_errors_and_subscriptions() {
+        let hash = H256::repeat_byte(1);
+        let fixture = format!(
+            r#"{{"type":"request","method":"eth_subscribe","params":["newPendingTransactions"],"response":{{"result":"0x1"}}}}
+{{"type":"request","method":"eth_call","params":[{{"to":"0x0000000000000000000000000000000000000000","type":"0x00"}},"latest"],"response":{{"error":{{"code":3,"message":"execution reverted","data":"0x"}}}}}}
+{{"type":"notification","subscription":"0x1","notification":"{hash:?}"}}"#
+        );
+
+        // subscriptions of a pubsub client are recorded as they are polled
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixture.jsonl");
+        let client = RecordingClient::new(ReplayClient::parse(&fixture).unwrap(), &path).unwrap();
+        let provider = Provider::new(client);
+
+        let txs: Vec<H256> = provider.subscribe_pending_txs().await.unwrap().collect().await;
+        assert_eq!(txs, vec![hash]);
+        let call = ethers_core::types::TransactionRequest::new().to(Address::zero()).into();
+        let err = provider.call(&call, None).await.unwrap_err();
+        assert_eq!(err.as_error_response().unwrap().code, 3);
+        drop(provider);
+
+        let provider = Provider::new(ReplayClient::open(&path).unwrap().strict_ordering(true));
+        let txs: Vec<H256> = provider.subscribe_pending_txs().await.unwrap().collect().await;
+        assert_eq!(txs, vec![hash]);
+        let err = provider.call(&call, None).await.unwrap_err();
+        assert!(err.as_error_response().unwrap().is_revert());
+    }
+}
@@ -2,11 +2,12 @@
 //! with an exponential backoff.
 
 use super::{common::JsonRpcError, http::ClientError};
-use crate::{errors::ProviderError, JsonRpcClient};
+use crate::{errors::ProviderError, BatchCall, BatchResult, JsonRpcClient};
 use async_trait::async_trait;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     fmt::Debug,
+    future::Future,
     sync::atomic::{AtomicU32, Ordering},
     time::Duration,
 };
@@ -246,35 +247,18 @@ impl From<RetryClientError> for ProviderError {
     }
 }
 
-#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
-#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
-impl<T> JsonRpcClient for RetryClient<T>
+impl<T> RetryClient<T>
 where
     T: JsonRpcClient + 'static,
     T::Error: Sync + Send + 'static,
 {
-    type Error = RetryClientError;
-
-    async fn request<A, R>(&self, method: &str, params: A) -> Result<R, Self::Error>
+    /// Sends the request built by `send` until it succeeds, retrying the errors accepted by the
+    /// policy and spurious connectivity errors with a backoff
+    async fn retry<F, Fut, R>(&self, mut send: F) -> Result<R, RetryClientError>
     where
-        A: Debug + Serialize + Send + Sync,
-        R: DeserializeOwned + Send,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<R, T::Error>>,
     {
-        // Helper type that caches the `params` value across several retries
-        // This is necessary because the wrapper provider is supposed to skip he `params` if it's of
-        // size 0, see `crate::transports::common::Request`
-        enum RetryParams<Params> {
-            Value(Params),
-            Zst(()),
-        }
-
-        let params = if std::mem::size_of::<A>() == 0 {
-            RetryParams::Zst(())
-        } else {
-            let params = serde_json::to_value(params).map_err(RetryClientError::SerdeJson)?;
-            RetryParams::Value(params)
-        };
-
         let ahead_in_queue = self.requests_enqueued.fetch_add(1, Ordering::SeqCst) as u64;
 
         let mut rate_limit_retry_number: u32 = 0;
@@ -286,11 +270,7 @@ where
             // hack to not hold `R` across an await in the sleep future and prevent requiring
             // R: Send + Sync
             {
-                let resp = match params {
-                    RetryParams::Value(ref params) => self.inner.request(method, params).await,
-                    RetryParams::Zst(unit) => self.inner.request(method, unit).await,
-                };
-                match resp {
+                match send().await {
                     Ok(ret) => {
                         self.requests_enqueued.fetch_sub(1, Ordering::SeqCst);
                         return Ok(ret)
@@ -355,6 +335,51 @@ where
     }
 }
 
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T> JsonRpcClient for RetryClient<T>
+where
+    T: JsonRpcClient + 'static,
+    T::Error: Sync + Send + 'static,
+{
+    type Error = RetryClientError;
+
+    async fn request<A, R>(&self, method: &str, params: A) -> Result<R, Self::Error>
+    where
+        A: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        // Helper type that caches the `params` value across several retries
+        // This is necessary because the wrapper provider is supposed to skip he `params` if it's of
+        // size 0, see `crate::transports::common::Request`
+        enum RetryParams<Params> {
+            Value(Params),
+            Zst(()),
+        }
+
+        let params = if std::mem::size_of::<A>() == 0 {
+            RetryParams::Zst(())
+        } else {
+            let params = serde_json::to_value(params).map_err(RetryClientError::SerdeJson)?;
+            RetryParams::Value(params)
+        };
+
+        let params = &params;
+        self.retry(|| async move {
+            match params {
+                RetryParams::Value(params) => self.inner.request(method, params).await,
+                RetryParams::Zst(unit) => self.inner.request(method, unit).await,
+            }
+        })
+        .await
+    }
+
+    /// Sends the batch with the inner client, retrying the whole batch if it fails
+    async fn batch_request(&self, calls: Vec<BatchCall>) -> Result<Vec<BatchResult>, Self::Error> {
+        self.retry(|| self.inner.batch_request(calls.clone())).await
+    }
+}
+
 /// Implements [RetryPolicy] that will retry requests that errored with
 /// status code 429 i.e. TOO_MANY_REQUESTS
 ///
@@ -484,6 +509,8 @@ fn maybe_connectivity(err: &ProviderError) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ethers_core::types::U64;
+
     // assumed average cost of a request
     const AVG_COST: u64 = 17u64;
     const COMPUTE_UNITS: u64 = 330u64;
@@ -577,4 +604,68 @@ mod tests {
         let should_retry = HttpRateLimitRetryPolicy::default().should_retry(&err);
         assert!(should_retry);
     }
+
+    /// Fails the first `failures` requests, then forwards to the mock
+    #[derive(Debug)]
+    struct FlakyClient {
+        mock: crate::MockProvider,
+        failures: AtomicU32,
+    }
+
+    #[async_trait]
+    impl JsonRpcClient for FlakyClient {
+        type Error = crate::MockError;
+
+        async fn request<A, R>(&self, method: &str, params: A) -> Result<R, Self::Error>
+        where
+            A: Debug + Serialize + Send + Sync,
+            R: DeserializeOwned + Send,
+        {
+            let failed = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if failed {
+                return Err(crate::MockError::EmptyResponses)
+            }
+            self.mock.request(method, params).await
+        }
+    }
+
+    #[derive(Debug)]
+    struct RetryAll;
+
+    impl RetryPolicy<crate::MockError> for RetryAll {
+        fn should_retry(&self, _error: &crate::MockError) -> bool {
+            true
+        }
+
+        fn backoff_hint(&self, _error: &crate::MockError) -> Option<Duration> {
+            Some(Duration::ZERO)
+        }
+    }
+
+    #[tokio::test]
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn retries_the_whole_batch() {
+        let mock = crate::MockProvider::new();
+        mock.on::<U64, _>("eth_blockNumber", U64::from(7)).unwrap();
+        mock.on::<U64, _>("eth_chainId", U64::from(1)).unwrap();
+        let flaky = FlakyClient { mock: mock.clone(), failures: AtomicU32::new(1) };
+        let client = RetryClientBuilder::default().build(flaky, Box::new(RetryAll));
+
+        let calls = vec![
+            BatchCall::new("eth_blockNumber", ()).unwrap(),
+            BatchCall::new("eth_chainId", ()).unwrap(),
+        ];
+        let results = client.batch_request(calls).await.unwrap();
+        let results: Vec<U64> = results
+            .iter()
+            .map(|res| serde_json::from_str(res.as_ref().unwrap().get()).unwrap())
+            .collect();
+        assert_eq!(results, vec![U64::from(7), U64::from(1)]);
+
+        // one call failed on the first attempt, then both calls were sent again
+        assert_eq!(mock.calls("eth_blockNumber") + mock.calls("eth_chainId"), 3);
+    }
 }
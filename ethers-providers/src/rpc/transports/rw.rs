@@ -1,7 +1,7 @@
 //! A [JsonRpcClient] implementation that serves as a wrapper around two different [JsonRpcClient]
 //! and uses a dedicated client for read and the other for write operations
 
-use crate::{errors::ProviderError, JsonRpcClient};
+use crate::{errors::ProviderError, BatchCall, BatchResult, JsonRpcClient};
 use async_trait::async_trait;
 use serde::{de::DeserializeOwned, Serialize};
 use thiserror::Error;
@@ -114,6 +114,11 @@ where
     }
 }
 
+/// Returns whether the method is sent with the _write_ client
+fn is_write(method: &str) -> bool {
+    matches!(method, "eth_sendTransaction" | "eth_sendRawTransaction")
+}
+
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 impl<Read, Write> JsonRpcClient for RwClient<Read, Write>
@@ -132,11 +137,38 @@ where
         T: std::fmt::Debug + Serialize + Send + Sync,
         R: DeserializeOwned + Send,
     {
-        match method {
-            "eth_sendTransaction" | "eth_sendRawTransaction" => {
-                self.w.request(method, params).await.map_err(RwClientError::Write)
-            }
-            _ => self.r.request(method, params).await.map_err(RwClientError::Read),
+        if is_write(method) {
+            self.w.request(method, params).await.map_err(RwClientError::Write)
+        } else {
+            self.r.request(method, params).await.map_err(RwClientError::Read)
+        }
+    }
+
+    /// Sends the read calls as one batch with the _read_ client, and the write calls as another
+    /// batch with the _write_ client
+    async fn batch_request(&self, calls: Vec<BatchCall>) -> Result<Vec<BatchResult>, Self::Error> {
+        if !calls.iter().any(|call| is_write(&call.method)) {
+            return self.r.batch_request(calls).await.map_err(RwClientError::Read)
         }
+
+        let (writes, reads): (Vec<_>, Vec<_>) =
+            calls.into_iter().enumerate().partition(|(_, call)| is_write(&call.method));
+        let (write_idx, writes): (Vec<_>, Vec<_>) = writes.into_iter().unzip();
+        let (read_idx, reads): (Vec<_>, Vec<_>) = reads.into_iter().unzip();
+
+        let reads = async {
+            if reads.is_empty() {
+                return Ok(Vec::new())
+            }
+            self.r.batch_request(reads).await.map_err(RwClientError::Read)
+        };
+        let writes = async { self.w.batch_request(writes).await.map_err(RwClientError::Write) };
+        let (reads, writes) = futures_util::future::try_join(reads, writes).await?;
+
+        // restore the order of the calls
+        let mut results: Vec<_> = read_idx.into_iter().zip(reads).collect();
+        results.extend(write_idx.into_iter().zip(writes));
+        results.sort_by_key(|(idx, _)| *idx);
+        Ok(results.into_iter().map(|(_, result)| result).collect())
     }
 }
@@ -0,0 +1,270 @@
+use super::{ReconnectEvent, WsClient};
+use crate::{utils::PinBoxFut, Middleware, Provider, ProviderError, SubscriptionStream};
+use ethers_core::types::{Block, BlockNumber, Filter, Log, TxHash, H256, U256, U64};
+use futures_channel::mpsc;
+use futures_core::stream::Stream;
+use futures_util::{future::try_join_all, StreamExt};
+use pin_project::pin_project;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    pin::Pin,
+    task::{Context, Poll},
+    vec::IntoIter,
+};
+use tracing::error;
+
+/// An item yielded by a [`BackfillStream`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SubscriptionEvent<R> {
+    /// An item notified by the subscription
+    Notification(R),
+    /// The client reconnected to the server. The items missed while the
+    /// connection was down follow as [`SubscriptionEvent::Backfilled`]
+    Reconnected(ReconnectEvent),
+    /// An item missed while the connection was down, fetched after
+    /// reconnecting
+    Backfilled(R),
+}
+
+/// Subscription items which can be fetched again after a reconnection.
+pub trait Backfill: Serialize + DeserializeOwned + Debug + Send + Sync {
+    /// Returns the number of the block containing the item and the index of
+    /// the item in that block, or `None` if the item is pending
+    fn position(&self) -> Option<(U64, U256)>;
+
+    /// Returns the hash of the block containing the item, the index of the
+    /// item in that block and whether the item was removed by a reorg, or
+    /// `None` if the item is pending
+    fn key(&self) -> Option<(H256, U256, bool)>;
+}
+
+impl Backfill for Block<TxHash> {
+    fn position(&self) -> Option<(U64, U256)> {
+        self.number.map(|number| (number, U256::zero()))
+    }
+
+    fn key(&self) -> Option<(H256, U256, bool)> {
+        self.hash.map(|hash| (hash, U256::zero(), false))
+    }
+}
+
+impl Backfill for Log {
+    fn position(&self) -> Option<(U64, U256)> {
+        Some((self.block_number?, self.log_index?))
+    }
+
+    fn key(&self) -> Option<(H256, U256, bool)> {
+        Some((self.block_hash?, self.log_index?, self.removed.unwrap_or_default()))
+    }
+}
+
+enum BackfillState<'a, R> {
+    Live,
+    GetBlockNumber(PinBoxFut<'a, U64>),
+    Backfill(PinBoxFut<'a, Vec<R>>),
+    NextItem(IntoIter<R>),
+}
+
+/// A subscription which recovers the items missed while the [`WsClient`]
+/// was reconnecting.
+///
+/// Subscriptions are transparently re-issued by the client after a
+/// reconnection, but the server does not replay the notifications sent while
+/// the connection was down. After each reconnection, this stream yields a
+/// [`SubscriptionEvent::Reconnected`] event, followed by the blocks or logs
+/// produced since the last yielded item, which are fetched with
+/// `eth_getBlockByNumber` or with `eth_getLogs` using the original filter.
+/// Backfilled items which were already yielded before the disconnection are
+/// skipped, as are notifications of items which were already backfilled.
+/// Any other notification is yielded as is, including the blocks of a reorg
+/// and the logs it removed.
+///
+/// Created with [`Provider::subscribe_blocks_with_backfill`] and
+/// [`Provider::subscribe_logs_with_backfill`].
+///
+/// ```no_run
+/// use ethers_providers::{Provider, StreamExt, SubscriptionEvent, Ws};
+///
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// let provider = Provider::<Ws>::connect_with_reconnects("ws://localhost:8545", 10).await?;
+/// let mut stream = provider.subscribe_blocks_with_backfill().await?;
+/// while let Some(event) = stream.next().await {
+///     match event {
+///         SubscriptionEvent::Notification(block) | SubscriptionEvent::Backfilled(block) => {
+///             println!("block {:?}", block.number)
+///         }
+///         SubscriptionEvent::Reconnected(_) => println!("reconnected"),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "subscriptions do nothing unless you stream them"]
+#[pin_project]
+pub struct BackfillStream<'a, R: Backfill> {
+    #[pin]
+    stream: SubscriptionStream<'a, WsClient, R>,
+    reconnects: mpsc::UnboundedReceiver<ReconnectEvent>,
+    // The filter of a logs subscription, `None` for blocks
+    filter: Option<Filter>,
+    // First block whose items may not all have been yielded yet
+    next_block: U64,
+    // Position of the last yielded item
+    last: Option<(U64, U256)>,
+    // Items yielded by the last backfill, which the server may notify again
+    backfilled: HashSet<(H256, U256, bool)>,
+    state: BackfillState<'a, R>,
+}
+
+impl<'a, R: Backfill + 'a> BackfillStream<'a, R> {
+    pub(super) fn new(
+        stream: SubscriptionStream<'a, WsClient, R>,
+        reconnects: mpsc::UnboundedReceiver<ReconnectEvent>,
+        filter: Option<Filter>,
+        head: U64,
+    ) -> Self {
+        Self {
+            stream,
+            reconnects,
+            filter,
+            next_block: head + 1,
+            last: None,
+            backfilled: HashSet::new(),
+            state: BackfillState::Live,
+        }
+    }
+
+    /// The subscription's id, which is preserved across reconnections
+    pub fn id(&self) -> U256 {
+        self.stream.id
+    }
+
+    /// Unsubscribes from the subscription.
+    pub async fn unsubscribe(&self) -> Result<bool, ProviderError> {
+        self.stream.unsubscribe().await
+    }
+}
+
+/// Records the position of an item about to be yielded.
+fn advance<R: Backfill>(
+    item: &R,
+    last: &mut Option<(U64, U256)>,
+    next_block: &mut U64,
+    is_logs: bool,
+) {
+    let Some(position) = item.position() else { return };
+    // removed logs are not part of the chain anymore
+    if item.key().map_or(false, |(_, _, removed)| removed) {
+        return
+    }
+    // a notification below the last position follows a reorg, and the items
+    // after it must be fetched again
+    *last = Some(position);
+    // the remaining logs of a block may have been missed
+    *next_block = if is_logs { position.0 } else { position.0 + 1 };
+}
+
+/// Fetches the items of blocks `from..=to`
+fn backfill<'a, R: Backfill + 'a>(
+    provider: &'a Provider<WsClient>,
+    filter: Option<&Filter>,
+    from: U64,
+    to: U64,
+) -> PinBoxFut<'a, Vec<R>> {
+    match filter {
+        Some(filter) => {
+            let filter = filter.clone().from_block(from).to_block(to);
+            Box::pin(async move { provider.request("eth_getLogs", [filter]).await })
+        }
+        None => Box::pin(async move {
+            let blocks = try_join_all((from.as_u64()..=to.as_u64()).map(|number| {
+                let params = (BlockNumber::Number(number.into()), false);
+                provider.request::<_, Option<R>>("eth_getBlockByNumber", params)
+            }))
+            .await?;
+            Ok(blocks.into_iter().flatten().collect())
+        }),
+    }
+}
+
+impl<'a, R: Backfill + 'a> Stream for BackfillStream<'a, R> {
+    type Item = SubscriptionEvent<R>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        let provider = this.stream.provider;
+
+        loop {
+            *this.state = match &mut this.state {
+                BackfillState::Live => {
+                    if let Poll::Ready(Some(event)) = this.reconnects.poll_next_unpin(cx) {
+                        *this.state =
+                            BackfillState::GetBlockNumber(Box::pin(provider.get_block_number()));
+                        return Poll::Ready(Some(SubscriptionEvent::Reconnected(event)))
+                    }
+                    match futures_util::ready!(this.stream.as_mut().poll_next(cx)) {
+                        Some(item) => {
+                            if item.key().map_or(false, |key| this.backfilled.contains(&key)) {
+                                continue
+                            }
+                            advance(&item, this.last, this.next_block, this.filter.is_some());
+                            return Poll::Ready(Some(SubscriptionEvent::Notification(item)))
+                        }
+                        None => return Poll::Ready(None),
+                    }
+                }
+                BackfillState::GetBlockNumber(fut) => {
+                    match futures_util::ready!(fut.as_mut().poll(cx)) {
+                        Ok(head) => {
+                            // a logs subscription may end at a given block
+                            let to = match this.filter.as_ref().and_then(Filter::get_to_block) {
+                                Some(to_block) => head.min(to_block),
+                                None => head,
+                            };
+                            // logs at a given block hash are never missed
+                            let at_block_hash =
+                                this.filter.as_ref().and_then(Filter::get_block_hash).is_some();
+                            if *this.next_block > to || at_block_hash {
+                                BackfillState::Live
+                            } else {
+                                let fut =
+                                    backfill(provider, this.filter.as_ref(), *this.next_block, to);
+                                BackfillState::Backfill(fut)
+                            }
+                        }
+                        Err(err) => {
+                            error!("failed to get the block number after reconnecting: {err}");
+                            BackfillState::Live
+                        }
+                    }
+                }
+                BackfillState::Backfill(fut) => match futures_util::ready!(fut.as_mut().poll(cx)) {
+                    Ok(items) => {
+                        this.backfilled.clear();
+                        BackfillState::NextItem(items.into_iter())
+                    }
+                    Err(err) => {
+                        error!("failed to backfill the subscription: {err}");
+                        BackfillState::Live
+                    }
+                },
+                BackfillState::NextItem(iter) => match iter.next() {
+                    Some(item) => {
+                        let (Some(position), Some(key)) = (item.position(), item.key()) else {
+                            continue
+                        };
+                        if this.last.map_or(false, |last| position <= last) {
+                            continue
+                        }
+                        this.backfilled.insert(key);
+                        advance(&item, this.last, this.next_block, this.filter.is_some());
+                        return Poll::Ready(Some(SubscriptionEvent::Backfilled(item)))
+                    }
+                    None => BackfillState::Live,
+                },
+            };
+        }
+    }
+}
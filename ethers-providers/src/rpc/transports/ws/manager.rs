@@ -1,7 +1,7 @@
 use super::{
     backend::{BackendDriver, WsBackend},
-    ActiveSub, ConnectionDetails, InFlight, Instruction, Notification, PubSubItem, Response, SubId,
-    WsClient, WsClientError,
+    ActiveSub, ConnectionDetails, InFlight, Instruction, Notification, PubSubItem, ReconnectEvent,
+    Response, SubId, WsClient, WsClientError,
 };
 use crate::JsonRpcError;
 use ethers_core::types::U256;
@@ -198,6 +198,8 @@ pub struct RequestManager {
     conn: ConnectionDetails,
     // Instructions from the user-facing providers
     instructions: mpsc::UnboundedReceiver<Instruction>,
+    // Listeners notified after each reconnection
+    reconnect_listeners: Vec<mpsc::UnboundedSender<ReconnectEvent>>,
 }
 
 impl RequestManager {
@@ -229,6 +231,7 @@ impl RequestManager {
                 backend,
                 conn,
                 instructions: instructions_rx,
+                reconnect_listeners: Default::default(),
             },
             WsClient { instructions: instructions_tx, channel_map },
         ))
@@ -262,6 +265,11 @@ impl RequestManager {
         // issue a shutdown command (even though it's likely gone)
         old_backend.shutdown();
 
+        // notify the listeners before any notification of the new backend can
+        // be forwarded, so that they know which items may have been missed
+        let event = ReconnectEvent { remaining_reconnects: self.reconnects };
+        self.reconnect_listeners.retain(|listener| listener.unbounded_send(event).is_ok());
+
         tracing::debug!(count = self.subs.count(), "Re-starting active subscriptions");
 
         // reissue subscriptionps
@@ -356,6 +364,7 @@ impl RequestManager {
                         .map_err(|_| WsClientError::DeadChannel)?;
                 }
             }
+            Instruction::ListenReconnects { sender } => self.reconnect_listeners.push(sender),
         }
         Ok(())
     }
@@ -8,15 +8,18 @@ use manager::{RequestManager, SharedChannelMap};
 use std::fmt;
 
 mod types;
-pub use types::ConnectionDetails;
 pub(self) use types::*;
+pub use types::{ConnectionDetails, ReconnectEvent};
 
 mod error;
 pub use error::*;
 
-use crate::{JsonRpcClient, ProviderError, PubsubClient};
+mod backfill;
+pub use backfill::{Backfill, BackfillStream, SubscriptionEvent};
+
+use crate::{JsonRpcClient, Middleware, ProviderError, PubsubClient};
 use async_trait::async_trait;
-use ethers_core::types::U256;
+use ethers_core::types::{Block, Filter, Log, TxHash, U256};
 use futures_channel::{mpsc, oneshot};
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::value::RawValue;
@@ -50,6 +53,21 @@ impl WsClient {
         Ok(this)
     }
 
+    /// Returns a stream of the events emitted each time the client reconnects
+    /// to the server, before it re-subscribes to the active subscriptions, so
+    /// that no notification of the new connection precedes the event. The
+    /// stream ends once the client gives up reconnecting.
+    ///
+    /// Notifications sent by the server while the connection was down are
+    /// lost. See [`BackfillStream`] for subscriptions which recover them.
+    pub fn reconnects(&self) -> mpsc::UnboundedReceiver<ReconnectEvent> {
+        let (tx, rx) = mpsc::unbounded();
+        // if the request manager is gone, the sender is dropped and the stream
+        // ends right away
+        let _ = self.instructions.unbounded_send(Instruction::ListenReconnects { sender: tx });
+        rx
+    }
+
     #[tracing::instrument(skip(self, params), err)]
     async fn make_request<R>(&self, method: &str, params: Box<RawValue>) -> Result<R, WsClientError>
     where
@@ -176,4 +194,28 @@ impl crate::Provider<WsClient> {
         let ws = crate::Ws::connect_with_reconnects(conn, reconnects).await?;
         Ok(Self::new(ws))
     }
+
+    /// Subscribes to new blocks, and fetches the blocks produced while the
+    /// client was reconnecting. See [`BackfillStream`].
+    pub async fn subscribe_blocks_with_backfill(
+        &self,
+    ) -> Result<BackfillStream<'_, Block<TxHash>>, ProviderError> {
+        // listen before subscribing so that no reconnection is missed
+        let reconnects = self.as_ref().reconnects();
+        let stream = self.subscribe_blocks().await?;
+        let head = self.get_block_number().await?;
+        Ok(BackfillStream::new(stream, reconnects, None, head))
+    }
+
+    /// Subscribes to logs matching the filter, and fetches the logs emitted
+    /// while the client was reconnecting. See [`BackfillStream`].
+    pub async fn subscribe_logs_with_backfill(
+        &self,
+        filter: &Filter,
+    ) -> Result<BackfillStream<'_, Log>, ProviderError> {
+        let reconnects = self.as_ref().reconnects();
+        let stream = self.subscribe_logs(filter).await?;
+        let head = self.get_block_number().await?;
+        Ok(BackfillStream::new(stream, reconnects, Some(filter.clone()), head))
+    }
 }
@@ -236,12 +236,22 @@ impl ActiveSub {
     }
 }
 
+/// Emitted by the `RequestManager` once it has reconnected to the backend,
+/// before it re-issues the active subscriptions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReconnectEvent {
+    /// Number of reconnections left before the client gives up
+    pub remaining_reconnects: usize,
+}
+
 /// Instructions for the `WsServer`.
 pub enum Instruction {
     /// JSON-RPC request
     Request { method: String, params: Box<RawValue>, sender: oneshot::Sender<Response> },
     /// Cancel an existing subscription
     Unsubscribe { id: U256 },
+    /// Register a listener for reconnection events
+    ListenReconnects { sender: mpsc::UnboundedSender<ReconnectEvent> },
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -3,3 +3,5 @@ pub use tx_stream::*;
 
 pub mod watcher;
 pub use watcher::*;
+
+pub mod reorg;
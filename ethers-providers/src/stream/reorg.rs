@@ -0,0 +1,509 @@
+use crate::{JsonRpcClient, Middleware, Provider, ProviderError};
+use ethers_core::types::{Block, Filter, Log, TxHash, H256, U64};
+use futures_core::stream::Stream;
+use futures_util::StreamExt;
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tracing::error;
+
+/// The default number of recent blocks tracked by a [`ReorgStream`]
+pub const DEFAULT_REORG_WINDOW: usize = 128;
+
+/// A change of the canonical chain, emitted by a [`ReorgStream`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReorgEvent {
+    /// A block was added to the canonical chain
+    Added {
+        /// The added block
+        block: Block<TxHash>,
+        /// The logs of the block matching the stream's filter
+        logs: Vec<Log>,
+    },
+    /// A previously added block was removed from the canonical chain by a reorg
+    Removed {
+        /// The removed block
+        block: Block<TxHash>,
+        /// The logs of the block matching the stream's filter, marked as removed
+        logs: Vec<Log>,
+    },
+}
+
+impl ReorgEvent {
+    /// Returns the block which was added or removed
+    pub fn block(&self) -> &Block<TxHash> {
+        match self {
+            ReorgEvent::Added { block, .. } | ReorgEvent::Removed { block, .. } => block,
+        }
+    }
+
+    /// Returns the logs of the block which was added or removed
+    pub fn logs(&self) -> &[Log] {
+        match self {
+            ReorgEvent::Added { logs, .. } | ReorgEvent::Removed { logs, .. } => logs,
+        }
+    }
+
+    /// Returns true if the block was removed from the canonical chain
+    pub fn is_removed(&self) -> bool {
+        matches!(self, ReorgEvent::Removed { .. })
+    }
+}
+
+struct TrackedBlock {
+    block: Block<TxHash>,
+    // `Some` once the block has been emitted as added
+    logs: Option<Vec<Log>>,
+}
+
+impl TrackedBlock {
+    fn hash(&self) -> H256 {
+        self.block.hash.unwrap_or_default()
+    }
+
+    fn number(&self) -> U64 {
+        self.block.number.unwrap_or_default()
+    }
+}
+
+/// Tracks a window of the most recent canonical blocks
+struct ChainTracker {
+    // canonical blocks, oldest first
+    blocks: VecDeque<TrackedBlock>,
+    window: usize,
+    confirmations: usize,
+    // events which have not been yielded yet
+    pending: VecDeque<ReorgEvent>,
+}
+
+impl ChainTracker {
+    fn new() -> Self {
+        Self {
+            blocks: VecDeque::new(),
+            window: DEFAULT_REORG_WINDOW,
+            confirmations: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn position(&self, hash: H256) -> Option<usize> {
+        self.blocks.iter().position(|tracked| tracked.hash() == hash)
+    }
+
+    /// Returns the parent of the block if it must be fetched to link the block
+    /// to the tracked chain
+    fn missing_parent(&self, block: &Block<TxHash>) -> Option<H256> {
+        let oldest = self.blocks.front()?.number();
+        let hash = block.hash.unwrap_or_default();
+        let number = block.number.unwrap_or_default();
+        let is_linked = self.position(hash).is_some() || self.position(block.parent_hash).is_some();
+        (!is_linked && number > oldest).then_some(block.parent_hash)
+    }
+
+    /// Inserts a chain segment, whose blocks are ordered by number, and
+    /// removes the blocks it replaces
+    fn insert(&mut self, segment: Vec<Block<TxHash>>) {
+        let mut segment = segment.into_iter().peekable();
+        // skip the blocks which are already tracked
+        while let Some(block) = segment.peek() {
+            if self.position(block.hash.unwrap_or_default()).is_none() {
+                break
+            }
+            segment.next();
+        }
+        let Some(first) = segment.peek() else { return };
+
+        // if the segment does not link to a tracked block, the reorg is deeper
+        // than the window, and all the tracked blocks are replaced
+        let keep = self.position(first.parent_hash).map_or(0, |parent| parent + 1);
+        let removed = self.blocks.drain(keep..).collect::<Vec<_>>();
+        for tracked in removed.into_iter().rev() {
+            if let Some(mut logs) = tracked.logs {
+                logs.iter_mut().for_each(|log| log.removed = Some(true));
+                self.pending.push_back(ReorgEvent::Removed { block: tracked.block, logs });
+            }
+        }
+
+        self.blocks.extend(segment.map(|block| TrackedBlock { block, logs: None }));
+    }
+
+    /// Returns the blocks which have enough confirmations to be emitted
+    fn confirmable(&self) -> Vec<Block<TxHash>> {
+        let Some(tip) = self.blocks.back().map(TrackedBlock::number) else { return vec![] };
+        self.blocks
+            .iter()
+            .filter(|tracked| {
+                tracked.logs.is_none() && tracked.number() + self.confirmations as u64 <= tip
+            })
+            .map(|tracked| tracked.block.clone())
+            .collect()
+    }
+
+    /// Marks the block as added, and trims the blocks falling out of the window
+    fn confirm(&mut self, hash: H256, logs: Vec<Log>) {
+        let Some(index) = self.position(hash) else { return };
+        let tracked = &mut self.blocks[index];
+        tracked.logs = Some(logs.clone());
+        self.pending.push_back(ReorgEvent::Added { block: tracked.block.clone(), logs });
+
+        while self.blocks.len() > self.window &&
+            self.blocks.front().map_or(false, |tracked| tracked.logs.is_some())
+        {
+            self.blocks.pop_front();
+        }
+    }
+
+    /// Links the new head to the tracked chain and queues the resulting events
+    async fn handle_head<P: JsonRpcClient>(
+        &mut self,
+        provider: &Provider<P>,
+        filter: Option<&Filter>,
+        head: Block<TxHash>,
+    ) -> Result<(), ProviderError> {
+        let mut segment = VecDeque::from([head]);
+        // the walk only stops unlinked once it goes below the oldest tracked
+        // block, i.e. for reorgs deeper than the window
+        while let Some(parent) = self.missing_parent(&segment[0]) {
+            // the node may not serve the parent yet, inserting the segment
+            // unlinked would remove all the tracked blocks. The head is handled
+            // again as the parent of the next head.
+            let block = provider.get_block(parent).await?.ok_or_else(|| {
+                ProviderError::CustomError(format!("parent block {parent:?} not found"))
+            })?;
+            segment.push_front(block);
+        }
+        self.insert(segment.into());
+
+        for block in self.confirmable() {
+            let hash = block.hash.unwrap_or_default();
+            let logs = match filter {
+                Some(filter) => provider.get_logs(&filter.clone().at_block_hash(hash)).await?,
+                None => vec![],
+            };
+            self.confirm(hash, logs);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+type HandleHeadFut<'a> =
+    Pin<Box<dyn Future<Output = (ChainTracker, Result<(), ProviderError>)> + 'a>>;
+#[cfg(not(target_arch = "wasm32"))]
+type HandleHeadFut<'a> =
+    Pin<Box<dyn Future<Output = (ChainTracker, Result<(), ProviderError>)> + Send + 'a>>;
+
+enum ReorgStreamState<'a> {
+    NextHead(ChainTracker),
+    HandleHead(HandleHeadFut<'a>),
+    NextEvent(ChainTracker),
+}
+
+/// Turns a stream of new block heads into a stream of changes of the
+/// canonical chain, by tracking a window of recent blocks.
+///
+/// Reorgs are detected when the parent hash of a new head does not match the
+/// current tip. The stream then fetches the ancestors of the new head until it
+/// finds the common ancestor, and emits a [`ReorgEvent::Removed`] event for
+/// each replaced block, newest first, followed by a [`ReorgEvent::Added`]
+/// event for each new block, oldest first. Missing blocks are fetched as well,
+/// so that every block is emitted.
+///
+/// Blocks are only added once they have the configured number of
+/// confirmations, so that shallower reorgs are never emitted. If a filter is
+/// set, the matching logs of each block are fetched by block hash and attached
+/// to its events.
+///
+/// ```no_run
+/// use ethers_core::types::Filter;
+/// use ethers_providers::{Middleware, Provider, ReorgEvent, ReorgStream, StreamExt, Ws};
+///
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// let provider = Provider::<Ws>::connect("ws://localhost:8545").await?;
+/// let heads = provider.subscribe_blocks().await?;
+/// let filter = Filter::new().event("Transfer(address,address,uint256)");
+/// let mut stream = ReorgStream::new(&provider, heads).filter(filter).confirmations(2);
+/// while let Some(event) = stream.next().await {
+///     match event {
+///         ReorgEvent::Added { block, logs } => println!("{:?}: {} logs", block.hash, logs.len()),
+///         ReorgEvent::Removed { block, .. } => println!("{:?} was reorged", block.hash),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "streams do nothing unless polled"]
+pub struct ReorgStream<'a, P, S> {
+    provider: &'a Provider<P>,
+    heads: S,
+    filter: Option<Filter>,
+    state: Option<ReorgStreamState<'a>>,
+}
+
+impl<'a, P, S> ReorgStream<'a, P, S>
+where
+    P: JsonRpcClient,
+    S: Stream<Item = Block<TxHash>> + Unpin,
+{
+    /// Creates a new stream tracking the canonical chain from a stream of new
+    /// heads, such as [`Middleware::subscribe_blocks`].
+    pub fn new(provider: &'a Provider<P>, heads: S) -> Self {
+        Self {
+            provider,
+            heads,
+            filter: None,
+            state: Some(ReorgStreamState::NextHead(ChainTracker::new())),
+        }
+    }
+
+    /// Sets the filter of the logs attached to each block
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Sets the number of blocks which must be built on top of a block before
+    /// it is added. Defaults to 0.
+    pub fn confirmations(mut self, confirmations: usize) -> Self {
+        self.tracker_mut().confirmations = confirmations;
+        self
+    }
+
+    /// Sets the number of emitted blocks which are kept to detect reorgs.
+    /// Reorgs deeper than the window remove all the tracked blocks. Defaults to
+    /// [`DEFAULT_REORG_WINDOW`].
+    pub fn window(mut self, window: usize) -> Self {
+        self.tracker_mut().window = window;
+        self
+    }
+
+    fn tracker_mut(&mut self) -> &mut ChainTracker {
+        match &mut self.state {
+            Some(ReorgStreamState::NextHead(tracker)) => tracker,
+            _ => unreachable!("the stream is configured before it is polled"),
+        }
+    }
+}
+
+impl<'a, P, S> Stream for ReorgStream<'a, P, S>
+where
+    P: JsonRpcClient,
+    S: Stream<Item = Block<TxHash>> + Unpin,
+{
+    type Item = ReorgEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            let state = this.state.take().expect("the state is always set between polls");
+            this.state = Some(match state {
+                ReorgStreamState::NextHead(mut tracker) => {
+                    let head = match this.heads.poll_next_unpin(cx) {
+                        Poll::Ready(Some(head)) => head,
+                        Poll::Ready(None) => {
+                            this.state = Some(ReorgStreamState::NextHead(tracker));
+                            return Poll::Ready(None)
+                        }
+                        Poll::Pending => {
+                            this.state = Some(ReorgStreamState::NextHead(tracker));
+                            return Poll::Pending
+                        }
+                    };
+                    // pending blocks can't be tracked
+                    if head.hash.is_none() || head.number.is_none() {
+                        ReorgStreamState::NextHead(tracker)
+                    } else {
+                        let provider = this.provider;
+                        let filter = this.filter.clone();
+                        ReorgStreamState::HandleHead(Box::pin(async move {
+                            let res = tracker.handle_head(provider, filter.as_ref(), head).await;
+                            (tracker, res)
+                        }))
+                    }
+                }
+                ReorgStreamState::HandleHead(mut fut) => match fut.as_mut().poll(cx) {
+                    Poll::Ready((tracker, res)) => {
+                        // events queued before the error are still emitted, and
+                        // the remaining blocks are handled with the next head
+                        if let Err(err) = res {
+                            error!("failed to handle new head: {err}");
+                        }
+                        ReorgStreamState::NextEvent(tracker)
+                    }
+                    Poll::Pending => {
+                        this.state = Some(ReorgStreamState::HandleHead(fut));
+                        return Poll::Pending
+                    }
+                },
+                ReorgStreamState::NextEvent(mut tracker) => match tracker.pending.pop_front() {
+                    Some(event) => {
+                        this.state = Some(ReorgStreamState::NextEvent(tracker));
+                        return Poll::Ready(Some(event))
+                    }
+                    None => ReorgStreamState::NextHead(tracker),
+                },
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockProvider, Provider};
+    use ethers_core::types::Address;
+
+    fn block(number: u64, fork: u8, parent_fork: u8) -> Block<TxHash> {
+        let hash = |number: u64, fork: u8| {
+            let mut hash = H256::from_low_u64_be(number);
+            hash.0[0] = fork;
+            hash
+        };
+        Block {
+            number: Some(number.into()),
+            hash: Some(hash(number, fork)),
+            parent_hash: if number == 0 { H256::zero() } else { hash(number - 1, parent_fork) },
+            ..Default::default()
+        }
+    }
+
+    fn describe(event: &ReorgEvent) -> (bool, u64, u8) {
+        let block = event.block();
+        (event.is_removed(), block.number.unwrap().as_u64(), block.hash.unwrap().0[0])
+    }
+
+    async fn run(
+        provider: &Provider<MockProvider>,
+        heads: Vec<Block<TxHash>>,
+        confirmations: usize,
+    ) -> Vec<(bool, u64, u8)> {
+        ReorgStream::new(provider, futures_util::stream::iter(heads))
+            .confirmations(confirmations)
+            .map(|event| describe(&event))
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn emits_reorged_blocks() {
+        let (provider, _) = Provider::mocked();
+        let heads = vec![
+            block(0, 0, 0),
+            block(1, 0, 0),
+            block(2, 0, 0),
+            // one block reorg
+            block(2, 1, 0),
+            block(3, 1, 1),
+            // duplicate head
+            block(3, 1, 1),
+        ];
+        let events = run(&provider, heads, 0).await;
+        assert_eq!(
+            events,
+            [
+                (false, 0, 0),
+                (false, 1, 0),
+                (false, 2, 0),
+                (true, 2, 0),
+                (false, 2, 1),
+                (false, 3, 1)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn fetches_missing_ancestors() {
+        let (provider, mock) = Provider::mocked();
+        // the new head forks from block 1, its ancestors are fetched newest first
+        mock.push(block(2, 1, 0)).unwrap();
+        mock.push(block(3, 1, 1)).unwrap();
+        let heads =
+            vec![block(0, 0, 0), block(1, 0, 0), block(2, 0, 0), block(3, 0, 0), block(4, 1, 1)];
+        let events = run(&provider, heads, 0).await;
+        assert_eq!(
+            events,
+            [
+                (false, 0, 0),
+                (false, 1, 0),
+                (false, 2, 0),
+                (false, 3, 0),
+                (true, 3, 0),
+                (true, 2, 0),
+                (false, 2, 1),
+                (false, 3, 1),
+                (false, 4, 1)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_missing_parents_with_the_next_head() {
+        let (provider, mock) = Provider::mocked();
+        // the node does not serve block 3 yet, then serves both blocks 4 and 3
+        mock.push(block(3, 0, 0)).unwrap();
+        mock.push(block(4, 0, 0)).unwrap();
+        mock.push::<Option<Block<TxHash>>, _>(None).unwrap();
+        let heads =
+            vec![block(0, 0, 0), block(1, 0, 0), block(2, 0, 0), block(4, 0, 0), block(5, 0, 0)];
+        let events = run(&provider, heads, 0).await;
+        assert_eq!(
+            events,
+            [
+                (false, 0, 0),
+                (false, 1, 0),
+                (false, 2, 0),
+                (false, 3, 0),
+                (false, 4, 0),
+                (false, 5, 0)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn waits_for_confirmations() {
+        let (provider, _) = Provider::mocked();
+        let heads = vec![
+            block(0, 0, 0),
+            block(1, 0, 0),
+            block(2, 0, 0),
+            // reorgs of unconfirmed blocks are not emitted
+            block(2, 1, 0),
+            block(3, 1, 1),
+        ];
+        let events = run(&provider, heads, 2).await;
+        assert_eq!(events, [(false, 0, 0), (false, 1, 0)]);
+    }
+
+    #[tokio::test]
+    async fn attaches_logs() {
+        let (provider, mock) = Provider::mocked();
+        let log = |block: &Block<TxHash>| Log {
+            address: Address::repeat_byte(1),
+            block_hash: block.hash,
+            block_number: block.number,
+            ..Default::default()
+        };
+        let (first, replaced, new) = (block(0, 0, 0), block(1, 0, 0), block(1, 1, 0));
+        mock.push::<Vec<Log>, _>(vec![log(&new)]).unwrap();
+        mock.push::<Vec<Log>, _>(vec![log(&replaced)]).unwrap();
+        mock.push::<Vec<Log>, _>(vec![log(&first)]).unwrap();
+
+        let filter = Filter::new().address(Address::repeat_byte(1));
+        let heads = futures_util::stream::iter(vec![first, replaced.clone(), new.clone()]);
+        let events: Vec<_> = ReorgStream::new(&provider, heads).filter(filter).collect().await;
+
+        assert_eq!(events.len(), 4);
+        assert_eq!(
+            events[2],
+            ReorgEvent::Removed {
+                block: replaced.clone(),
+                logs: vec![Log { removed: Some(true), ..log(&replaced) }]
+            }
+        );
+        assert_eq!(events[3], ReorgEvent::Added { block: new.clone(), logs: vec![log(&new)] });
+    }
+}
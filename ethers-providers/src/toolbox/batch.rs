@@ -0,0 +1,251 @@
+//! JSON-RPC batch requests
+
+use crate::{BatchCall, BatchResult, JsonRpcClient, Provider, ProviderError};
+use futures_channel::oneshot;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tracing::trace;
+use tracing_futures::Instrument;
+
+/// The default maximum number of calls sent in a single batch.
+///
+/// Most node providers reject batches above a certain size, commonly 100 calls.
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 100;
+
+/// A builder for a JSON-RPC 2.0 batch request, see [`Provider::batch`].
+///
+/// Every call added to the batch returns a [`BatchResponse`] future which resolves to the
+/// typed result of that call once the batch has been [sent](BatchRequest::send).
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// use ethers_core::types::{Address, BlockNumber, TransactionReceipt, H256, U256};
+/// use ethers_providers::{Http, Provider};
+/// use std::convert::TryFrom;
+///
+/// let provider = Provider::<Http>::try_from("http://localhost:8545")?;
+/// let address: Address = "0x6fC21092DA55B392b045eD78F4732bff3C580e2c".parse()?;
+/// let tx_hash = H256::zero();
+///
+/// let mut batch = provider.batch();
+/// let balance = batch.add_call::<_, U256>("eth_getBalance", (address, BlockNumber::Latest))?;
+/// let receipt =
+///     batch.add_call::<_, Option<TransactionReceipt>>("eth_getTransactionReceipt", [tx_hash])?;
+/// batch.send().await?;
+///
+/// let balance = balance.await?;
+/// let receipt = receipt.await?;
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "batch requests do nothing unless sent"]
+pub struct BatchRequest<'a, P> {
+    provider: &'a Provider<P>,
+    calls: Vec<BatchCall>,
+    channels: Vec<oneshot::Sender<BatchResult>>,
+    max_batch_size: usize,
+}
+
+impl<P> fmt::Debug for BatchRequest<'_, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchRequest")
+            .field("calls", &self.calls)
+            .field("max_batch_size", &self.max_batch_size)
+            .finish()
+    }
+}
+
+impl<'a, P: JsonRpcClient> BatchRequest<'a, P> {
+    /// Creates a new, empty batch for the given provider
+    pub fn new(provider: &'a Provider<P>) -> Self {
+        Self {
+            provider,
+            calls: Vec::new(),
+            channels: Vec::new(),
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+        }
+    }
+
+    /// Sets the maximum number of calls sent in a single batch (default: 100).
+    ///
+    /// Larger batches are split into several batch requests when sent. A value of `0` is
+    /// treated as `1`.
+    pub fn max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size.max(1);
+        self
+    }
+
+    /// Adds a call of `method` with the provided `params` to the batch and returns a future
+    /// which resolves to its result after the batch has been sent.
+    ///
+    /// Errors if the params could not be serialized.
+    pub fn add_call<T, R>(
+        &mut self,
+        method: &str,
+        params: T,
+    ) -> Result<BatchResponse<R>, ProviderError>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.calls.push(BatchCall::new(method, params)?);
+        self.channels.push(tx);
+        Ok(BatchResponse { rx, _ret: PhantomData })
+    }
+
+    /// Returns the number of calls in the batch
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// Returns `true` if the batch contains no calls
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Sends the batch, in chunks of at most `max_batch_size` calls, and resolves the
+    /// [`BatchResponse`]s of its calls.
+    ///
+    /// If a chunk fails as a whole, this returns the error and the responses of all calls which
+    /// have not been resolved yet fail.
+    pub async fn send(self) -> Result<(), ProviderError> {
+        let Self { provider, mut calls, mut channels, max_batch_size } = self;
+
+        while !calls.is_empty() {
+            let rest = calls.split_off(calls.len().min(max_batch_size));
+            let chunk = std::mem::replace(&mut calls, rest);
+            let rest = channels.split_off(chunk.len());
+            let chunk_channels = std::mem::replace(&mut channels, rest);
+
+            let span = tracing::trace_span!("rpc_batch", size = chunk.len());
+            let results = async move {
+                trace!("tx");
+                let results = provider.as_ref().batch_request(chunk).await.map_err(Into::into)?;
+                trace!(rx = results.len());
+                Ok::<_, ProviderError>(results)
+            }
+            .instrument(span)
+            .await?;
+
+            for (channel, result) in chunk_channels.into_iter().zip(results) {
+                // the response future may have been dropped, which is fine
+                let _ = channel.send(result);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A future resolving to the result of a single call of a [`BatchRequest`].
+///
+/// Resolves once the batch has been sent. JSON-RPC errors returned for this call are surfaced as
+/// [`ProviderError::JsonRpcClientError`], so they can be inspected via
+/// [`RpcError::as_error_response`](crate::RpcError::as_error_response).
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct BatchResponse<R> {
+    rx: oneshot::Receiver<BatchResult>,
+    _ret: PhantomData<fn() -> R>,
+}
+
+impl<R> fmt::Debug for BatchResponse<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchResponse").finish_non_exhaustive()
+    }
+}
+
+impl<R: DeserializeOwned> Future for BatchResponse<R> {
+    type Output = Result<R, ProviderError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let result = match futures_util::ready!(Pin::new(&mut self.get_mut().rx).poll(cx)) {
+            Ok(Ok(raw)) => serde_json::from_str(raw.get()).map_err(Into::into),
+            Ok(Err(err)) => Err(ProviderError::JsonRpcClientError(Box::new(err))),
+            Err(_) => Err(ProviderError::CustomError(
+                "batch request failed or was dropped before it was sent".to_string(),
+            )),
+        };
+        Poll::Ready(result)
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use crate::{JsonRpcError, RpcError};
+    use ethers_core::types::{Address, BlockNumber, U256, U64};
+
+    #[tokio::test]
+    async fn resolves_batch_responses() {
+        let (provider, mock) = Provider::mocked();
+        let address = Address::random();
+
+        // mock responses are popped from the back
+        mock.push(U256::from(1000)).unwrap();
+        mock.push(U64::from(12)).unwrap();
+
+        let mut batch = provider.batch();
+        let block = batch.add_call::<_, U64>("eth_blockNumber", ()).unwrap();
+        let balance =
+            batch.add_call::<_, U256>("eth_getBalance", (address, BlockNumber::Latest)).unwrap();
+        assert_eq!(batch.len(), 2);
+        batch.send().await.unwrap();
+
+        assert_eq!(block.await.unwrap(), U64::from(12));
+        assert_eq!(balance.await.unwrap(), U256::from(1000));
+
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        mock.assert_request("eth_getBalance", (address, BlockNumber::Latest)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn splits_into_chunks() {
+        let (provider, mock) = Provider::mocked();
+        for i in (0..5u64).rev() {
+            mock.push(U64::from(i)).unwrap();
+        }
+
+        let mut batch = provider.batch().max_batch_size(2);
+        let responses: Vec<BatchResponse<U64>> =
+            (0..5).map(|_| batch.add_call("eth_blockNumber", ()).unwrap()).collect();
+        batch.send().await.unwrap();
+
+        for (i, response) in responses.into_iter().enumerate() {
+            assert_eq!(response.await.unwrap(), U64::from(i));
+        }
+    }
+
+    #[tokio::test]
+    async fn maps_call_errors() {
+        let err =
+            JsonRpcError { code: -32000, message: "header not found".to_string(), data: None };
+        let (tx, rx) = oneshot::channel();
+        let response = BatchResponse::<U64> { rx, _ret: PhantomData };
+        tx.send(Err(err)).unwrap();
+
+        let err = response.await.unwrap_err();
+        let err = err.as_error_response().unwrap();
+        assert_eq!(err.code, -32000);
+        assert_eq!(err.message, "header not found");
+    }
+
+    #[tokio::test]
+    async fn fails_unsent_responses() {
+        let (provider, _mock) = Provider::mocked();
+        let mut batch = provider.batch();
+        let response = batch.add_call::<_, U64>("eth_blockNumber", ()).unwrap();
+        // no response was pushed, so the batch fails as a whole
+        batch.send().await.unwrap_err();
+        response.await.unwrap_err();
+    }
+}
@@ -9,3 +9,6 @@ pub use log_query::{LogQuery, LogQueryError};
 
 pub mod call_raw;
 pub use call_raw::*;
+
+mod batch;
+pub use batch::{BatchRequest, BatchResponse, DEFAULT_MAX_BATCH_SIZE};
@@ -19,6 +19,9 @@ mod txpool;
 #[cfg(not(feature = "celo"))]
 mod ws_errors;
 
+#[cfg(not(feature = "celo"))]
+mod ws_backfill;
+
 /// Spawns Anvil and instantiates an Http provider.
 pub fn spawn_anvil() -> (Provider<Http>, AnvilInstance) {
     let anvil = Anvil::new().block_time(1u64).spawn();
@@ -0,0 +1,117 @@
+use ethers_core::types::{Block, TxHash, H256};
+use ethers_providers::{Provider, StreamExt, SubscriptionEvent};
+use futures_util::SinkExt;
+use serde_json::{json, Value};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::{
+    accept_async,
+    tungstenite::{protocol::Message, Error},
+};
+
+/// Block `number` of the fork `fork`
+fn block(number: u64, fork: u64) -> Value {
+    let block = Block::<TxHash> {
+        number: Some(number.into()),
+        hash: Some(H256::from_low_u64_be(fork << 32 | number)),
+        ..Default::default()
+    };
+    serde_json::to_value(block).unwrap()
+}
+
+fn notification(number: u64, fork: u64) -> Message {
+    let notification = json!({
+        "jsonrpc": "2.0",
+        "method": "eth_subscription",
+        "params": { "subscription": "0x1", "result": block(number, fork) },
+    });
+    Message::Text(notification.to_string())
+}
+
+/// Serves block 2 and drops the first connection, while blocks 3 to 6 are produced. Blocks 5 and
+/// 6 are notified on the second connection, followed by a reorg of block 6.
+async fn handle_conn(stream: TcpStream, connection: usize) -> Result<(), Error> {
+    let mut ws_stream = accept_async(stream).await?;
+
+    while let Some(msg) = ws_stream.next().await {
+        let req: Value = serde_json::from_str(msg?.to_text()?).unwrap();
+        let result = match req["method"].as_str().unwrap() {
+            "eth_subscribe" => json!("0x1"),
+            "eth_blockNumber" if connection == 0 => json!("0x1"),
+            "eth_blockNumber" => json!("0x5"),
+            "eth_getBlockByNumber" => {
+                let number = req["params"][0].as_str().unwrap().trim_start_matches("0x");
+                block(u64::from_str_radix(number, 16).unwrap(), 0)
+            }
+            method => panic!("unexpected request {method}"),
+        };
+        let res = json!({ "jsonrpc": "2.0", "id": req["id"], "result": result });
+        ws_stream.send(Message::Text(res.to_string())).await?;
+
+        match req["method"].as_str().unwrap() {
+            "eth_blockNumber" if connection == 0 => {
+                ws_stream.send(notification(2, 0)).await?;
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                return ws_stream.close(None).await
+            }
+            "eth_subscribe" if connection == 1 => {
+                ws_stream.send(notification(5, 0)).await?;
+                ws_stream.send(notification(6, 0)).await?;
+                ws_stream.send(notification(6, 1)).await?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn backfills_blocks_after_reconnect() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let connection = connections.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(handle_conn(stream, connection));
+        }
+    });
+
+    let provider = Provider::connect_with_reconnects(endpoint, 1).await.unwrap();
+    let mut stream = provider.subscribe_blocks_with_backfill().await.unwrap();
+
+    let mut events = vec![];
+    for _ in 0..7 {
+        let event = tokio::time::timeout(Duration::from_secs(5), stream.next()).await.unwrap();
+        events.push(match event.unwrap() {
+            SubscriptionEvent::Notification(block) => {
+                let fork = block.hash.unwrap().to_low_u64_be() >> 32;
+                format!("notification {} fork {fork}", block.number.unwrap())
+            }
+            SubscriptionEvent::Backfilled(block) => format!("backfilled {}", block.number.unwrap()),
+            SubscriptionEvent::Reconnected(event) => {
+                assert_eq!(event.remaining_reconnects, 0);
+                "reconnected".to_string()
+            }
+        });
+    }
+    assert_eq!(
+        events,
+        [
+            "notification 2 fork 0",
+            "reconnected",
+            "backfilled 3",
+            "backfilled 4",
+            "backfilled 5",
+            "notification 6 fork 0",
+            "notification 6 fork 1"
+        ]
+    );
+}
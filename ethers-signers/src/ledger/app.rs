@@ -157,9 +157,11 @@ impl LedgerEthereum {
             };
 
             signature.v = match tx {
-                TypedTransaction::Eip2930(_) | TypedTransaction::Eip1559(_) => {
-                    (ecc_parity % 2 != 1) as u64
-                }
+                TypedTransaction::Eip2930(_) |
+                TypedTransaction::Eip1559(_) |
+                TypedTransaction::Eip4844(_) => (ecc_parity % 2 != 1) as u64,
+                #[cfg(feature = "optimism")]
+                TypedTransaction::DepositTransaction(_) => (ecc_parity % 2 != 1) as u64,
                 TypedTransaction::Legacy(_) => eip155_chain_id + ecc_parity,
             };
         }
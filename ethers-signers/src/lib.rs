@@ -39,10 +39,15 @@ mod aws;
 #[cfg(feature = "aws")]
 pub use aws::{AwsSigner, AwsSignerError};
 
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "remote")]
+pub use remote::{RemoteSigner, RemoteSignerError, RemoteSignerProtocol};
+
 use async_trait::async_trait;
 use ethers_core::types::{
     transaction::{eip2718::TypedTransaction, eip712::Eip712},
-    Address, Signature,
+    Address, Signature, UserOperation,
 };
 use std::error::Error;
 
@@ -51,6 +56,17 @@ pub fn to_eip155_v<T: Into<u8>>(recovery_id: T, chain_id: u64) -> u64 {
     (recovery_id.into() as u64) + 35 + chain_id * 2
 }
 
+/// Signs the ERC-4337 user operation hash for the given `EntryPoint` and the signer's chain id,
+/// prefixed as a personal message, as expected by the reference `SimpleAccount`. Accounts which
+/// validate signatures differently must sign [`UserOperation::hash`] themselves.
+pub async fn sign_user_operation<S: Signer>(
+    signer: &S,
+    user_op: &UserOperation,
+    entry_point: Address,
+) -> Result<Signature, S::Error> {
+    signer.sign_message(user_op.hash(entry_point, signer.chain_id())).await
+}
+
 /// Trait for signing transactions and messages
 ///
 /// Implement this trait to support different signing modes, e.g. Ledger, hosted etc.
@@ -0,0 +1,477 @@
+//! Signer delegating to an external signing daemon over JSON-RPC
+
+use super::Signer;
+use async_trait::async_trait;
+use ethers_core::{
+    types::{
+        transaction::{
+            eip2718::TypedTransaction,
+            eip712::{Eip712, TypedData},
+        },
+        Address, Bytes, Signature, SignatureError, H256,
+    },
+    utils::rlp::Rlp,
+};
+use reqwest::{Client, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use thiserror::Error;
+use tracing::{instrument, trace};
+
+/// The API spoken by the signing daemon of a [`RemoteSigner`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RemoteSignerProtocol {
+    /// The [Clef](https://geth.ethereum.org/docs/tools/clef/apis) external API:
+    /// `account_list`, `account_signTransaction`, `account_signData` and
+    /// `account_signTypedData`
+    #[default]
+    Clef,
+    /// The [Web3Signer](https://docs.web3signer.consensys.net/reference/api/json-rpc) Eth1 API:
+    /// `eth_accounts`, `eth_signTransaction`, `eth_sign` and `eth_signTypedData`
+    Web3Signer,
+}
+
+impl RemoteSignerProtocol {
+    fn accounts_method(&self) -> &'static str {
+        match self {
+            RemoteSignerProtocol::Clef => "account_list",
+            RemoteSignerProtocol::Web3Signer => "eth_accounts",
+        }
+    }
+
+    fn sign_transaction_method(&self) -> &'static str {
+        match self {
+            RemoteSignerProtocol::Clef => "account_signTransaction",
+            RemoteSignerProtocol::Web3Signer => "eth_signTransaction",
+        }
+    }
+
+    fn sign_typed_data_method(&self) -> &'static str {
+        match self {
+            RemoteSignerProtocol::Clef => "account_signTypedData",
+            RemoteSignerProtocol::Web3Signer => "eth_signTypedData",
+        }
+    }
+}
+
+/// The response of Clef to `account_signTransaction`
+#[derive(Deserialize)]
+struct ClefSignTxResponse {
+    raw: Bytes,
+}
+
+#[derive(Serialize)]
+struct Request<'a, T> {
+    id: u64,
+    jsonrpc: &'a str,
+    method: &'a str,
+    params: T,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<ErrorResponse>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    code: i64,
+    message: String,
+}
+
+/// An ethers Signer that delegates signing to an external daemon, e.g.
+/// [Clef](https://geth.ethereum.org/docs/tools/clef/introduction) or
+/// [Web3Signer](https://docs.web3signer.consensys.net), over JSON-RPC, so that the keys are held
+/// by a separate process.
+///
+/// The signatures returned by the daemon are checked against the signer's address before they
+/// are returned. Since the daemon needs the whole EIP-712 payload rather than its hash,
+/// [`Signer::sign_typed_data`] is not supported: use [`RemoteSigner::sign_typed_data_json`]
+/// instead.
+///
+/// ```no_run
+/// use ethers_signers::{RemoteSigner, RemoteSignerProtocol, Signer};
+///
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// // uses the first account of the daemon
+/// let signer = RemoteSigner::connect("http://localhost:8550".parse()?, RemoteSignerProtocol::Clef)
+///     .await?
+///     .with_chain_id(1u64);
+/// let sig = signer.sign_message("hello").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RemoteSigner {
+    client: Client,
+    url: Url,
+    protocol: RemoteSignerProtocol,
+    address: Address,
+    chain_id: u64,
+    next_id: AtomicU64,
+}
+
+impl Clone for RemoteSigner {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            url: self.url.clone(),
+            protocol: self.protocol,
+            address: self.address,
+            chain_id: self.chain_id,
+            next_id: AtomicU64::new(self.next_id.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Errors thrown by a [`RemoteSigner`]
+#[derive(Error, Debug)]
+pub enum RemoteSignerError {
+    /// Thrown if the daemon could not be reached
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    /// The JSON-RPC error returned by the daemon, e.g. if the request was rejected
+    #[error("(code: {code}, message: {message})")]
+    JsonRpc {
+        /// The error code
+        code: i64,
+        /// The error message
+        message: String,
+    },
+    /// (De)Serialization error
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+    /// Thrown if the daemon returned an invalid signature
+    #[error(transparent)]
+    Signature(#[from] SignatureError),
+    /// Thrown if the daemon signed a different transaction than the requested one
+    #[error("the remote signer signed a different transaction")]
+    TransactionMismatch,
+    /// Thrown if the daemon has no account
+    #[error("the remote signer has no account")]
+    NoAccounts,
+    /// Thrown by [`Signer::sign_typed_data`], see [`RemoteSigner::sign_typed_data_json`]
+    #[error(
+        "signing EIP-712 payloads requires their JSON representation, use `sign_typed_data_json`"
+    )]
+    TypedDataUnsupported,
+    /// Thrown if the EIP-712 payload could not be hashed
+    #[error("{0}")]
+    Eip712(String),
+    /// Thrown if the response could not be decoded
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
+}
+
+impl RemoteSigner {
+    /// Creates a new `RemoteSigner` for the account `address` of the daemon at `url`, with chain
+    /// id 1.
+    pub fn new(url: Url, protocol: RemoteSignerProtocol, address: Address) -> Self {
+        Self {
+            client: Client::new(),
+            url,
+            protocol,
+            address,
+            chain_id: 1,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Creates a new `RemoteSigner` for the first account of the daemon at `url`, with chain id
+    /// 1.
+    pub async fn connect(
+        url: Url,
+        protocol: RemoteSignerProtocol,
+    ) -> Result<Self, RemoteSignerError> {
+        let mut signer = Self::new(url, protocol, Address::zero());
+        signer.address =
+            signer.accounts().await?.into_iter().next().ok_or(RemoteSignerError::NoAccounts)?;
+        Ok(signer)
+    }
+
+    /// Sets the HTTP client used to reach the daemon, e.g. to configure authentication headers
+    /// or TLS client certificates
+    #[must_use]
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Returns the accounts managed by the daemon
+    pub async fn accounts(&self) -> Result<Vec<Address>, RemoteSignerError> {
+        self.request(self.protocol.accounts_method(), [(); 0]).await
+    }
+
+    /// Signs the EIP-712 `payload` with the daemon
+    #[instrument(err, skip(payload))]
+    pub async fn sign_typed_data_json(
+        &self,
+        payload: &TypedData,
+    ) -> Result<Signature, RemoteSignerError> {
+        let hash =
+            payload.encode_eip712().map_err(|err| RemoteSignerError::Eip712(err.to_string()))?;
+        let sig: Bytes =
+            self.request(self.protocol.sign_typed_data_method(), (self.address, payload)).await?;
+        self.checked_signature(&sig, H256::from(hash))
+    }
+
+    /// Parses a 65 bytes signature, and checks that it was produced by the signer's address
+    fn checked_signature(&self, sig: &[u8], hash: H256) -> Result<Signature, RemoteSignerError> {
+        let mut sig = Signature::try_from(sig)?;
+        // some signers use 0/1 rather than the 'Electrum' notation
+        if sig.v < 27 {
+            sig.v += 27;
+        }
+        sig.verify(hash, self.address)?;
+        Ok(sig)
+    }
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, RemoteSignerError>
+    where
+        T: Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = Request { id, jsonrpc: "2.0", method, params };
+        trace!(id, method, "sending request to remote signer");
+
+        let res: Response =
+            self.client.post(self.url.clone()).json(&request).send().await?.json().await?;
+        match res {
+            Response { error: Some(err), .. } => {
+                Err(RemoteSignerError::JsonRpc { code: err.code, message: err.message })
+            }
+            Response { result: Some(result), .. } => Ok(serde_json::from_value(result)?),
+            _ => Err(RemoteSignerError::InvalidResponse("missing result".to_string())),
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Signer for RemoteSigner {
+    type Error = RemoteSignerError;
+
+    #[instrument(err, skip(message))]
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        let message = message.as_ref();
+        // both APIs sign the EIP-191 prefixed message
+        let data = Bytes::from(message.to_vec());
+        let sig: Bytes = match self.protocol {
+            RemoteSignerProtocol::Clef => {
+                self.request("account_signData", ("text/plain", self.address, data)).await?
+            }
+            RemoteSignerProtocol::Web3Signer => {
+                self.request("eth_sign", (self.address, data)).await?
+            }
+        };
+        self.checked_signature(&sig, ethers_core::utils::hash_message(message))
+    }
+
+    #[instrument(err)]
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        let mut tx = tx.clone();
+        tx.set_from(self.address);
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.chain_id);
+        }
+
+        // the chain id of legacy transactions is not serialized, and must be sent explicitly so
+        // that the daemon does not sign for its own default chain
+        let mut request = serde_json::to_value(&tx)?;
+        request["chainId"] = serde_json::to_value(tx.chain_id())?;
+
+        let method = self.protocol.sign_transaction_method();
+        let raw = match self.protocol {
+            RemoteSignerProtocol::Clef => {
+                self.request::<_, ClefSignTxResponse>(method, [&request]).await?.raw
+            }
+            RemoteSignerProtocol::Web3Signer => {
+                self.request::<_, Bytes>(method, [&request]).await?
+            }
+        };
+
+        let (signed, sig) = TypedTransaction::decode_signed(&Rlp::new(&raw))
+            .map_err(|err| RemoteSignerError::InvalidResponse(err.to_string()))?;
+        if signed.sighash() != tx.sighash() {
+            return Err(RemoteSignerError::TransactionMismatch)
+        }
+        let recovered = sig.recover(tx.sighash())?;
+        if recovered != self.address {
+            return Err(SignatureError::VerificationError(self.address, recovered).into())
+        }
+        Ok(sig)
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        _payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        Err(RemoteSignerError::TypedDataUnsupported)
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn with_chain_id<T: Into<u64>>(mut self, chain_id: T) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+}
+
+impl FromStr for RemoteSignerProtocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "clef" => Ok(RemoteSignerProtocol::Clef),
+            "web3signer" => Ok(RemoteSignerProtocol::Web3Signer),
+            _ => Err(format!("unknown remote signer protocol: {s}")),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::LocalWallet;
+    use ethers_core::types::TransactionRequest;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    /// A stand-in signing daemon, answering JSON-RPC requests over HTTP with a local wallet
+    async fn spawn_daemon(wallet: LocalWallet, protocol: RemoteSignerProtocol) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap()).parse().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let wallet = wallet.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    let mut len = 0;
+                    loop {
+                        let mut line = String::new();
+                        stream.read_line(&mut line).await.unwrap();
+                        if line == "\r\n" {
+                            break
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                len = value.trim().parse().unwrap();
+                            }
+                        }
+                    }
+                    let mut body = vec![0; len];
+                    stream.read_exact(&mut body).await.unwrap();
+                    let req: Value = serde_json::from_slice(&body).unwrap();
+
+                    let result = answer(&wallet, protocol, &req).await;
+                    let body =
+                        serde_json::json!({ "jsonrpc": "2.0", "id": req["id"], "result": result })
+                            .to_string();
+                    let res = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                    stream.write_all(res.as_bytes()).await.unwrap();
+                });
+            }
+        });
+        url
+    }
+
+    async fn answer(wallet: &LocalWallet, protocol: RemoteSignerProtocol, req: &Value) -> Value {
+        let params = &req["params"];
+        match req["method"].as_str().unwrap() {
+            "account_list" | "eth_accounts" => serde_json::json!([wallet.address()]),
+            "account_signTransaction" | "eth_signTransaction" => {
+                let tx: TypedTransaction = serde_json::from_value(params[0].clone()).unwrap();
+                let sig = wallet.sign_transaction(&tx).await.unwrap();
+                let raw = tx.rlp_signed(&sig);
+                match protocol {
+                    RemoteSignerProtocol::Clef => serde_json::json!({ "raw": raw, "tx": tx }),
+                    RemoteSignerProtocol::Web3Signer => serde_json::json!(raw),
+                }
+            }
+            method => {
+                let data: Bytes = match method {
+                    "account_signData" => serde_json::from_value(params[2].clone()).unwrap(),
+                    "eth_sign" => serde_json::from_value(params[1].clone()).unwrap(),
+                    _ => panic!("unexpected method {method}"),
+                };
+                let sig = wallet.sign_message(&data).await.unwrap();
+                serde_json::json!(Bytes::from(<[u8; 65]>::from(sig).to_vec()))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn signs_with_remote_daemon() {
+        for protocol in [RemoteSignerProtocol::Clef, RemoteSignerProtocol::Web3Signer] {
+            let wallet = LocalWallet::new(&mut rand::thread_rng()).with_chain_id(5u64);
+            let url = spawn_daemon(wallet.clone(), protocol).await;
+            let signer = RemoteSigner::connect(url, protocol).await.unwrap().with_chain_id(5u64);
+            assert_eq!(signer.address(), wallet.address());
+
+            let sig = signer.sign_message("hello").await.unwrap();
+            assert_eq!(sig, wallet.sign_message("hello").await.unwrap());
+
+            let tx: TypedTransaction = TransactionRequest::new()
+                .to(Address::random())
+                .value(100)
+                .gas(21_000)
+                .gas_price(1)
+                .nonce(3)
+                .into();
+            let sig = signer.sign_transaction(&tx).await.unwrap();
+            let mut expected = tx.clone();
+            expected.set_chain_id(5u64);
+            assert_eq!(sig, wallet.sign_transaction(&expected).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_the_chain_id_of_legacy_transactions() {
+        for protocol in [RemoteSignerProtocol::Clef, RemoteSignerProtocol::Web3Signer] {
+            // the daemon signs for another chain by default
+            let wallet = LocalWallet::new(&mut rand::thread_rng()).with_chain_id(1u64);
+            let url = spawn_daemon(wallet.clone(), protocol).await;
+            let signer = RemoteSigner::connect(url, protocol).await.unwrap().with_chain_id(5u64);
+
+            let tx: TypedTransaction =
+                TransactionRequest::new().to(Address::random()).gas(21_000).gas_price(1).into();
+            let sig = signer.sign_transaction(&tx).await.unwrap();
+            let mut expected = tx.clone();
+            expected.set_chain_id(5u64);
+            assert_eq!(sig, wallet.sign_transaction(&expected).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_signatures_from_other_accounts() {
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let url = spawn_daemon(wallet, RemoteSignerProtocol::Clef).await;
+        let signer = RemoteSigner::new(url, RemoteSignerProtocol::Clef, Address::random());
+
+        let err = signer.sign_message("hello").await.unwrap_err();
+        assert!(matches!(err, RemoteSignerError::Signature(_)), "{err}");
+    }
+}
@@ -183,6 +183,7 @@ impl TrezorEthereum {
                 transaction.data,
                 chain_id,
             )?,
+            TypedTransaction::Eip4844(_) => return Err(TrezorError::NoBlobTransactionSupport),
             TypedTransaction::Eip1559(eip1559_tx) => client.ethereum_sign_eip1559_tx(
                 arr_path,
                 transaction.nonce,
@@ -195,6 +196,10 @@ impl TrezorEthereum {
                 transaction.max_priority_fee_per_gas,
                 transaction.access_list,
             )?,
+            #[cfg(feature = "optimism")]
+            TypedTransaction::DepositTransaction(_) => {
+                return Err(TrezorError::NoDepositTransactionSupport)
+            }
         };
 
         Ok(Signature { r: signature.r, s: signature.s, v: signature.v })
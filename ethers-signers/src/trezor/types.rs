@@ -51,6 +51,10 @@ pub enum TrezorError {
     UnsupportedFirmwareVersion(String),
     #[error("Does not support ENS.")]
     NoENSSupport,
+    #[error("Does not support EIP-4844 blob transactions.")]
+    NoBlobTransactionSupport,
+    #[error("Does not support OP-stack deposit transactions.")]
+    NoDepositTransactionSupport,
     #[error("Unable to access trezor cached session.")]
     CacheError(String),
 }
@@ -92,6 +96,7 @@ impl TrezorTransaction {
         let data = tx.data().map_or(vec![], |v| v.to_vec());
 
         match tx {
+            TypedTransaction::Eip4844(_) => Err(TrezorError::NoBlobTransactionSupport),
             TypedTransaction::Eip2930(_) | TypedTransaction::Legacy(_) => Ok(Self {
                 nonce,
                 gas,
@@ -135,6 +140,10 @@ impl TrezorTransaction {
                     access_list,
                 })
             }
+            #[cfg(feature = "optimism")]
+            TypedTransaction::DepositTransaction(_) => {
+                Err(TrezorError::NoDepositTransactionSupport)
+            }
         }
     }
 }
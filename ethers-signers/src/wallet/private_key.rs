@@ -227,6 +227,21 @@ mod tests {
         assert_eq!(recovered2, address);
     }
 
+    #[tokio::test]
+    async fn signs_user_operation() {
+        use crate::sign_user_operation;
+        use ethers_core::types::{UserOperation, ENTRY_POINT_V06_ADDRESS};
+
+        let key = Wallet::<SigningKey>::new(&mut rand::thread_rng()).with_chain_id(5u64);
+        let user_op = UserOperation::new(Address::random()).nonce(1).call_data(vec![0x01]);
+
+        let signature = sign_user_operation(&key, &user_op, ENTRY_POINT_V06_ADDRESS).await.unwrap();
+
+        // the account recovers the signer from the prefixed user operation hash
+        let hash = user_op.hash(ENTRY_POINT_V06_ADDRESS, 5);
+        assert_eq!(signature.recover(hash.as_bytes()).unwrap(), key.address);
+    }
+
     #[tokio::test]
     #[cfg(not(feature = "celo"))]
     async fn signs_tx() {